@@ -0,0 +1,190 @@
+use crate::crc32_reader;
+use crate::sfv::parse_sfv_bytes;
+use crate::verify::{classify, entry_components, VerifyResult};
+use flate2::read::MultiGzDecoder;
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, Read};
+use std::path::Path;
+use zip::result::ZipError;
+use zip::ZipArchive;
+
+fn zip_error(err: ZipError) -> io::Error {
+    match err {
+        ZipError::Io(e) => e,
+        ZipError::FileNotFound => io::Error::new(io::ErrorKind::NotFound, err),
+        other => io::Error::new(io::ErrorKind::InvalidData, other),
+    }
+}
+
+/// Verify the `.sfv` manifest stored inside a ZIP archive against the other
+/// members of the same archive, without extracting anything to disk.
+///
+/// The first `.sfv` member found is used. Its filenames are resolved relative
+/// to the manifest's directory within the archive; entries that are not in
+/// the archive are reported as `Missing`.
+pub fn verify_sfv_in_zip(zip_path: &Path) -> io::Result<Vec<VerifyResult>> {
+    let file = File::open(zip_path)?;
+    let mut archive = ZipArchive::new(BufReader::new(file)).map_err(zip_error)?;
+
+    let sfv_name = archive
+        .file_names()
+        .find(|name| name.to_ascii_lowercase().ends_with(".sfv"))
+        .map(str::to_owned)
+        .ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::NotFound,
+                format!("no .sfv file in {}", zip_path.display()),
+            )
+        })?;
+
+    let mut manifest = Vec::new();
+    archive
+        .by_name(&sfv_name)
+        .map_err(zip_error)?
+        .read_to_end(&mut manifest)?;
+    let entries = parse_sfv_bytes(&manifest)?;
+
+    // ZIP member names always use '/', whatever wrote the manifest.
+    let prefix = match sfv_name.rfind('/') {
+        Some(pos) => &sfv_name[..=pos],
+        None => "",
+    };
+
+    Ok(entries
+        .into_iter()
+        .map(|entry| {
+            let relative: Vec<&str> = entry_components(&entry.filename).collect();
+            let member = format!("{}{}", prefix, relative.join("/"));
+            let hashed = archive
+                .by_name(&member)
+                .map_err(zip_error)
+                .and_then(crc32_reader);
+            classify(entry.filename, entry.crc, hashed)
+        })
+        .collect())
+}
+
+/// CRC32 of the decompressed contents of a gzip file, for manifests that
+/// list the uncompressed file while only the `.gz` is on disk.
+///
+/// Concatenated gzip members are decompressed one after another, as
+/// `gunzip` does. A file that does not start with the gzip magic bytes fails
+/// with [`io::ErrorKind::InvalidData`] instead of being hashed as is, and a
+/// truncated or corrupt stream fails the same way as a read error.
+pub fn crc32_gz_path(path: &Path) -> io::Result<u32> {
+    let mut reader = BufReader::new(crate::hash::open_file(path)?);
+    if !reader.fill_buf()?.starts_with(&[0x1f, 0x8b]) {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("{} is not gzip-compressed", path.display()),
+        ));
+    }
+    crc32_reader(MultiGzDecoder::new(reader))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::TempDir;
+    use flate2::write::GzEncoder;
+    use flate2::Compression;
+    use std::io::Write;
+    use zip::write::SimpleFileOptions;
+    use zip::ZipWriter;
+
+    fn write_zip(path: &Path, members: &[(&str, &[u8])]) {
+        let mut writer = ZipWriter::new(File::create(path).unwrap());
+        for (name, data) in members {
+            writer
+                .start_file(*name, SimpleFileOptions::default())
+                .unwrap();
+            writer.write_all(data).unwrap();
+        }
+        writer.finish().unwrap();
+    }
+
+    #[test]
+    fn test_verify_sfv_in_zip() {
+        let dir = TempDir::new("zip");
+        let zip_path = dir.path().join("release.zip");
+        write_zip(
+            &zip_path,
+            &[
+                (
+                    "release/release.sfv",
+                    b"good.txt CBF43926\nsub\\bad.txt CBF43926\nabsent.txt 00000000\n",
+                ),
+                ("release/good.txt", b"123456789"),
+                ("release/sub/bad.txt", b"Hello, World!"),
+            ],
+        );
+
+        let results = verify_sfv_in_zip(&zip_path).unwrap();
+        assert_eq!(
+            results,
+            vec![
+                VerifyResult::Ok {
+                    filename: "good.txt".to_string(),
+                    crc: 0xCBF43926,
+                },
+                VerifyResult::Mismatch {
+                    filename: "sub\\bad.txt".to_string(),
+                    expected: 0xCBF43926,
+                    actual: 0xEC4AC3D0,
+                },
+                VerifyResult::Missing {
+                    filename: "absent.txt".to_string(),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_verify_sfv_in_zip_without_manifest() {
+        let dir = TempDir::new("zip_nosfv");
+        let zip_path = dir.path().join("plain.zip");
+        write_zip(&zip_path, &[("a.txt", b"a")]);
+
+        let err = verify_sfv_in_zip(&zip_path).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::NotFound);
+    }
+
+    fn gzip(data: &[u8]) -> Vec<u8> {
+        let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(data).unwrap();
+        encoder.finish().unwrap()
+    }
+
+    #[test]
+    fn test_crc32_gz_path() {
+        let dir = TempDir::new("gz");
+        let path = dir.write("check.txt.gz", &gzip(b"123456789"));
+        assert_eq!(crc32_gz_path(&path).unwrap(), 0xCBF43926);
+
+        let mut members = gzip(b"12345");
+        members.extend(gzip(b"6789"));
+        let path = dir.write("members.txt.gz", &members);
+        assert_eq!(crc32_gz_path(&path).unwrap(), 0xCBF43926);
+    }
+
+    #[test]
+    fn test_crc32_gz_path_rejects_bad_input() {
+        let dir = TempDir::new("gz_bad");
+        for (name, data) in [
+            ("plain.txt", b"123456789".to_vec()),
+            ("empty.gz", Vec::new()),
+            ("truncated.gz", gzip(b"123456789")[..20].to_vec()),
+        ] {
+            let err = crc32_gz_path(&dir.write(name, &data)).unwrap_err();
+            assert!(
+                matches!(
+                    err.kind(),
+                    io::ErrorKind::InvalidData | io::ErrorKind::UnexpectedEof
+                ),
+                "{name}: {err}"
+            );
+        }
+        let err = crc32_gz_path(&dir.write("plain.bin", b"abc")).unwrap_err();
+        assert!(err.to_string().contains("not gzip-compressed"), "{err}");
+    }
+}
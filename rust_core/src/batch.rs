@@ -0,0 +1,183 @@
+use crate::{crc32_path_buffered_with_len, hash_buffer_size};
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::{mpsc, Mutex};
+use std::thread;
+
+/// Number of worker threads used by the batch helpers when the caller does
+/// not ask for a specific count.
+pub fn default_workers() -> usize {
+    thread::available_parallelism()
+        .map(|n| n.get())
+        .unwrap_or(1)
+}
+
+/// Compute CRC32 checksums for many files in parallel.
+///
+/// Results are returned in the same order as `paths`, regardless of which
+/// worker finished first.
+pub fn crc32_batch(paths: &[PathBuf]) -> Vec<std::io::Result<u32>> {
+    crc32_batch_with_len(paths)
+        .into_iter()
+        .map(|result| result.map(|(crc, _)| crc))
+        .collect()
+}
+
+/// Like [`crc32_batch`], with each file's length as counted while hashing
+/// it; see [`crate::crc32_with_len`].
+pub fn crc32_batch_with_len(paths: &[PathBuf]) -> Vec<std::io::Result<(u32, u64)>> {
+    let workers = default_workers().min(paths.len()).max(1);
+    let next = AtomicUsize::new(0);
+    let results = Mutex::new((0..paths.len()).map(|_| None).collect::<Vec<_>>());
+
+    thread::scope(|scope| {
+        for _ in 0..workers {
+            scope.spawn(|| {
+                // One read buffer per worker, reused for every file it hashes.
+                let mut buffer = vec![0u8; hash_buffer_size()];
+                loop {
+                    let index = next.fetch_add(1, Ordering::Relaxed);
+                    let Some(path) = paths.get(index) else {
+                        break;
+                    };
+                    let result = crc32_path_buffered_with_len(path, &mut buffer);
+                    results.lock().unwrap()[index] = Some(result);
+                }
+            });
+        }
+    });
+
+    results
+        .into_inner()
+        .unwrap()
+        .into_iter()
+        .map(|r| r.expect("every path is hashed by exactly one worker"))
+        .collect()
+}
+
+/// Run `job` on every item with up to `workers` threads, handing each result
+/// to `on_result` on the calling thread as soon as it is ready.
+///
+/// Results arrive in completion order, tagged with the item's index; with a
+/// single worker that is the order of `items`, and no thread is spawned.
+/// Once `on_result` returns `false` no further items are started, and the
+/// results of items already in progress are dropped.
+pub fn for_each_parallel<T, R>(
+    items: &[T],
+    workers: usize,
+    job: impl Fn(&T) -> R + Sync,
+    mut on_result: impl FnMut(usize, R) -> bool,
+) where
+    T: Sync,
+    R: Send,
+{
+    let workers = workers.min(items.len()).max(1);
+    if workers == 1 {
+        for (index, item) in items.iter().enumerate() {
+            if !on_result(index, job(item)) {
+                break;
+            }
+        }
+        return;
+    }
+
+    let next = AtomicUsize::new(0);
+    let stop = AtomicBool::new(false);
+    let (sender, receiver) = mpsc::channel();
+    thread::scope(|scope| {
+        for _ in 0..workers {
+            let sender = sender.clone();
+            let (job, next, stop) = (&job, &next, &stop);
+            scope.spawn(move || {
+                while !stop.load(Ordering::Relaxed) {
+                    let index = next.fetch_add(1, Ordering::Relaxed);
+                    let Some(item) = items.get(index) else {
+                        break;
+                    };
+                    if sender.send((index, job(item))).is_err() {
+                        break;
+                    }
+                }
+            });
+        }
+        drop(sender);
+        for (index, result) in receiver {
+            if !on_result(index, result) {
+                stop.store(true, Ordering::Relaxed);
+                break;
+            }
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::TempDir;
+
+    #[test]
+    fn test_crc32_batch_preserves_order() {
+        let dir = TempDir::new("batch");
+        let a = dir.write("a.txt", b"123456789");
+        let b = dir.write("b.txt", b"Hello, World!");
+        let missing = dir.path().join("missing.txt");
+
+        let results = crc32_batch(&[a, missing, b]);
+        assert_eq!(results.len(), 3);
+        assert_eq!(*results[0].as_ref().unwrap(), 0xCBF43926);
+        assert!(results[1].is_err());
+        assert_eq!(*results[2].as_ref().unwrap(), 0xEC4AC3D0);
+    }
+
+    #[test]
+    fn test_for_each_parallel_visits_every_item() {
+        let items: Vec<u32> = (0..100).collect();
+        for workers in [1, 4] {
+            let mut seen = vec![None; items.len()];
+            for_each_parallel(
+                &items,
+                workers,
+                |n| n * 2,
+                |index, doubled| {
+                    seen[index] = Some(doubled);
+                    true
+                },
+            );
+            let expected: Vec<_> = items.iter().map(|n| Some(n * 2)).collect();
+            assert_eq!(seen, expected);
+        }
+    }
+
+    #[test]
+    fn test_for_each_parallel_stops_when_asked() {
+        let items: Vec<u32> = (0..100).collect();
+        let mut seen = Vec::new();
+        for_each_parallel(
+            &items,
+            1,
+            |n| *n,
+            |index, _| {
+                seen.push(index);
+                index < 2
+            },
+        );
+        assert_eq!(seen, [0, 1, 2]);
+
+        let mut received = 0;
+        for_each_parallel(
+            &items,
+            4,
+            |n| *n,
+            |_, _| {
+                received += 1;
+                false
+            },
+        );
+        assert_eq!(received, 1);
+    }
+
+    #[test]
+    fn test_crc32_batch_empty() {
+        assert!(crc32_batch(&[]).is_empty());
+    }
+}
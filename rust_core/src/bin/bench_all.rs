@@ -0,0 +1,65 @@
+//! Throughput of every hash algorithm over a range of in-memory buffer sizes.
+//!
+//! Buffers are generated in memory so the numbers reflect CPU cost only, not
+//! disk speed. Run with
+//! `cargo run --release --no-default-features --features std --bin bench_all`
+//! (the feature flags skip building the GUI).
+
+use mtsfv_core::{hash_reader, HashAlgo};
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+
+const SIZES: [usize; 4] = [4 << 10, 64 << 10, 1 << 20, 16 << 20];
+
+/// Keep hashing a buffer until at least this much time has passed, so small
+/// sizes are not dominated by timer resolution.
+const MIN_DURATION: Duration = Duration::from_millis(300);
+
+fn label(size: usize) -> String {
+    if size >= 1 << 20 {
+        format!("{} MiB", size >> 20)
+    } else {
+        format!("{} KiB", size >> 10)
+    }
+}
+
+/// Deterministic, non-constant test data.
+fn make_buffer(size: usize) -> Vec<u8> {
+    let mut state = 0x2545_F491_u32;
+    (0..size)
+        .map(|_| {
+            state ^= state << 13;
+            state ^= state >> 17;
+            state ^= state << 5;
+            state as u8
+        })
+        .collect()
+}
+
+fn mb_per_sec(algo: HashAlgo, buffer: &[u8]) -> f64 {
+    let start = Instant::now();
+    let mut bytes = 0u64;
+    while start.elapsed() < MIN_DURATION {
+        black_box(hash_reader(black_box(buffer), algo).expect("reading a slice cannot fail"));
+        bytes += buffer.len() as u64;
+    }
+    bytes as f64 / (1024.0 * 1024.0) / start.elapsed().as_secs_f64()
+}
+
+fn main() {
+    let buffers: Vec<Vec<u8>> = SIZES.iter().map(|&size| make_buffer(size)).collect();
+
+    print!("{:<10}", "MB/s");
+    for &size in &SIZES {
+        print!("{:>12}", label(size));
+    }
+    println!();
+
+    for algo in HashAlgo::ALL {
+        print!("{:<10}", algo.name());
+        for buffer in &buffers {
+            print!("{:>12.1}", mb_per_sec(algo, buffer));
+        }
+        println!();
+    }
+}
@@ -0,0 +1,140 @@
+//! CRC32 throughput of a large cached file for a range of read buffer sizes.
+//!
+//! The file is written to the temp directory and read once before timing,
+//! so the numbers reflect the read loop rather than the disk. Each size is
+//! also timed with the buffer deliberately misaligned by one byte, and
+//! compared with the CRC of the same data already in memory: the ceiling no
+//! read loop can beat. A size well below it is held back by reading, not by
+//! the CRC. Run with
+//! `cargo run --release --no-default-features --features std --bin bench_buffer`
+//! (the feature flags skip building the GUI).
+
+use mtsfv_core::crc::crc32_update;
+use mtsfv_core::HASH_BUFFER_SIZE;
+use std::fs::{self, File};
+use std::hint::black_box;
+use std::io::{self, IsTerminal, Read};
+use std::path::Path;
+use std::time::Instant;
+
+const FILE_SIZE: usize = 256 << 20;
+
+const SIZES: [usize; 6] = [16 << 10, 64 << 10, 128 << 10, 256 << 10, 1 << 20, 4 << 20];
+
+/// Passes over the file per measurement; the best one is reported.
+const PASSES: usize = 5;
+
+/// Share of the in-memory ceiling above which reading is not the bottleneck.
+const CPU_BOUND_PERCENT: f64 = 80.0;
+
+/// Share below which reading clearly is.
+const IO_BOUND_PERCENT: f64 = 50.0;
+
+fn label(size: usize) -> String {
+    if size >= 1 << 20 {
+        format!("{} MiB", size >> 20)
+    } else {
+        format!("{} KiB", size >> 10)
+    }
+}
+
+fn crc_file(path: &Path, buffer: &mut [u8]) -> io::Result<u32> {
+    let mut file = File::open(path)?;
+    let mut crc = 0;
+    loop {
+        let read = file.read(buffer)?;
+        if read == 0 {
+            return Ok(crc);
+        }
+        crc = crc32_update(crc, &buffer[..read]);
+    }
+}
+
+fn mb_per_sec(bytes: usize, start: Instant) -> f64 {
+    bytes as f64 / (1024.0 * 1024.0) / start.elapsed().as_secs_f64()
+}
+
+/// Best CRC throughput over `data` with no reading at all.
+fn ceiling_mb_per_sec(data: &[u8]) -> f64 {
+    (0..PASSES)
+        .map(|_| {
+            let start = Instant::now();
+            black_box(crc32_update(0, black_box(data)));
+            mb_per_sec(data.len(), start)
+        })
+        .fold(0.0, f64::max)
+}
+
+/// `percent` right-aligned in `width` columns, green when reading keeps up
+/// with the CRC, yellow in between and red when reading holds it back.
+/// Plain when `color` is off.
+fn percent_cell(percent: f64, width: usize, color: bool) -> String {
+    let cell = format!("{:>width$}", format!("{:.0}%", percent));
+    if !color {
+        return cell;
+    }
+    let code = if percent >= CPU_BOUND_PERCENT {
+        32
+    } else if percent >= IO_BOUND_PERCENT {
+        33
+    } else {
+        31
+    };
+    format!("\x1b[{}m{}\x1b[0m", code, cell)
+}
+
+fn best_mb_per_sec(path: &Path, buffer: &mut [u8]) -> f64 {
+    (0..PASSES)
+        .map(|_| {
+            let start = Instant::now();
+            black_box(crc_file(path, buffer).expect("read benchmark file"));
+            mb_per_sec(FILE_SIZE, start)
+        })
+        .fold(0.0, f64::max)
+}
+
+fn main() -> io::Result<()> {
+    let path = std::env::temp_dir().join(format!("mtsfv_bench_{}.bin", std::process::id()));
+    let data: Vec<u8> = (0..FILE_SIZE).map(|i| (i * 7 % 251) as u8).collect();
+    fs::write(&path, &data)?;
+    let ceiling = ceiling_mb_per_sec(&data);
+    drop(data);
+    crc_file(&path, &mut vec![0u8; 1 << 20])?;
+    let color = io::stdout().is_terminal() && std::env::var_os("NO_COLOR").is_none();
+
+    println!(
+        "{:<10}{:>12}{:>12}{:>12}",
+        "buffer", "aligned", "offset +1", "of ceiling"
+    );
+    let mut best = 0.0f64;
+    for size in SIZES {
+        // Over-allocate so both an aligned and an odd start fit.
+        let mut storage = vec![0u8; size + 64];
+        let skip = storage.as_ptr().align_offset(64);
+        let aligned = best_mb_per_sec(&path, &mut storage[skip..skip + size]);
+        let misaligned = best_mb_per_sec(&path, &mut storage[skip + 1..skip + 1 + size]);
+        let marker = if size == HASH_BUFFER_SIZE { " *" } else { "" };
+        let percent = aligned / ceiling * 100.0;
+        best = best.max(percent);
+        println!(
+            "{:<10}{:>12.1}{:>12.1}{}{}",
+            label(size),
+            aligned,
+            misaligned,
+            percent_cell(percent, 12, color),
+            marker
+        );
+    }
+    println!("MB/s, best of {PASSES}; * marks HASH_BUFFER_SIZE");
+    println!("In-memory CRC32 ceiling: {:.1} MB/s", ceiling);
+    if best >= CPU_BOUND_PERCENT {
+        println!("CPU-bound: reads keep up with the CRC, so faster storage won't help");
+    } else {
+        println!(
+            "I/O-bound: reads reach at most {:.0}% of the CRC's speed, so storage is the limit",
+            best
+        );
+    }
+
+    fs::remove_file(&path)
+}
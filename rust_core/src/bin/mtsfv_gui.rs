@@ -1,81 +1,1459 @@
 use eframe::{egui, App};
-use mtsfv_core::crc32_path;
+use mtsfv_core::{
+    append_to_sfv, batch, detect_checksum_format, find_sfv, format_size, format_utc,
+    hash_file_strict_with_progress, parse_hashsum, parse_sfv, parse_sfv_bytes, read_sfv,
+    relative_path, safe_entry_path, session::algo_name, walk_files_with_depth, ChecksumFormat,
+    Digest, HashAlgo, HashError, Session, SessionEntry, SessionState, SfvEntry, SfvManifest,
+};
+use notify::event::{EventKind, ModifyKind};
+use notify::{RecursiveMode, Watcher};
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::fs;
+use std::io;
 use std::path::{Path, PathBuf};
+use std::process::Command;
 use std::sync::mpsc;
 use std::thread;
+use std::time::{Duration, Instant, SystemTime};
 
 #[derive(Debug)]
 struct FileEntry {
+    id: u64,
     path: PathBuf,
+    /// Digest listed in the checksum file when the entry came from "Verify
+    /// SFV..." (which also reads `.md5`, `.sha1` and `.sha256` files), or a
+    /// CRC32 from the clipboard.
+    expected: Option<Digest>,
+    /// The checksum file's algorithm when `expected` is set.
+    algo: HashAlgo,
     state: EntryState,
+    /// Filled in by the worker alongside the digest; `None` until then or if
+    /// the metadata could not be read.
+    meta: Option<FileMeta>,
 }
 
+#[derive(Debug, Clone, Copy)]
+struct FileMeta {
+    size: u64,
+    modified: Option<SystemTime>,
+}
+
+impl FileMeta {
+    fn read(path: &Path) -> Option<Self> {
+        let metadata = fs::metadata(path).ok()?;
+        Some(FileMeta {
+            size: metadata.len(),
+            modified: metadata.modified().ok(),
+        })
+    }
+}
+
+/// Messages from worker threads, tagged with the entry id and the number of
+/// the job that hashed it.
+enum WorkerMessage {
+    /// Sent at most every `HEARTBEAT_INTERVAL` while hashing.
+    Progress {
+        id: u64,
+        job: u64,
+        bytes_done: u64,
+    },
+    Done(WorkerReport),
+}
+
+/// What a worker thread sends back for one entry.
+struct WorkerReport {
+    id: u64,
+    job: u64,
+    digest: io::Result<Digest>,
+    meta: Option<FileMeta>,
+}
+
+/// How often a worker reports that it is still making progress.
+const HEARTBEAT_INTERVAL: Duration = Duration::from_millis(500);
+
+/// A pending entry with no heartbeat for this long is shown as stalled.
+const STALL_AFTER: Duration = Duration::from_secs(10);
+
+/// How often the window redraws by itself while files are being hashed, to
+/// pick up results and progress. With nothing running it only redraws on
+/// input.
+const REPAINT_INTERVAL: Duration = Duration::from_millis(250);
+
+/// With watching on, a changed file is only hashed again once it has gone
+/// this long without further changes, so a file still being written is not
+/// re-queued on every chunk.
+const WATCH_DEBOUNCE: Duration = Duration::from_secs(1);
+
 #[derive(Debug)]
 enum EntryState {
+    /// Waiting for a free worker, or for the queue to be resumed.
+    Queued,
+    /// Being hashed by a worker. `job` tells its reports apart from those
+    /// of an earlier job for the same row, which are dropped when the row
+    /// is hashed again before that job finishes.
+    Pending {
+        job: u64,
+        bytes_done: u64,
+        last_heartbeat: Instant,
+    },
+    Missing,
+    Done(Result<Digest, String>),
+}
+
+/// Verification outcome of a row, derived from its state and expected digest.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum RowStatus {
     Pending,
-    Done(Result<u32, String>),
+    Ok,
+    Mismatch,
+    Missing,
+    Error,
+}
+
+impl EntryState {
+    fn pending(job: u64) -> Self {
+        EntryState::Pending {
+            job,
+            bytes_done: 0,
+            last_heartbeat: Instant::now(),
+        }
+    }
+
+    /// Whether the entry has no result yet, queued or being hashed.
+    fn is_pending(&self) -> bool {
+        matches!(self, EntryState::Queued | EntryState::Pending { .. })
+    }
+
+    fn is_running(&self) -> bool {
+        matches!(self, EntryState::Pending { .. })
+    }
+
+    /// Whether the entry is being hashed by job number `job`.
+    fn is_running_job(&self, job: u64) -> bool {
+        matches!(self, EntryState::Pending { job: running, .. } if *running == job)
+    }
+
+    fn is_stalled(&self) -> bool {
+        matches!(self, EntryState::Pending { last_heartbeat, .. }
+            if last_heartbeat.elapsed() >= STALL_AFTER)
+    }
+}
+
+impl FileEntry {
+    fn status(&self) -> RowStatus {
+        match (&self.state, &self.expected) {
+            (EntryState::Queued | EntryState::Pending { .. }, _) => RowStatus::Pending,
+            (EntryState::Missing, _) => RowStatus::Missing,
+            (EntryState::Done(Err(_)), _) => RowStatus::Error,
+            (EntryState::Done(Ok(digest)), Some(expected)) if digest != expected => {
+                RowStatus::Mismatch
+            }
+            (EntryState::Done(Ok(_)), _) => RowStatus::Ok,
+        }
+    }
+
+    /// The Status column text. Every state has its own wording, so rows
+    /// never rely on their tint alone.
+    fn status_text(&self, paused: bool) -> String {
+        match &self.state {
+            EntryState::Queued if paused => "Paused".to_string(),
+            EntryState::Queued => "Queued".to_string(),
+            EntryState::Pending { .. } if self.state.is_stalled() => {
+                "Stalled (network?)".to_string()
+            }
+            EntryState::Pending { bytes_done: 0, .. } => "Calculating...".to_string(),
+            EntryState::Pending { bytes_done, .. } => {
+                format!("Calculating... {}", format_size(*bytes_done))
+            }
+            EntryState::Missing => "MISSING".to_string(),
+            EntryState::Done(Ok(digest)) => match &self.expected {
+                Some(expected) if digest != expected => {
+                    format!("MISMATCH (expected {})", self.algo.format(expected))
+                }
+                _ => "OK".to_string(),
+            },
+            EntryState::Done(Err(err)) => err.clone(),
+        }
+    }
 }
 
-fn compute_crc_for_display(path: &Path) -> Result<u32, String> {
-    crc32_path(path).map_err(|e| format!("{}: {}", path.display(), e))
+impl RowStatus {
+    fn is_failure(self) -> bool {
+        matches!(self, RowStatus::Mismatch | RowStatus::Missing | RowStatus::Error)
+    }
+
+    /// Subtle background tint for the row, chosen to suit the current theme.
+    fn row_color(self, dark_mode: bool) -> Option<egui::Color32> {
+        let (light, dark) = match self {
+            RowStatus::Pending => return None,
+            RowStatus::Ok => ((220, 244, 220), (28, 56, 32)),
+            RowStatus::Mismatch | RowStatus::Error => ((250, 222, 222), (72, 32, 32)),
+            RowStatus::Missing => ((250, 236, 204), (72, 58, 24)),
+        };
+        let (r, g, b) = if dark_mode { dark } else { light };
+        Some(egui::Color32::from_rgb(r, g, b))
+    }
+}
+
+/// What the compare banner says about the two selected rows.
+#[derive(Debug, PartialEq, Eq)]
+enum Comparison {
+    /// At least one of the two is still queued or being hashed.
+    Hashing,
+    Identical(HashAlgo),
+    Different(HashAlgo),
+    /// Both were hashed, but with different algorithms.
+    AlgosDiffer,
+    /// One of the files could not be hashed; the text says why.
+    Unavailable(String),
+}
+
+impl Comparison {
+    fn of(a: &FileEntry, b: &FileEntry) -> Self {
+        match (&a.state, &b.state) {
+            (EntryState::Done(Ok(_)), EntryState::Done(Ok(_))) if a.algo != b.algo => {
+                Comparison::AlgosDiffer
+            }
+            (EntryState::Done(Ok(x)), EntryState::Done(Ok(y))) if x == y => {
+                Comparison::Identical(a.algo)
+            }
+            (EntryState::Done(Ok(_)), EntryState::Done(Ok(_))) => Comparison::Different(a.algo),
+            _ if a.state.is_pending() || b.state.is_pending() => Comparison::Hashing,
+            _ => {
+                let failed = if matches!(a.state, EntryState::Done(Ok(_))) {
+                    b
+                } else {
+                    a
+                };
+                Comparison::Unavailable(match &failed.state {
+                    EntryState::Missing => format!("{} is missing", failed.path.display()),
+                    _ => failed.status_text(false),
+                })
+            }
+        }
+    }
+}
+
+/// Preferences remembered between launches via eframe's storage.
+///
+/// The window size is persisted by eframe itself (`persist_window`).
+#[derive(Debug, serde::Serialize, serde::Deserialize)]
+#[serde(default)]
+struct Settings {
+    last_dir: Option<PathBuf>,
+    theme: ThemePreference,
+    /// Add folder contents without asking for confirmation first.
+    always_recurse: bool,
+    /// How many directory levels below an added folder to include.
+    max_depth: usize,
+    /// Algorithm for newly added files. Manifests are checked with their
+    /// own algorithm: CRC32 for `.sfv`, MD5, SHA-1 or SHA-256 for
+    /// `md5sum`-style files.
+    #[serde(with = "algo_name")]
+    algo: HashAlgo,
+    /// Hash listed files again when they change on disk.
+    watch_files: bool,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            last_dir: None,
+            theme: ThemePreference::default(),
+            always_recurse: false,
+            max_depth: 8,
+            algo: HashAlgo::Crc32,
+            watch_files: false,
+        }
+    }
+}
+
+/// The algorithms offered in the header, in menu order.
+const GUI_ALGOS: [HashAlgo; 3] = [HashAlgo::Crc32, HashAlgo::Md5, HashAlgo::Sha256];
+
+/// Column heading and menu label for `algo`.
+fn algo_label(algo: HashAlgo) -> &'static str {
+    match algo {
+        HashAlgo::Crc32 => "CRC32",
+        HashAlgo::Crc32c => "CRC32C",
+        HashAlgo::Adler32 => "Adler-32",
+        HashAlgo::Md5 => "MD5",
+        HashAlgo::Sha1 => "SHA-1",
+        HashAlgo::Sha256 => "SHA-256",
+    }
+}
+
+/// A folder whose files are waiting for the user to confirm adding them.
+struct PendingFolder {
+    root: PathBuf,
+    files: Vec<PathBuf>,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+enum ThemePreference {
+    #[default]
+    System,
+    Light,
+    Dark,
+}
+
+impl ThemePreference {
+    fn next(self) -> Self {
+        match self {
+            ThemePreference::System => ThemePreference::Light,
+            ThemePreference::Light => ThemePreference::Dark,
+            ThemePreference::Dark => ThemePreference::System,
+        }
+    }
+
+    fn label(self) -> &'static str {
+        match self {
+            ThemePreference::System => "System",
+            ThemePreference::Light => "Light",
+            ThemePreference::Dark => "Dark",
+        }
+    }
 }
 
 struct MtsfvGui {
     entries: Vec<FileEntry>,
     status: String,
-    tx: mpsc::Sender<(PathBuf, Result<u32, String>)>,
-    rx: mpsc::Receiver<(PathBuf, Result<u32, String>)>,
+    tx: mpsc::Sender<WorkerMessage>,
+    rx: mpsc::Receiver<WorkerMessage>,
+    settings: Settings,
+    next_id: u64,
+    /// Number of the last hashing job handed to a worker.
+    next_job: u64,
+    selected: HashSet<u64>,
+    /// Display-only filters; they never touch `entries` or in-flight work.
+    filter_text: String,
+    only_failed: bool,
+    only_missing: bool,
+    /// Folders awaiting confirmation; the first one is being asked about.
+    pending_folders: Vec<PendingFolder>,
+    /// Existing file "Save as SFV..." is asking whether to replace.
+    pending_overwrite: Option<PathBuf>,
+    /// Row whose filename had keyboard focus last frame, for the summary.
+    focused_row: Option<u64>,
+    /// Ids of `Queued` entries in the order they will be hashed. Ids of rows
+    /// since removed or re-queued are skipped when they come up.
+    queue: VecDeque<u64>,
+    /// Worker threads still hashing, including any for removed rows.
+    running: usize,
+    max_workers: usize,
+    /// Stop starting queued entries; running ones still finish.
+    paused: bool,
+    /// Present while `settings.watch_files` is on; dropping it stops watching.
+    watcher: Option<notify::RecommendedWatcher>,
+    /// Folders `watcher` has been asked to watch, absolute.
+    watched_dirs: HashSet<PathBuf>,
+    watch_tx: mpsc::Sender<PathBuf>,
+    watch_rx: mpsc::Receiver<PathBuf>,
+    /// Changed files, absolute, and when each last changed, until they have
+    /// settled for `WATCH_DEBOUNCE`.
+    changed: HashMap<PathBuf, Instant>,
 }
 
 impl MtsfvGui {
+    fn new(cc: &eframe::CreationContext<'_>) -> Self {
+        let settings = cc
+            .storage
+            .and_then(|storage| eframe::get_value(storage, eframe::APP_KEY))
+            .unwrap_or_default();
+        Self {
+            settings,
+            ..Default::default()
+        }
+    }
+
     fn add_files(&mut self) {
-        if let Some(files) = rfd::FileDialog::new()
-            .set_title("Select files to verify")
-            .pick_files()
-        {
-            for path in files {
-                let worker_tx = self.tx.clone();
-                let worker_path = path.clone();
-                self.entries.push(FileEntry {
-                    path,
-                    state: EntryState::Pending,
+        let mut dialog = rfd::FileDialog::new().set_title("Select files to verify");
+        if let Some(dir) = &self.settings.last_dir {
+            dialog = dialog.set_directory(dir);
+        }
+        if let Some(files) = dialog.pick_files() {
+            if let Some(parent) = files.first().and_then(|p| p.parent()) {
+                self.settings.last_dir = Some(parent.to_path_buf());
+            }
+            self.add_paths(files);
+        }
+    }
+
+    fn add_folder(&mut self) {
+        let mut dialog = rfd::FileDialog::new().set_title("Select a folder to verify");
+        if let Some(dir) = &self.settings.last_dir {
+            dialog = dialog.set_directory(dir);
+        }
+        if let Some(folder) = dialog.pick_folder() {
+            self.settings.last_dir = Some(folder.clone());
+            self.add_paths(vec![folder]);
+        }
+    }
+
+    /// Queue files directly; folders are expanded, after confirmation unless
+    /// the user chose to always recurse.
+    fn add_paths(&mut self, paths: Vec<PathBuf>) {
+        for path in paths {
+            if !path.is_dir() {
+                self.enqueue(path, None);
+                continue;
+            }
+            match self.list_folder(&path) {
+                Ok(files) if self.settings.always_recurse => {
+                    for file in files {
+                        self.enqueue(file, None);
+                    }
+                }
+                Ok(files) => self.pending_folders.push(PendingFolder { root: path, files }),
+                Err(e) => self.status = format!("Failed to read {}: {}", path.display(), e),
+            }
+        }
+    }
+
+    fn list_folder(&self, root: &Path) -> io::Result<Vec<PathBuf>> {
+        let files = walk_files_with_depth(root, self.settings.max_depth)?;
+        Ok(files.into_iter().map(|file| root.join(file)).collect())
+    }
+
+    /// Add a row for `path` and queue it for hashing, with the algorithm of
+    /// the expected digest when there is one to compare against and the
+    /// selected algorithm otherwise.
+    fn enqueue(&mut self, path: PathBuf, expected: Option<(HashAlgo, Digest)>) {
+        let verifying = expected.is_some();
+        let (algo, expected) = match expected {
+            Some((algo, digest)) => (algo, Some(digest)),
+            None => (self.settings.algo, None),
+        };
+        self.next_id += 1;
+        self.entries.push(FileEntry {
+            id: self.next_id,
+            path,
+            expected,
+            algo,
+            state: EntryState::Queued,
+            meta: None,
+        });
+        self.queue_job(self.next_id);
+        self.status = if verifying {
+            "Verifying...".to_string()
+        } else {
+            "Calculating...".to_string()
+        };
+    }
+
+    /// Ask whether to add the files of the first pending folder.
+    fn show_folder_prompt(&mut self, ctx: &egui::Context) {
+        let Some(folder) = self.pending_folders.first() else {
+            return;
+        };
+        let mut add = false;
+        let mut cancel = false;
+        let mut depth = self.settings.max_depth;
+
+        egui::Window::new("Add folder")
+            .collapsible(false)
+            .resizable(false)
+            .anchor(egui::Align2::CENTER_CENTER, egui::Vec2::ZERO)
+            .show(ctx, |ui| {
+                ui.label(format!(
+                    "Add {} files from {}?",
+                    folder.files.len(),
+                    folder.root.display()
+                ));
+                ui.horizontal(|ui| {
+                    ui.label("Depth limit:");
+                    ui.add(egui::DragValue::new(&mut depth).range(0..=64));
+                });
+                ui.checkbox(
+                    &mut self.settings.always_recurse,
+                    "Always add folder contents without asking",
+                );
+                ui.horizontal(|ui| {
+                    add = ui.button("Add").clicked();
+                    cancel = ui.button("Cancel").clicked();
+                });
+            });
+
+        if depth != self.settings.max_depth {
+            self.settings.max_depth = depth;
+            let root = self.pending_folders[0].root.clone();
+            match self.list_folder(&root) {
+                Ok(files) => self.pending_folders[0].files = files,
+                Err(e) => self.status = format!("Failed to read {}: {}", root.display(), e),
+            }
+        }
+        if add {
+            let folder = self.pending_folders.remove(0);
+            for file in folder.files {
+                self.enqueue(file, None);
+            }
+        } else if cancel {
+            self.pending_folders.remove(0);
+        }
+    }
+
+    /// Add whatever was dropped onto the window.
+    fn handle_dropped_files(&mut self, ctx: &egui::Context) {
+        let dropped: Vec<PathBuf> = ctx.input(|i| {
+            i.raw
+                .dropped_files
+                .iter()
+                .filter_map(|file| file.path.clone())
+                .collect()
+        });
+        if !dropped.is_empty() {
+            self.add_paths(dropped);
+        }
+    }
+
+    /// Load an `.sfv` manifest or `md5sum`/`sha1sum`/`sha256sum` output and
+    /// queue each listed file for verification with the file's algorithm.
+    fn verify_sfv(&mut self) {
+        let mut dialog = rfd::FileDialog::new()
+            .set_title("Select a checksum file to verify")
+            .add_filter("Checksum files", &["sfv", "md5", "sha1", "sha256"])
+            .add_filter("All files", &["*"]);
+        if let Some(dir) = &self.settings.last_dir {
+            dialog = dialog.set_directory(dir);
+        }
+        let Some(path) = dialog.pick_file() else {
+            return;
+        };
+        let base_dir = path.parent().map(PathBuf::from).unwrap_or_default();
+        self.settings.last_dir = Some(base_dir.clone());
+
+        let bytes = match fs::read(&path) {
+            Ok(bytes) => bytes,
+            Err(e) => {
+                self.status = format!("Failed to read {}: {}", path.display(), e);
+                return;
+            }
+        };
+        let text = String::from_utf8_lossy(&bytes);
+        match detect_checksum_format(&path, &text) {
+            Some(ChecksumFormat::Sfv) => match parse_sfv_bytes(&bytes) {
+                Ok(entries) => self.enqueue_manifest(&base_dir, entries),
+                Err(e) => self.status = format!("Failed to parse {}: {}", path.display(), e),
+            },
+            Some(ChecksumFormat::Hashsum(algo)) => match parse_hashsum(&text) {
+                Ok(entries) => {
+                    // parse_hashsum only accepts whole bytes of hex.
+                    let entries = entries.into_iter().map(|entry| {
+                        let digest = Digest(parse_hex(&entry.digest).unwrap_or_default());
+                        (entry.filename, digest)
+                    });
+                    self.enqueue_checks(&base_dir, algo, entries);
+                }
+                Err(e) => self.status = format!("Failed to parse {}: {}", path.display(), e),
+            },
+            None => {
+                self.status = format!(
+                    "Failed to parse {}: not an SFV, MD5, SHA-1 or SHA-256 checksum file",
+                    path.display()
+                );
+            }
+        }
+    }
+
+    /// Pick a folder and verify the one `.sfv` manifest inside it.
+    fn verify_folder(&mut self) {
+        let mut dialog = rfd::FileDialog::new().set_title("Select a folder containing an SFV file");
+        if let Some(dir) = &self.settings.last_dir {
+            dialog = dialog.set_directory(dir);
+        }
+        let Some(dir) = dialog.pick_folder() else {
+            return;
+        };
+        self.settings.last_dir = Some(dir.clone());
+
+        let entries = match find_sfv(&dir).and_then(|sfv| read_sfv(&sfv)) {
+            Ok(entries) => entries,
+            Err(e) => {
+                self.status = format!("Cannot verify {}: {}", dir.display(), e);
+                return;
+            }
+        };
+        self.enqueue_manifest(&dir, entries);
+    }
+
+    /// Queue each manifest entry for verification, resolving its filename
+    /// against `base_dir`.
+    fn enqueue_manifest(&mut self, base_dir: &Path, entries: Vec<SfvEntry>) {
+        let entries = entries
+            .into_iter()
+            .map(|entry| (entry.filename, Digest::from_u32(entry.crc)));
+        self.enqueue_checks(base_dir, HashAlgo::Crc32, entries);
+    }
+
+    /// Queue each `(filename, digest)` of a checksum file made with `algo`,
+    /// resolving the filename against `base_dir`.
+    fn enqueue_checks(
+        &mut self,
+        base_dir: &Path,
+        algo: HashAlgo,
+        entries: impl IntoIterator<Item = (String, Digest)>,
+    ) {
+        for (filename, digest) in entries {
+            match safe_entry_path(base_dir, &filename) {
+                Ok(path) => self.enqueue(path, Some((algo, digest))),
+                // Listed but never opened: the manifest may not be trusted.
+                Err(reason) => {
+                    self.next_id += 1;
+                    self.entries.push(FileEntry {
+                        id: self.next_id,
+                        path: PathBuf::from(&filename),
+                        expected: Some(digest),
+                        algo,
+                        state: EntryState::Done(Err(format!("Rejected: {}", reason))),
+                        meta: None,
+                    });
+                }
+            }
+        }
+    }
+
+    /// Verify against clipboard text: either a bare CRC32, compared with the
+    /// one selected row, or SFV lines whose files are looked up in a folder
+    /// the user picks.
+    fn verify_clipboard(&mut self) {
+        let text = match arboard::Clipboard::new().and_then(|mut c| c.get_text()) {
+            Ok(text) => text,
+            Err(e) => {
+                self.status = format!("Could not read the clipboard: {}", e);
+                return;
+            }
+        };
+
+        if let Some(crc) = parse_bare_crc(&text) {
+            let mut selected = (0..self.entries.len())
+                .filter(|&i| self.selected.contains(&self.entries[i].id));
+            match (selected.next(), selected.next()) {
+                (Some(index), None) => {
+                    self.entries[index].expected = Some(Digest::from_u32(crc));
+                    if self.entries[index].algo != HashAlgo::Crc32 {
+                        self.rehash(index, HashAlgo::Crc32);
+                    }
+                    self.status = format!(
+                        "Comparing {} against {:08X} from the clipboard",
+                        self.entries[index].path.display(),
+                        crc
+                    );
+                }
+                _ => {
+                    self.status = format!(
+                        "Select one file to compare against {:08X} from the clipboard",
+                        crc
+                    );
+                }
+            }
+            return;
+        }
+
+        let entries = match parse_sfv(&text) {
+            Ok(entries) if !entries.is_empty() => entries,
+            Ok(_) => {
+                self.status = "The clipboard has no SFV lines or CRC32 to check".to_string();
+                return;
+            }
+            Err(e) => {
+                self.status = format!(
+                    "The clipboard is not SFV lines or a CRC32 ({}); expected \
+                     '<filename> <crc32>' lines or 8 hex digits",
+                    e
+                );
+                return;
+            }
+        };
+        let mut dialog =
+            rfd::FileDialog::new().set_title("Select the folder with the files to verify");
+        if let Some(dir) = &self.settings.last_dir {
+            dialog = dialog.set_directory(dir);
+        }
+        let Some(base_dir) = dialog.pick_folder() else {
+            return;
+        };
+        self.settings.last_dir = Some(base_dir.clone());
+        self.enqueue_manifest(&base_dir, entries);
+    }
+
+    /// Pick two files and select them, so the compare banner reports
+    /// whether they are identical once both are hashed.
+    fn compare_files(&mut self) {
+        let mut dialog = rfd::FileDialog::new().set_title("Select two files to compare");
+        if let Some(dir) = &self.settings.last_dir {
+            dialog = dialog.set_directory(dir);
+        }
+        let Some(files) = dialog.pick_files() else {
+            return;
+        };
+        if files.len() != 2 {
+            self.status = format!("Select exactly two files to compare, not {}", files.len());
+            return;
+        }
+        if let Some(parent) = files[0].parent() {
+            self.settings.last_dir = Some(parent.to_path_buf());
+        }
+        self.selected.clear();
+        for file in files {
+            self.enqueue(file, None);
+            self.selected.insert(self.next_id);
+        }
+        self.status = "Comparing...".to_string();
+    }
+
+    /// The indices of the two selected rows, when exactly two are selected.
+    fn compared_pair(&self) -> Option<(usize, usize)> {
+        let mut selected = (0..self.entries.len())
+            .filter(|&i| self.selected.contains(&self.entries[i].id));
+        match (selected.next(), selected.next(), selected.next()) {
+            (Some(a), Some(b), None) => Some((a, b)),
+            _ => None,
+        }
+    }
+
+    /// Hash rows `a` and `b` again with SHA-256, so a CRC32 match can be
+    /// confirmed by a hash that is not practical to collide.
+    fn confirm_with_sha256(&mut self, a: usize, b: usize) {
+        for index in [a, b] {
+            if self.entries[index].algo != HashAlgo::Sha256 {
+                self.rehash(index, HashAlgo::Sha256);
+            }
+        }
+        self.status = "Comparing with SHA-256...".to_string();
+    }
+
+    /// Show whether the two selected files are identical, above the list.
+    fn show_compare_banner(&mut self, ctx: &egui::Context) {
+        let Some((a, b)) = self.compared_pair() else {
+            return;
+        };
+        let (first, second) = (&self.entries[a], &self.entries[b]);
+        let comparison = Comparison::of(first, second);
+        let digest = |entry: &FileEntry| match &entry.state {
+            EntryState::Done(Ok(digest)) => entry.algo.format(digest),
+            _ => String::new(),
+        };
+        let (verdict, detail, tint) = match &comparison {
+            Comparison::Hashing => ("Comparing...", String::new(), RowStatus::Pending),
+            Comparison::Identical(algo) => (
+                "IDENTICAL",
+                format!("Both have {} {}", algo_label(*algo), digest(first)),
+                RowStatus::Ok,
+            ),
+            Comparison::Different(algo) => (
+                "DIFFERENT",
+                format!(
+                    "{} {} vs {}",
+                    algo_label(*algo),
+                    digest(first),
+                    digest(second)
+                ),
+                RowStatus::Mismatch,
+            ),
+            Comparison::AlgosDiffer => (
+                "Not compared",
+                format!(
+                    "Hashed with {} and {}",
+                    algo_label(first.algo),
+                    algo_label(second.algo)
+                ),
+                RowStatus::Pending,
+            ),
+            Comparison::Unavailable(reason) => ("Not compared", reason.clone(), RowStatus::Error),
+        };
+        // Rows checked against a manifest CRC stay on CRC32.
+        let can_confirm = first.expected.is_none()
+            && second.expected.is_none()
+            && match comparison {
+                Comparison::Identical(algo) => algo != HashAlgo::Sha256,
+                Comparison::AlgosDiffer => true,
+                _ => false,
+            };
+
+        let mut confirm = false;
+        egui::TopBottomPanel::top("compare").show(ctx, |ui| {
+            let fill = tint.row_color(ui.visuals().dark_mode).unwrap_or_default();
+            egui::Frame::none()
+                .fill(fill)
+                .inner_margin(8.0)
+                .show(ui, |ui| {
+                    ui.horizontal(|ui| {
+                        ui.label(egui::RichText::new(verdict).size(24.0).strong());
+                        ui.label(detail);
+                        if can_confirm
+                            && ui
+                                .button("Confirm with SHA-256")
+                                .on_hover_text(
+                                    "A CRC32 match is very likely but not proof; hash both \
+                                     files again with SHA-256",
+                                )
+                                .clicked()
+                        {
+                            confirm = true;
+                        }
+                    });
                 });
+        });
+        if confirm {
+            self.confirm_with_sha256(a, b);
+        }
+    }
+
+    /// Put the `Queued` entry `id` at the back of the queue.
+    fn queue_job(&mut self, id: u64) {
+        self.queue.push_back(id);
+        self.dispatch();
+    }
+
+    /// Start queued entries while fewer than `max_workers` are hashing,
+    /// unless the queue is paused.
+    fn dispatch(&mut self) {
+        while !self.paused && self.running < self.max_workers {
+            let Some(id) = self.queue.pop_front() else {
+                break;
+            };
+            let Some(entry) = self
+                .entries
+                .iter_mut()
+                .find(|e| e.id == id && matches!(e.state, EntryState::Queued))
+            else {
+                continue;
+            };
+            self.next_job += 1;
+            entry.state = EntryState::pending(self.next_job);
+            let (path, algo) = (entry.path.clone(), entry.algo);
+            self.spawn_worker(id, self.next_job, path, algo);
+            self.running += 1;
+        }
+    }
+
+    fn toggle_pause(&mut self) {
+        self.paused = !self.paused;
+        self.status = if self.paused {
+            format!(
+                "Paused; {} file(s) being hashed will finish",
+                self.entries.iter().filter(|e| e.state.is_running()).count()
+            )
+        } else {
+            "Resumed".to_string()
+        };
+        self.dispatch();
+    }
+
+    /// Hash `path` with `algo` and read its metadata on a background thread,
+    /// and report back under `id` and `job`. A file whose size changes while it is read,
+    /// such as a log being written, fails rather than getting a digest of
+    /// whatever was read.
+    fn spawn_worker(&self, id: u64, job: u64, path: PathBuf, algo: HashAlgo) {
+        let worker_tx = self.tx.clone();
+        thread::spawn(move || {
+            let mut last_sent = Instant::now();
+            let digest = hash_file_strict_with_progress(&path, algo, |bytes_done| {
+                if last_sent.elapsed() >= HEARTBEAT_INTERVAL {
+                    last_sent = Instant::now();
+                    let _ = worker_tx.send(WorkerMessage::Progress {
+                        id,
+                        job,
+                        bytes_done,
+                    });
+                }
+            });
+            let report = WorkerReport {
+                id,
+                job,
+                digest,
+                meta: FileMeta::read(&path),
+            };
+            if let Err(err) = worker_tx.send(WorkerMessage::Done(report)) {
+                eprintln!("Failed to send {algo} result for {}: {err}", path.display());
+            }
+        });
+    }
+
+    /// Hash row `index` again from scratch with `algo`. A worker still
+    /// hashing it runs to the end, but its report is stale and ignored.
+    fn rehash(&mut self, index: usize, algo: HashAlgo) {
+        let entry = &mut self.entries[index];
+        entry.algo = algo;
+        entry.state = EntryState::Queued;
+        entry.meta = None;
+        let id = entry.id;
+        self.queue_job(id);
+    }
+
+    /// Re-queue every entry that failed, was missing or mismatched, leaving
+    /// OK rows alone.
+    fn retry_failed(&mut self) {
+        let mut retried = 0;
+        for i in 0..self.entries.len() {
+            if self.entries[i].status().is_failure() {
+                self.rehash(i, self.entries[i].algo);
+                retried += 1;
+            }
+        }
+        if retried > 0 {
+            self.status = format!("Retrying {retried} file(s)...");
+        }
+    }
+
+    /// Hash again, with the selected algorithm, every finished row that used
+    /// another one. Rows checked against a manifest CRC stay on CRC32.
+    fn recompute(&mut self) {
+        let algo = self.settings.algo;
+        let mut recomputed = 0;
+        for i in 0..self.entries.len() {
+            let entry = &self.entries[i];
+            if entry.algo != algo && entry.expected.is_none() && !entry.state.is_pending() {
+                self.rehash(i, algo);
+                recomputed += 1;
+            }
+        }
+        self.status = if recomputed > 0 {
+            format!("Recomputing {recomputed} file(s) with {}...", algo_label(algo))
+        } else {
+            format!("Every file already has a {}", algo_label(algo))
+        };
+    }
 
-                thread::spawn(move || {
-                    let result = compute_crc_for_display(&worker_path);
-                    let path_for_send = worker_path.clone();
-                    if let Err(err) = worker_tx.send((path_for_send, result)) {
-                        eprintln!(
-                            "Failed to send CRC result for {}: {err}",
-                            worker_path.display()
-                        );
+    /// Copy every CRC32 entry to the clipboard as SFV lines. Rows without a
+    /// CRC are listed as trailing comments so nothing is silently dropped.
+    fn copy_as_sfv(&mut self, ctx: &egui::Context) {
+        let manifest = self.sfv_manifest(None);
+        ctx.copy_text(manifest.to_string());
+        self.status = format!(
+            "Copied {} CRC(s), {} skipped",
+            manifest.entries.len(),
+            manifest.trailing_comments.len()
+        );
+    }
+
+    /// The rows as a manifest: CRC32 results as entries, the rest as
+    /// trailing comments. Filenames are relative to `base_dir` where
+    /// possible, and bare file names without one.
+    fn sfv_manifest(&self, base_dir: Option<&Path>) -> SfvManifest {
+        let mut manifest = SfvManifest {
+            header_comments: vec![format!(" Generated by MTSFV {}", env!("CARGO_PKG_VERSION"))],
+            ..Default::default()
+        };
+        for entry in &self.entries {
+            let filename = base_dir
+                .and_then(|dir| relative_path(&entry.path, dir))
+                .as_deref()
+                .or(entry.path.file_name().map(Path::new))
+                .unwrap_or(&entry.path)
+                .to_string_lossy()
+                .into_owned();
+            match &entry.state {
+                EntryState::Done(Ok(digest)) => match digest.to_u32() {
+                    Some(crc) if entry.algo == HashAlgo::Crc32 => {
+                        manifest.entries.push(SfvEntry {
+                            filename,
+                            crc,
+                            comments: Vec::new(),
+                            size: None,
+                        })
                     }
+                    _ => manifest.trailing_comments.push(format!(
+                        " {filename}: {} {}",
+                        algo_label(entry.algo),
+                        entry.algo.format(digest)
+                    )),
+                },
+                EntryState::Queued | EntryState::Pending { .. } => manifest
+                    .trailing_comments
+                    .push(format!(" {filename}: pending")),
+                EntryState::Missing => manifest
+                    .trailing_comments
+                    .push(format!(" {filename}: missing")),
+                EntryState::Done(Err(_)) => manifest
+                    .trailing_comments
+                    .push(format!(" {filename}: error")),
+            }
+        }
+        manifest
+    }
+
+    /// Write the CRC32 results to an `.sfv` file, with filenames relative to
+    /// its folder. An existing file is not replaced without asking.
+    fn save_as_sfv(&mut self) {
+        let mut dialog = rfd::FileDialog::new()
+            .set_title("Save as SFV")
+            .add_filter("SFV files", &["sfv"])
+            .set_file_name("checksums.sfv");
+        if let Some(dir) = &self.settings.last_dir {
+            dialog = dialog.set_directory(dir);
+        }
+        let Some(path) = dialog.save_file() else {
+            return;
+        };
+        if path.exists() {
+            self.pending_overwrite = Some(path);
+        } else {
+            self.write_sfv_file(&path, false);
+        }
+    }
+
+    /// Write the rows to the manifest at `path`, merging them into the one
+    /// already there when `append` is set.
+    fn write_sfv_file(&mut self, path: &Path, append: bool) {
+        let base_dir = path.parent().unwrap_or(Path::new(""));
+        let mut manifest = self.sfv_manifest(Some(base_dir));
+        if append {
+            match SfvManifest::read(path) {
+                Ok(existing) => manifest = append_to_sfv(&existing, manifest.entries),
+                Err(e) => {
+                    self.status = format!("Failed to read {}: {}", path.display(), e);
+                    return;
+                }
+            }
+        }
+        self.status = match manifest.write(path) {
+            Ok(()) => format!(
+                "Saved {} CRC(s) to {}",
+                manifest.entries.len(),
+                path.display()
+            ),
+            Err(e) => format!("Failed to save {}: {}", path.display(), e),
+        };
+    }
+
+    /// Ask whether "Save as SFV..." may replace or add to an existing file.
+    fn show_overwrite_prompt(&mut self, ctx: &egui::Context) {
+        let Some(path) = &self.pending_overwrite else {
+            return;
+        };
+        let (mut replace, mut append, mut cancel) = (false, false, false);
+
+        egui::Window::new("File exists")
+            .collapsible(false)
+            .resizable(false)
+            .anchor(egui::Align2::CENTER_CENTER, egui::Vec2::ZERO)
+            .show(ctx, |ui| {
+                ui.label(format!("{} already exists.", path.display()));
+                ui.label("Appending updates the CRCs of listed files and keeps the rest.");
+                ui.horizontal(|ui| {
+                    replace = ui.button("Replace").clicked();
+                    append = ui.button("Append").clicked();
+                    cancel = ui.button("Cancel").clicked();
                 });
+            });
+
+        if replace || append || cancel {
+            let path = self.pending_overwrite.take().unwrap();
+            if !cancel {
+                self.write_sfv_file(&path, append);
             }
-            self.status = "Calculating...".to_string();
         }
     }
 
+    /// Write the rows and their results to a JSON session file.
+    fn save_session(&mut self) {
+        let mut dialog = rfd::FileDialog::new()
+            .set_title("Save session")
+            .add_filter("MTSFV session", &["json"])
+            .set_file_name("session.json");
+        if let Some(dir) = &self.settings.last_dir {
+            dialog = dialog.set_directory(dir);
+        }
+        let Some(path) = dialog.save_file() else {
+            return;
+        };
+
+        let session = Session {
+            entries: self
+                .entries
+                .iter()
+                .map(|entry| SessionEntry {
+                    path: entry.path.clone(),
+                    expected: entry.expected.as_ref().and_then(Digest::to_u32),
+                    expected_digest: entry
+                        .expected
+                        .as_ref()
+                        .filter(|expected| expected.to_u32().is_none())
+                        .map(Digest::to_hex_lower),
+                    algo: entry.algo,
+                    state: match &entry.state {
+                        EntryState::Queued | EntryState::Pending { .. } => {
+                            SessionState::Pending
+                        }
+                        EntryState::Missing => SessionState::Missing,
+                        EntryState::Done(Ok(digest)) => match digest.to_u32() {
+                            Some(crc) => SessionState::Done { crc },
+                            None => SessionState::Hashed {
+                                digest: digest.to_hex_lower(),
+                            },
+                        },
+                        EntryState::Done(Err(message)) => SessionState::Error {
+                            message: message.clone(),
+                        },
+                    },
+                })
+                .collect(),
+        };
+        let written = serde_json::to_string_pretty(&session)
+            .map_err(io::Error::from)
+            .and_then(|json| fs::write(&path, json));
+        self.status = match written {
+            Ok(()) => format!("Saved {} files to {}", session.entries.len(), path.display()),
+            Err(e) => format!("Failed to save {}: {}", path.display(), e),
+        };
+    }
+
+    /// Replace the rows with those of a saved session, hashing again any
+    /// that were still pending when it was saved.
+    fn open_session(&mut self) {
+        let mut dialog = rfd::FileDialog::new()
+            .set_title("Open session")
+            .add_filter("MTSFV session", &["json"]);
+        if let Some(dir) = &self.settings.last_dir {
+            dialog = dialog.set_directory(dir);
+        }
+        let Some(path) = dialog.pick_file() else {
+            return;
+        };
+
+        let session: Session = match fs::read(&path)
+            .and_then(|bytes| serde_json::from_slice(&bytes).map_err(io::Error::from))
+        {
+            Ok(session) => session,
+            Err(e) => {
+                self.status = format!("Failed to open {}: {}", path.display(), e);
+                return;
+            }
+        };
+        self.clear();
+        let count = session.entries.len();
+        for saved in session.entries {
+            let state = match saved.state {
+                SessionState::Pending => EntryState::Queued,
+                SessionState::Missing => EntryState::Missing,
+                SessionState::Done { crc } => EntryState::Done(Ok(Digest::from_u32(crc))),
+                SessionState::Hashed { digest } => EntryState::Done(
+                    parse_hex(&digest)
+                        .map(Digest)
+                        .ok_or_else(|| format!("invalid digest '{digest}' in the session")),
+                ),
+                SessionState::Error { message } => EntryState::Done(Err(message)),
+            };
+            self.next_id += 1;
+            let queued = state.is_pending();
+            self.entries.push(FileEntry {
+                id: self.next_id,
+                meta: FileMeta::read(&saved.path),
+                path: saved.path,
+                expected: saved
+                    .expected
+                    .map(Digest::from_u32)
+                    .or_else(|| saved.expected_digest.as_deref().and_then(parse_hex).map(Digest)),
+                algo: saved.algo,
+                state,
+            });
+            if queued {
+                self.queue_job(self.next_id);
+            }
+        }
+        self.status = format!("Opened {} files from {}", count, path.display());
+    }
+
     fn clear(&mut self) {
         self.entries.clear();
+        self.selected.clear();
+        self.queue.clear();
         self.status = "Cleared".to_string();
     }
 
+    fn remove_selected(&mut self) {
+        if self.selected.is_empty() {
+            return;
+        }
+        let before = self.entries.len();
+        self.entries.retain(|e| !self.selected.contains(&e.id));
+        self.selected.clear();
+        self.status = format!("Removed {} file(s)", before - self.entries.len());
+    }
+
+    /// Start or stop watching to match the setting, and watch the folder of
+    /// every listed file. Folders rather than files are watched so a file
+    /// that is replaced, as a re-download usually is, keeps being followed.
+    fn sync_watcher(&mut self, ctx: &egui::Context) {
+        if !self.settings.watch_files {
+            if self.watcher.take().is_some() {
+                self.watched_dirs.clear();
+                self.changed.clear();
+            }
+            return;
+        }
+        if self.watcher.is_none() {
+            let (tx, ctx) = (self.watch_tx.clone(), ctx.clone());
+            let watcher = notify::recommended_watcher(move |event| {
+                let paths = changed_paths(event);
+                if !paths.is_empty() {
+                    for path in paths {
+                        let _ = tx.send(path);
+                    }
+                    ctx.request_repaint();
+                }
+            });
+            match watcher {
+                Ok(watcher) => self.watcher = Some(watcher),
+                Err(e) => {
+                    self.settings.watch_files = false;
+                    self.status = format!("Cannot watch files: {}", e);
+                    return;
+                }
+            }
+        }
+        let Some(watcher) = self.watcher.as_mut() else {
+            return;
+        };
+        for entry in &self.entries {
+            let Some(dir) = std::path::absolute(&entry.path)
+                .ok()
+                .and_then(|path| path.parent().map(Path::to_path_buf))
+            else {
+                continue;
+            };
+            if self.watched_dirs.contains(&dir) {
+                continue;
+            }
+            if let Err(e) = watcher.watch(&dir, RecursiveMode::NonRecursive) {
+                self.status = format!("Cannot watch {}: {}", dir.display(), e);
+            }
+            self.watched_dirs.insert(dir);
+        }
+    }
+
+    /// Re-queue listed files that changed and have since settled for
+    /// `WATCH_DEBOUNCE`. A file still being hashed waits until it is done.
+    fn poll_watcher(&mut self, ctx: &egui::Context) {
+        while let Ok(path) = self.watch_rx.try_recv() {
+            self.changed.insert(path, Instant::now());
+        }
+        let settled: Vec<PathBuf> = self
+            .changed
+            .iter()
+            .filter(|(_, at)| at.elapsed() >= WATCH_DEBOUNCE)
+            .map(|(path, _)| path.clone())
+            .collect();
+        let mut requeued = 0;
+        for path in settled {
+            let rows: Vec<usize> = (0..self.entries.len())
+                .filter(|&i| std::path::absolute(&self.entries[i].path).is_ok_and(|p| p == path))
+                .collect();
+            if rows.iter().any(|&i| self.entries[i].state.is_pending()) {
+                continue;
+            }
+            self.changed.remove(&path);
+            for i in rows {
+                self.rehash(i, self.entries[i].algo);
+                requeued += 1;
+            }
+        }
+        if requeued > 0 {
+            self.status = format!("Re-checking {requeued} changed file(s)...");
+        }
+        if !self.changed.is_empty() {
+            ctx.request_repaint_after(WATCH_DEBOUNCE);
+        }
+    }
+
+    /// Ctrl+O adds files, Ctrl+L clears, Delete removes the selected rows.
+    /// Ignored while a text field has keyboard focus.
+    fn handle_shortcuts(&mut self, ctx: &egui::Context) {
+        if ctx.wants_keyboard_input() {
+            return;
+        }
+        let (add, clear, remove) = ctx.input_mut(|i| {
+            (
+                i.consume_key(egui::Modifiers::COMMAND, egui::Key::O),
+                i.consume_key(egui::Modifiers::COMMAND, egui::Key::L),
+                i.consume_key(egui::Modifiers::NONE, egui::Key::Delete),
+            )
+        });
+        if add {
+            self.add_files();
+        }
+        if clear {
+            self.clear();
+        }
+        if remove {
+            self.remove_selected();
+        }
+    }
+
+    /// Keep egui's visuals in line with the theme preference. `System`
+    /// follows the OS setting when eframe can detect it.
+    fn apply_theme(&self, ctx: &egui::Context, frame: &eframe::Frame) {
+        let dark = match self.settings.theme {
+            ThemePreference::System => match frame.info().system_theme {
+                Some(eframe::Theme::Dark) => true,
+                Some(eframe::Theme::Light) => false,
+                None => return,
+            },
+            ThemePreference::Light => false,
+            ThemePreference::Dark => true,
+        };
+        if ctx.style().visuals.dark_mode != dark {
+            ctx.set_visuals(if dark {
+                egui::Visuals::dark()
+            } else {
+                egui::Visuals::light()
+            });
+        }
+    }
+
+    /// Whether `entry` passes the filename filter and the status chips.
+    fn is_visible(&self, entry: &FileEntry) -> bool {
+        let needle = self.filter_text.trim().to_lowercase();
+        if !needle.is_empty()
+            && !entry
+                .path
+                .to_string_lossy()
+                .to_lowercase()
+                .contains(&needle)
+        {
+            return false;
+        }
+        if !self.only_failed && !self.only_missing {
+            return true;
+        }
+        match entry.status() {
+            RowStatus::Mismatch | RowStatus::Error => self.only_failed,
+            RowStatus::Missing => self.only_missing,
+            RowStatus::Pending | RowStatus::Ok => false,
+        }
+    }
+
+    /// Plain-text counts for the summary bar, followed by the status of the
+    /// focused row, or else of the one selected row. Screen readers read it
+    /// as a single label.
+    fn summary_text(&self) -> String {
+        let (mut pending, mut ok, mut mismatch, mut missing, mut error) = (0, 0, 0, 0, 0);
+        for entry in &self.entries {
+            match entry.status() {
+                RowStatus::Pending => pending += 1,
+                RowStatus::Ok => ok += 1,
+                RowStatus::Mismatch => mismatch += 1,
+                RowStatus::Missing => missing += 1,
+                RowStatus::Error => error += 1,
+            }
+        }
+        let mut summary = format!(
+            "{} files: {ok} OK, {mismatch} mismatched, {missing} missing, \
+             {error} unreadable, {pending} still hashing{}.",
+            self.entries.len(),
+            if self.paused { " (paused)" } else { "" }
+        );
+
+        let mut selected = self.entries.iter().filter(|e| self.selected.contains(&e.id));
+        let row = self
+            .focused_row
+            .and_then(|id| self.entries.iter().find(|e| e.id == id))
+            .or_else(|| match (selected.next(), selected.next()) {
+                (Some(entry), None) => Some(entry),
+                _ => None,
+            });
+        if let Some(entry) = row {
+            summary.push_str(&format!(
+                " {}: {}",
+                entry.path.display(),
+                entry.status_text(self.paused)
+            ));
+        }
+        summary
+    }
+
+    /// Whether the list is being built into a new manifest rather than
+    /// checked against one; only then does row order mean anything.
+    fn is_create_mode(&self) -> bool {
+        self.entries.iter().all(|entry| entry.expected.is_none())
+    }
+
+    /// Heading of the digest column: the rows' algorithm when they share
+    /// one, as those of a checksum file do, and the selected one otherwise.
+    fn digest_heading(&self) -> &'static str {
+        let mut algos = self.entries.iter().map(|entry| entry.algo);
+        match algos.next() {
+            Some(first) if algos.all(|algo| algo == first) => algo_label(first),
+            Some(_) => "Digest",
+            None => algo_label(self.settings.algo),
+        }
+    }
+
+    /// Move row `id` to the position of row `target`, shifting the rows in
+    /// between. Row order is the order "Copy as SFV" writes.
+    fn move_entry(&mut self, id: u64, target: u64) {
+        let Some(from) = self.entries.iter().position(|e| e.id == id) else {
+            return;
+        };
+        let Some(to) = self.entries.iter().position(|e| e.id == target) else {
+            return;
+        };
+        let entry = self.entries.remove(from);
+        self.entries.insert(to, entry);
+    }
+
+    fn toggle_selection(&mut self, id: u64, extend: bool) {
+        if extend {
+            if !self.selected.remove(&id) {
+                self.selected.insert(id);
+            }
+        } else {
+            self.selected.clear();
+            self.selected.insert(id);
+        }
+    }
+
     fn poll_workers(&mut self) {
-        while let Ok((path, result)) = self.rx.try_recv() {
+        while let Ok(message) = self.rx.try_recv() {
+            let report = match message {
+                WorkerMessage::Progress {
+                    id,
+                    job,
+                    bytes_done,
+                } => {
+                    if let Some(EntryState::Pending {
+                        bytes_done: done,
+                        last_heartbeat,
+                        ..
+                    }) = self
+                        .entries
+                        .iter_mut()
+                        .find(|e| e.id == id && e.state.is_running_job(job))
+                        .map(|e| &mut e.state)
+                    {
+                        *done = bytes_done;
+                        *last_heartbeat = Instant::now();
+                    }
+                    continue;
+                }
+                WorkerMessage::Done(report) => report,
+            };
+            self.running = self.running.saturating_sub(1);
             if let Some(entry) = self
                 .entries
                 .iter_mut()
-                .find(|e| e.path == path && matches!(e.state, EntryState::Pending))
+                .find(|e| e.id == report.id && e.state.is_running_job(report.job))
             {
-                entry.state = EntryState::Done(result);
+                entry.meta = report.meta;
+                entry.state = match report.digest {
+                    Ok(digest) => EntryState::Done(Ok(digest)),
+                    Err(e) if e.kind() == io::ErrorKind::NotFound => EntryState::Missing,
+                    Err(e) => EntryState::Done(Err(
+                        match e.get_ref().and_then(|e| e.downcast_ref::<HashError>()) {
+                            Some(HashError::Truncated { expected, actual }) => format!(
+                                "File changed during hashing ({} when opened, {} read)",
+                                format_size(*expected),
+                                format_size(*actual)
+                            ),
+                            _ => format!("{}: {}", entry.path.display(), e),
+                        },
+                    )),
+                };
             }
         }
-        if self
-            .entries
-            .iter()
-            .all(|e| matches!(e.state, EntryState::Done(_)))
-        {
+        self.dispatch();
+        if self.entries.iter().all(|e| !e.state.is_pending()) {
             self.status = "Ready".to_string();
         }
     }
@@ -84,74 +1462,335 @@ impl MtsfvGui {
 impl Default for MtsfvGui {
     fn default() -> Self {
         let (tx, rx) = mpsc::channel();
+        let (watch_tx, watch_rx) = mpsc::channel();
         Self {
             entries: Vec::new(),
             status: "Ready".to_string(),
             tx,
             rx,
+            settings: Settings::default(),
+            next_id: 0,
+            next_job: 0,
+            selected: HashSet::new(),
+            filter_text: String::new(),
+            only_failed: false,
+            only_missing: false,
+            pending_folders: Vec::new(),
+            pending_overwrite: None,
+            focused_row: None,
+            queue: VecDeque::new(),
+            running: 0,
+            max_workers: batch::default_workers(),
+            paused: false,
+            watcher: None,
+            watched_dirs: HashSet::new(),
+            watch_tx,
+            watch_rx,
+            changed: HashMap::new(),
         }
     }
 }
 
 impl App for MtsfvGui {
-    fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
+    fn save(&mut self, storage: &mut dyn eframe::Storage) {
+        eframe::set_value(storage, eframe::APP_KEY, &self.settings);
+    }
+
+    fn update(&mut self, ctx: &egui::Context, frame: &mut eframe::Frame) {
         self.poll_workers();
+        self.sync_watcher(ctx);
+        self.poll_watcher(ctx);
+        let running = self.entries.iter().filter(|e| e.state.is_running()).count();
+        if running > 0 {
+            // Keep progress and stall markers current without user input.
+            ctx.request_repaint_after(REPAINT_INTERVAL);
+        }
+        self.handle_shortcuts(ctx);
+        self.handle_dropped_files(ctx);
+        self.apply_theme(ctx, frame);
+        self.show_folder_prompt(ctx);
+        self.show_overwrite_prompt(ctx);
 
         egui::TopBottomPanel::top("header").show(ctx, |ui| {
             ui.heading("MTSFV - QuickSFV style file verifier");
             ui.horizontal(|ui| {
-                if ui.button("Add files...").clicked() {
+                if ui.button("Add files...").on_hover_text("Ctrl+O").clicked() {
                     self.add_files();
                 }
-                if ui.button("Clear").clicked() {
+                if ui.button("Add folder...").clicked() {
+                    self.add_folder();
+                }
+                if ui
+                    .button("Compare files...")
+                    .on_hover_text("Check whether two files are identical; or select two rows")
+                    .clicked()
+                {
+                    self.compare_files();
+                }
+                if ui
+                    .button("Verify SFV...")
+                    .on_hover_text("Check files against an .sfv, .md5, .sha1 or .sha256 file")
+                    .clicked()
+                {
+                    self.verify_sfv();
+                }
+                if ui
+                    .button("Verify folder...")
+                    .on_hover_text("Verify the folder's .sfv file")
+                    .clicked()
+                {
+                    self.verify_folder();
+                }
+                if ui
+                    .button("Verify clipboard")
+                    .on_hover_text("Check SFV lines, or a CRC32 against the selected file")
+                    .clicked()
+                {
+                    self.verify_clipboard();
+                }
+                if ui.button("Retry failed").clicked() {
+                    self.retry_failed();
+                }
+                let pause_label = if self.paused { "Resume" } else { "Pause" };
+                if ui
+                    .button(pause_label)
+                    .on_hover_text("Stop starting queued files; files being hashed still finish")
+                    .clicked()
+                {
+                    self.toggle_pause();
+                }
+                ui.toggle_value(&mut self.settings.watch_files, "Watch")
+                    .on_hover_text("Check files again when they change on disk");
+                if ui
+                    .button("Recompute")
+                    .on_hover_text("Hash the listed files again with the selected algorithm")
+                    .clicked()
+                {
+                    self.recompute();
+                }
+                if ui.button("Copy as SFV").clicked() {
+                    self.copy_as_sfv(ui.ctx());
+                }
+                if ui.button("Save as SFV...").clicked() {
+                    self.save_as_sfv();
+                }
+                if ui.button("Save session...").clicked() {
+                    self.save_session();
+                }
+                if ui.button("Open session...").clicked() {
+                    self.open_session();
+                }
+                if ui.button("Clear").on_hover_text("Ctrl+L").clicked() {
                     self.clear();
                 }
+                if ui
+                    .button(format!("Theme: {}", self.settings.theme.label()))
+                    .clicked()
+                {
+                    self.settings.theme = self.settings.theme.next();
+                }
+                egui::ComboBox::from_id_source("algo")
+                    .selected_text(algo_label(self.settings.algo))
+                    .show_ui(ui, |ui| {
+                        for algo in GUI_ALGOS {
+                            ui.selectable_value(&mut self.settings.algo, algo, algo_label(algo));
+                        }
+                    })
+                    .response
+                    .on_hover_text("Algorithm for newly added files");
                 if ui.button("Close").clicked() {
                     ui.ctx().send_viewport_cmd(egui::ViewportCommand::Close);
                 }
                 ui.separator();
+                // Not a spinner: it would redraw every frame for as long as
+                // hashing runs.
+                if running > 0 {
+                    ui.label(format!("Hashing {running} file(s)"));
+                }
                 ui.label(format!("Status: {}", self.status));
             });
+            ui.horizontal(|ui| {
+                ui.label("Filter:");
+                ui.add(
+                    egui::TextEdit::singleline(&mut self.filter_text)
+                        .hint_text("filename contains...")
+                        .desired_width(240.0),
+                );
+                ui.toggle_value(&mut self.only_failed, "Failed");
+                ui.toggle_value(&mut self.only_missing, "Missing");
+            });
+        });
+
+        self.show_compare_banner(ctx);
+
+        egui::TopBottomPanel::bottom("summary").show(ctx, |ui| {
+            ui.label(self.summary_text());
         });
 
         egui::CentralPanel::default().show(ctx, |ui| {
             ui.label("Selected files");
             ui.separator();
 
+            let visible: Vec<usize> = (0..self.entries.len())
+                .filter(|&i| self.is_visible(&self.entries[i]))
+                .collect();
+            // Row 0 is the header; visible entry rows follow in order.
+            let statuses: Vec<RowStatus> =
+                visible.iter().map(|&i| self.entries[i].status()).collect();
+
             egui::ScrollArea::vertical().show(ui, |ui| {
                 egui::Grid::new("file_grid")
-                    .striped(true)
-                    .num_columns(3)
+                    .with_row_color(move |row, style| {
+                        let status = row.checked_sub(1).and_then(|i| statuses.get(i))?;
+                        status.row_color(style.visuals.dark_mode).or_else(|| {
+                            (row % 2 == 1).then_some(style.visuals.faint_bg_color)
+                        })
+                    })
+                    .num_columns(5)
                     .show(ui, |ui| {
                         ui.heading("File");
-                        ui.heading("CRC32");
+                        ui.heading("Size");
+                        ui.heading("Modified (UTC)");
+                        ui.heading(self.digest_heading());
                         ui.heading("Status");
                         ui.end_row();
 
                         if self.entries.is_empty() {
                             ui.label("No files selected");
-                            ui.label("");
-                            ui.label("");
+                            for _ in 0..4 {
+                                ui.label("");
+                            }
+                            ui.end_row();
+                        } else if visible.is_empty() {
+                            ui.label("No files match the filter");
+                            for _ in 0..4 {
+                                ui.label("");
+                            }
                             ui.end_row();
                         } else {
-                            for entry in &self.entries {
-                                ui.label(entry.path.display().to_string());
-                                match &entry.state {
-                                    EntryState::Pending => {
-                                        ui.monospace("--");
-                                        ui.label("Calculating...");
+                            let mut clicked = None;
+                            let mut reveal = None;
+                            let mut moved = None;
+                            let mut focused = None;
+                            let reorderable = self.is_create_mode();
+                            for &index in &visible {
+                                let entry = &self.entries[index];
+                                let selected = self.selected.contains(&entry.id);
+                                let cell = ui.horizontal(|ui| {
+                                    if reorderable {
+                                        let handle = egui::Id::new(("row_handle", entry.id));
+                                        ui.dnd_drag_source(handle, entry.id, |ui| ui.label("☰"));
+                                    }
+                                    ui.selectable_label(selected, entry.path.display().to_string())
+                                });
+                                if reorderable {
+                                    if let Some(dragged) = cell.response.dnd_hover_payload::<u64>()
+                                    {
+                                        // Mark the side of this row the dragged row will land on.
+                                        let from =
+                                            self.entries.iter().position(|e| e.id == *dragged);
+                                        let rect = cell.response.rect;
+                                        let y = if from.is_some_and(|from| from > index) {
+                                            rect.top()
+                                        } else {
+                                            rect.bottom()
+                                        };
+                                        ui.painter().hline(
+                                            rect.x_range(),
+                                            y,
+                                            ui.visuals().selection.stroke,
+                                        );
+                                    }
+                                    if let Some(dragged) =
+                                        cell.response.dnd_release_payload::<u64>()
+                                    {
+                                        moved = Some((*dragged, entry.id));
+                                    }
+                                }
+                                let label = cell.inner;
+                                // Let assistive tech read the row's status with its name.
+                                label.widget_info(|| {
+                                    egui::WidgetInfo::selected(
+                                        egui::WidgetType::SelectableLabel,
+                                        true,
+                                        selected,
+                                        format!(
+                                            "{}, {}",
+                                            entry.path.display(),
+                                            entry.status_text(self.paused)
+                                        ),
+                                    )
+                                });
+                                if label.has_focus() {
+                                    focused = Some(entry.id);
+                                }
+                                if label.clicked() {
+                                    clicked = Some(entry.id);
+                                }
+                                label.context_menu(|ui| {
+                                    if ui.button("Show in folder").clicked() {
+                                        reveal = Some(entry.path.clone());
+                                        ui.close_menu();
+                                    }
+                                });
+                                match entry.meta {
+                                    Some(meta) => {
+                                        ui.label(format_size(meta.size))
+                                            .on_hover_text(format!("{} bytes", meta.size));
+                                        ui.label(
+                                            meta.modified.map(format_utc).unwrap_or("--".into()),
+                                        );
                                     }
-                                    EntryState::Done(Ok(crc)) => {
-                                        ui.monospace(format!("{crc:08X}"));
-                                        ui.label("OK");
+                                    None => {
+                                        ui.label("--");
+                                        ui.label("--");
                                     }
-                                    EntryState::Done(Err(err)) => {
+                                }
+                                match &entry.state {
+                                    EntryState::Done(Ok(digest)) => {
+                                        let digest_label =
+                                            ui.monospace(entry.algo.format(digest));
+                                        // Recompute leaves rows being verified alone.
+                                        if entry.expected.is_some() {
+                                            digest_label.on_hover_text(algo_label(entry.algo));
+                                        } else if entry.algo != self.settings.algo {
+                                            digest_label.on_hover_text(format!(
+                                                "{}; click Recompute for {}",
+                                                algo_label(entry.algo),
+                                                algo_label(self.settings.algo)
+                                            ));
+                                        }
+                                    }
+                                    _ => {
                                         ui.monospace("--");
-                                        ui.label(err);
                                     }
-                                };
+                                }
+                                let status = ui.label(entry.status_text(self.paused));
+                                if entry.state.is_stalled() {
+                                    status.on_hover_text(
+                                        "No progress for a while; the row will update if the \
+                                         read completes",
+                                    );
+                                }
                                 ui.end_row();
                             }
+                            self.focused_row = focused;
+                            if let Some(id) = clicked {
+                                let extend = ui.input(|i| i.modifiers.command);
+                                self.toggle_selection(id, extend);
+                            }
+                            if let Some((id, target)) = moved {
+                                self.move_entry(id, target);
+                            }
+                            if let Some(path) = reveal {
+                                if let Err(e) = show_in_folder(&path) {
+                                    self.status = format!(
+                                        "Failed to open folder for {}: {}",
+                                        path.display(),
+                                        e
+                                    );
+                                }
+                            }
                         }
                     });
             });
@@ -159,16 +1798,84 @@ impl App for MtsfvGui {
     }
 }
 
+/// Files whose contents `event` says changed. Metadata-only changes
+/// (including access times) are left out, so hashing a file does not set off
+/// its own re-check.
+fn changed_paths(event: notify::Result<notify::Event>) -> Vec<PathBuf> {
+    match event {
+        Ok(event) => match event.kind {
+            EventKind::Modify(ModifyKind::Metadata(_)) => Vec::new(),
+            EventKind::Create(_) | EventKind::Modify(_) | EventKind::Remove(_) => event.paths,
+            _ => Vec::new(),
+        },
+        Err(_) => Vec::new(),
+    }
+}
+
+/// A CRC32 pasted on its own: exactly 8 hex digits, optionally prefixed
+/// with `0x`.
+fn parse_bare_crc(text: &str) -> Option<u32> {
+    let text = text.trim();
+    let digits = text
+        .strip_prefix("0x")
+        .or_else(|| text.strip_prefix("0X"))
+        .unwrap_or(text);
+    if digits.len() != 8 || !digits.bytes().all(|b| b.is_ascii_hexdigit()) {
+        return None;
+    }
+    u32::from_str_radix(digits, 16).ok()
+}
+
+/// Bytes of a hex string such as a saved digest, or `None` if it is not an
+/// even number of hex digits.
+fn parse_hex(hex: &str) -> Option<Vec<u8>> {
+    (0..hex.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(hex.get(i..i + 2)?, 16).ok())
+        .collect()
+}
+
+/// Open the platform file manager with `path` selected. On Linux there is
+/// no common way to select a file, so its folder is opened instead.
+fn show_in_folder(path: &Path) -> io::Result<()> {
+    #[cfg(target_os = "windows")]
+    let mut command = {
+        use std::os::windows::process::CommandExt;
+        let mut command = Command::new("explorer");
+        // explorer parses its own command line; the path must stay quoted
+        // inside the single `/select,` argument.
+        command.raw_arg(format!("/select,\"{}\"", path.display()));
+        command
+    };
+
+    #[cfg(target_os = "macos")]
+    let mut command = {
+        let mut command = Command::new("open");
+        command.arg("-R").arg(path);
+        command
+    };
+
+    #[cfg(not(any(target_os = "windows", target_os = "macos")))]
+    let mut command = {
+        let mut command = Command::new("xdg-open");
+        command.arg(path.parent().unwrap_or(Path::new(".")));
+        command
+    };
+
+    command.spawn().map(|_| ())
+}
+
 fn main() -> eframe::Result<()> {
     let options = eframe::NativeOptions {
         viewport: egui::ViewportBuilder::default().with_inner_size([820.0, 520.0]),
+        persist_window: true,
         ..Default::default()
     };
     eframe::run_native(
         "MTSFV - File Verifier",
         options,
-        Box::new(|_cc| {
-            Ok(Box::new(MtsfvGui::default()))
+        Box::new(|cc| {
+            Ok(Box::new(MtsfvGui::new(cc)))
         }),
     )
 }
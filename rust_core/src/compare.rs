@@ -0,0 +1,74 @@
+use crate::hash::open_file;
+use crate::hash_buffer_size;
+use std::io::{self, BufRead, BufReader, Read, Seek, SeekFrom};
+use std::path::Path;
+
+/// Offset of the first byte at which `reference` and `candidate` differ, or
+/// `None` when their contents are identical.
+///
+/// When one file is a prefix of the other, the offset is the length of the
+/// shorter one.
+pub fn first_difference(reference: &Path, candidate: &Path) -> io::Result<Option<u64>> {
+    let size = hash_buffer_size();
+    let mut reference = BufReader::with_capacity(size, open_file(reference)?);
+    let mut candidate = BufReader::with_capacity(size, open_file(candidate)?);
+    let mut offset = 0u64;
+
+    loop {
+        let a = reference.fill_buf()?;
+        let b = candidate.fill_buf()?;
+        if a.is_empty() || b.is_empty() {
+            return Ok((a.len() != b.len()).then_some(offset));
+        }
+        let len = a.len().min(b.len());
+        if let Some(index) = a[..len].iter().zip(&b[..len]).position(|(x, y)| x != y) {
+            return Ok(Some(offset + index as u64));
+        }
+        reference.consume(len);
+        candidate.consume(len);
+        offset += len as u64;
+    }
+}
+
+/// Read up to `len` bytes of `path` starting at `offset`; fewer are returned
+/// near the end of the file.
+pub fn read_range(path: &Path, offset: u64, len: usize) -> io::Result<Vec<u8>> {
+    let mut file = open_file(path)?;
+    file.seek(SeekFrom::Start(offset))?;
+    let mut bytes = Vec::with_capacity(len);
+    file.take(len as u64).read_to_end(&mut bytes)?;
+    Ok(bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::TempDir;
+
+    #[test]
+    fn test_first_difference() {
+        let dir = TempDir::new("first_difference");
+        let data = crate::test_util::patterned(200_000);
+        let reference = dir.write("reference.bin", &data);
+        let same = dir.write("same.bin", &data);
+        assert_eq!(first_difference(&reference, &same).unwrap(), None);
+
+        let mut corrupt = data.clone();
+        corrupt[150_000] ^= 0xFF;
+        let candidate = dir.write("corrupt.bin", &corrupt);
+        assert_eq!(
+            first_difference(&reference, &candidate).unwrap(),
+            Some(150_000)
+        );
+
+        let short = dir.write("short.bin", &data[..70_000]);
+        assert_eq!(first_difference(&reference, &short).unwrap(), Some(70_000));
+        assert_eq!(first_difference(&short, &reference).unwrap(), Some(70_000));
+
+        assert_eq!(
+            read_range(&candidate, 149_999, 3).unwrap()[1],
+            data[150_000] ^ 0xFF
+        );
+        assert_eq!(read_range(&short, 69_998, 16).unwrap().len(), 2);
+    }
+}
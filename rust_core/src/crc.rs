@@ -0,0 +1,64 @@
+//! In-memory CRC32 that only needs `core`.
+//!
+//! This is the part of the crate available without the `std` feature, for
+//! embedded hosts that hash buffers they already hold.
+
+use crc32fast::Hasher;
+
+/// CRC32 (IEEE) of `data`.
+pub fn crc32(data: &[u8]) -> u32 {
+    crc32_update(0, data)
+}
+
+/// Continue a CRC32 over `data`, starting from the result of a previous
+/// call, so a buffer can be hashed in pieces.
+pub fn crc32_update(crc: u32, data: &[u8]) -> u32 {
+    let mut hasher = Hasher::new_with_initial(crc);
+    hasher.update(data);
+    hasher.finalize()
+}
+
+/// CRC32 of the concatenation `a ++ b`, given the CRC of each part and the
+/// length of `b`, without access to the data itself.
+pub fn crc32_combine(crc_a: u32, crc_b: u32, len_b: u64) -> u32 {
+    let mut hasher = Hasher::new_with_initial(crc_a);
+    hasher.combine(&Hasher::new_with_initial_len(crc_b, len_b));
+    hasher.finalize()
+}
+
+/// Fold bytes appended to a file or buffer into its previous CRC32, so that
+/// only `new_data` has to be read.
+///
+/// `previous_crc` must be the CRC32 of the old contents and `previous_len`
+/// their length in bytes; both must be correct, or the result is not the
+/// CRC of the whole. The combination itself only needs the length of the
+/// appended part, so `previous_len` is currently unused and reserved.
+pub fn crc32_append(previous_crc: u32, _previous_len: u64, new_data: &[u8]) -> u32 {
+    crc32_combine(previous_crc, crc32(new_data), new_data.len() as u64)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_crc32_in_pieces() {
+        assert_eq!(crc32(b""), 0);
+        assert_eq!(crc32(b"123456789"), 0xCBF43926);
+        assert_eq!(crc32_update(crc32(b"1234"), b"56789"), 0xCBF43926);
+    }
+
+    #[test]
+    fn test_crc32_append_matches_whole_buffer() {
+        let data: [u8; 1000] = core::array::from_fn(|i| (i * 7 % 251) as u8);
+        let whole = crc32(&data);
+        for split in [0, 1, 9, 500, 999, 1000] {
+            let (head, tail) = data.split_at(split);
+            assert_eq!(crc32_append(crc32(head), head.len() as u64, tail), whole);
+            assert_eq!(
+                crc32_combine(crc32(head), crc32(tail), tail.len() as u64),
+                whole
+            );
+        }
+    }
+}
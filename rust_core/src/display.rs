@@ -0,0 +1,96 @@
+//! Human-readable sizes and timestamps, as shown by the CLI and the GUI.
+
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// `bytes` in the largest binary unit that keeps the number at least 1, e.g.
+/// `1.5 MiB`; below 1 KiB, the exact count (`512 B`).
+pub fn format_size(bytes: u64) -> String {
+    const UNITS: [&str; 5] = ["KiB", "MiB", "GiB", "TiB", "PiB"];
+    if bytes < 1024 {
+        return format!("{bytes} B");
+    }
+    let mut value = bytes as f64 / 1024.0;
+    let mut unit = 0;
+    while value >= 1024.0 && unit < UNITS.len() - 1 {
+        value /= 1024.0;
+        unit += 1;
+    }
+    format!("{value:.1} {}", UNITS[unit])
+}
+
+/// `YYYY-MM-DDTHH:MM:SSZ` in UTC; times before 1970 show as the epoch.
+pub fn format_rfc3339(time: SystemTime) -> String {
+    let secs = time
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |since_epoch| since_epoch.as_secs());
+    let (year, month, day, secs_of_day) = utc_date(secs);
+    format!(
+        "{year:04}-{month:02}-{day:02}T{:02}:{:02}:{:02}Z",
+        secs_of_day / 3_600,
+        secs_of_day % 3_600 / 60,
+        secs_of_day % 60
+    )
+}
+
+/// `YYYY-MM-DD HH:MM` in UTC; times before 1970 show as `--`.
+pub fn format_utc(time: SystemTime) -> String {
+    let Ok(since_epoch) = time.duration_since(UNIX_EPOCH) else {
+        return "--".to_string();
+    };
+    let (year, month, day, secs_of_day) = utc_date(since_epoch.as_secs());
+    format!(
+        "{year:04}-{month:02}-{day:02} {:02}:{:02}",
+        secs_of_day / 3_600,
+        secs_of_day % 3_600 / 60
+    )
+}
+
+/// Year, month, day and seconds into the day of `secs` after the Unix epoch.
+fn utc_date(secs: u64) -> (i64, i64, i64, u64) {
+    let (days, secs_of_day) = (secs / 86_400, secs % 86_400);
+
+    // Civil-from-days, from Howard Hinnant's date algorithms.
+    let z = days as i64 + 719_468;
+    let era = z / 146_097;
+    let doe = z - era * 146_097;
+    let yoe = (doe - doe / 1_460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + i64::from(month <= 2);
+
+    (year, month, day, secs_of_day)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[test]
+    fn test_format_size() {
+        assert_eq!(format_size(0), "0 B");
+        assert_eq!(format_size(1023), "1023 B");
+        assert_eq!(format_size(1024), "1.0 KiB");
+        assert_eq!(format_size(1536 * 1024), "1.5 MiB");
+        assert_eq!(format_size(5 << 30), "5.0 GiB");
+        assert_eq!(format_size(u64::MAX), "16384.0 PiB");
+    }
+
+    #[test]
+    fn test_format_times() {
+        let at = |secs| UNIX_EPOCH + Duration::from_secs(secs);
+        assert_eq!(format_rfc3339(at(0)), "1970-01-01T00:00:00Z");
+        // 2000-02-29, a leap day in a century year.
+        assert_eq!(
+            format_rfc3339(at(951_782_400 + 45_296)),
+            "2000-02-29T12:34:56Z"
+        );
+        assert_eq!(format_utc(at(1_700_000_000)), "2023-11-14 22:13");
+
+        let before_epoch = UNIX_EPOCH - Duration::from_secs(1);
+        assert_eq!(format_utc(before_epoch), "--");
+        assert_eq!(format_rfc3339(before_epoch), "1970-01-01T00:00:00Z");
+    }
+}
@@ -0,0 +1,102 @@
+//! Parsing of `--files-from` path lists.
+
+use std::borrow::Cow;
+
+/// Split the contents of a path list into individual paths.
+///
+/// Entries are separated by newlines (a trailing `\r` is dropped so lists
+/// written on Windows work), or by NUL bytes when `null_separated` is set to
+/// match `find -print0`. Empty entries are skipped. The list is read as
+/// UTF-8, or as UTF-16 when it starts with a UTF-16 byte order mark, as
+/// Windows editors often save it; a leading BOM is never part of the first
+/// path.
+pub fn parse_file_list(contents: &[u8], null_separated: bool) -> Vec<String> {
+    let separator = if null_separated { '\0' } else { '\n' };
+    decode(contents)
+        .split(separator)
+        .map(|entry| {
+            if null_separated {
+                entry
+            } else {
+                entry.strip_suffix('\r').unwrap_or(entry)
+            }
+        })
+        .filter(|entry| !entry.is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
+/// List text from its raw bytes, going by the byte order mark if any.
+fn decode(contents: &[u8]) -> Cow<'_, str> {
+    if let Some(rest) = contents.strip_prefix(b"\xEF\xBB\xBF") {
+        String::from_utf8_lossy(rest)
+    } else if let Some(rest) = contents.strip_prefix(b"\xFF\xFE") {
+        Cow::Owned(decode_utf16(rest, u16::from_le_bytes))
+    } else if let Some(rest) = contents.strip_prefix(b"\xFE\xFF") {
+        Cow::Owned(decode_utf16(rest, u16::from_be_bytes))
+    } else {
+        String::from_utf8_lossy(contents)
+    }
+}
+
+/// Decode UTF-16 with the given byte order, replacing unpaired surrogates
+/// and a dangling odd byte with U+FFFD.
+fn decode_utf16(bytes: &[u8], unit: fn([u8; 2]) -> u16) -> String {
+    let chunks = bytes.chunks_exact(2);
+    let odd = !chunks.remainder().is_empty();
+    let mut text: String = char::decode_utf16(chunks.map(|pair| unit([pair[0], pair[1]])))
+        .map(|c| c.unwrap_or(char::REPLACEMENT_CHARACTER))
+        .collect();
+    if odd {
+        text.push(char::REPLACEMENT_CHARACTER);
+    }
+    text
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_file_list_lines() {
+        let list = b"a.bin\r\n\r\nsub dir/b.bin\nc.bin";
+        assert_eq!(
+            parse_file_list(list, false),
+            vec!["a.bin", "sub dir/b.bin", "c.bin"]
+        );
+    }
+
+    #[test]
+    fn test_parse_file_list_null_separated() {
+        let list = b"a.bin\0with\nnewline.bin\0\0";
+        assert_eq!(
+            parse_file_list(list, true),
+            vec!["a.bin", "with\nnewline.bin"]
+        );
+    }
+
+    #[test]
+    fn test_parse_file_list_strips_utf8_bom() {
+        let list = b"\xEF\xBB\xBFa.bin\r\nb.bin\r\n";
+        assert_eq!(parse_file_list(list, false), vec!["a.bin", "b.bin"]);
+    }
+
+    #[test]
+    fn test_parse_file_list_utf16() {
+        let text = "caf\u{e9}.bin\r\n\u{1F600}.bin\r\n";
+        let mut le = vec![0xFF, 0xFE];
+        le.extend(text.encode_utf16().flat_map(u16::to_le_bytes));
+        let mut be = vec![0xFE, 0xFF];
+        be.extend(text.encode_utf16().flat_map(u16::to_be_bytes));
+        for list in [le, be] {
+            assert_eq!(
+                parse_file_list(&list, false),
+                vec!["caf\u{e9}.bin", "\u{1F600}.bin"]
+            );
+        }
+
+        let mut nul = vec![0xFF, 0xFE];
+        nul.extend("a.bin\0b.bin\0".encode_utf16().flat_map(u16::to_le_bytes));
+        assert_eq!(parse_file_list(&nul, true), vec!["a.bin", "b.bin"]);
+    }
+}
@@ -0,0 +1,859 @@
+use md5::Md5;
+use sha1::Sha1;
+use sha2::{Digest as _, Sha256};
+use std::borrow::Cow;
+use std::fmt;
+use std::fs::{self, File};
+use std::io::{self, BufReader, Read};
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+/// Hash functions supported by the unified `hash_file` dispatcher.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum HashAlgo {
+    Crc32,
+    Crc32c,
+    Adler32,
+    Md5,
+    Sha1,
+    Sha256,
+}
+
+impl HashAlgo {
+    pub const ALL: [HashAlgo; 6] = [
+        HashAlgo::Crc32,
+        HashAlgo::Crc32c,
+        HashAlgo::Adler32,
+        HashAlgo::Md5,
+        HashAlgo::Sha1,
+        HashAlgo::Sha256,
+    ];
+
+    /// Lowercase name as accepted on the command line.
+    pub fn name(self) -> &'static str {
+        match self {
+            HashAlgo::Crc32 => "crc32",
+            HashAlgo::Crc32c => "crc32c",
+            HashAlgo::Adler32 => "adler32",
+            HashAlgo::Md5 => "md5",
+            HashAlgo::Sha1 => "sha1",
+            HashAlgo::Sha256 => "sha256",
+        }
+    }
+
+    /// Format a digest the way tools for this algorithm conventionally do:
+    /// uppercase for the 32-bit checksums (QuickSFV), lowercase for MD5/SHA
+    /// (coreutils).
+    pub fn format(self, digest: &Digest) -> String {
+        match self {
+            HashAlgo::Crc32 | HashAlgo::Crc32c | HashAlgo::Adler32 => digest.to_hex_upper(),
+            HashAlgo::Md5 | HashAlgo::Sha1 | HashAlgo::Sha256 => digest.to_hex_lower(),
+        }
+    }
+}
+
+impl fmt::Display for HashAlgo {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+/// Error returned when parsing an unknown algorithm name.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UnknownAlgo(pub String);
+
+impl fmt::Display for UnknownAlgo {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let names: Vec<&str> = HashAlgo::ALL.iter().map(|a| a.name()).collect();
+        write!(
+            f,
+            "unknown algorithm '{}' (expected one of: {})",
+            self.0,
+            names.join(", ")
+        )
+    }
+}
+
+impl std::error::Error for UnknownAlgo {}
+
+impl FromStr for HashAlgo {
+    type Err = UnknownAlgo;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        HashAlgo::ALL
+            .into_iter()
+            .find(|algo| algo.name().eq_ignore_ascii_case(s))
+            .ok_or_else(|| UnknownAlgo(s.to_string()))
+    }
+}
+
+/// Why a path could not be hashed, beyond plain I/O failures.
+///
+/// The hashing helpers return `io::Result`; these errors are carried inside
+/// the `io::Error` and can be recovered with `get_ref()` and `downcast_ref`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum HashError {
+    /// The path names a directory, not a file.
+    IsDirectory(PathBuf),
+    /// A strict hash read a different number of bytes than the file's size
+    /// when it was opened, usually because it was being written to.
+    Truncated { expected: u64, actual: u64 },
+    /// Hashing was stopped part-way by setting [`ReadControl::cancel`].
+    Cancelled,
+}
+
+impl fmt::Display for HashError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            HashError::IsDirectory(_) => f.write_str("is a directory"),
+            HashError::Truncated { expected, actual } => write!(
+                f,
+                "size changed while hashing: expected {} bytes, read {}",
+                expected, actual
+            ),
+            HashError::Cancelled => f.write_str("cancelled"),
+        }
+    }
+}
+
+impl std::error::Error for HashError {}
+
+impl From<HashError> for io::Error {
+    fn from(err: HashError) -> Self {
+        let kind = match err {
+            HashError::IsDirectory(_) => io::ErrorKind::IsADirectory,
+            HashError::Truncated { .. } => io::ErrorKind::InvalidData,
+            HashError::Cancelled => io::ErrorKind::Interrupted,
+        };
+        io::Error::new(kind, err)
+    }
+}
+
+/// Open `path` for hashing, rejecting directories up front with
+/// [`HashError::IsDirectory`] rather than failing later on read.
+pub fn open_file(path: &Path) -> io::Result<File> {
+    let extended = extended_length_path(path);
+    if extended != path {
+        log::debug!("{}: opening as {}", path.display(), extended.display());
+    }
+    let opened = fs::metadata(&extended).and_then(|metadata| {
+        if metadata.is_dir() {
+            return Err(HashError::IsDirectory(path.to_path_buf()).into());
+        }
+        File::open(&extended)
+    });
+    if let Err(e) = &opened {
+        log::debug!("{}: cannot open ({:?}): {}", path.display(), e.kind(), e);
+    }
+    opened
+}
+
+/// Windows refuses plain paths of `MAX_PATH` (260) characters or more.
+#[cfg(windows)]
+const MAX_PATH: usize = 260;
+
+/// Give a long absolute Windows path the extended-length `\\?\` prefix so
+/// it can be opened past `MAX_PATH`. Such paths are not normalized by the
+/// OS, so separators and `.` components are cleaned up here; paths with `..`
+/// components, relative paths and short paths are returned unchanged.
+#[cfg(windows)]
+pub(crate) fn extended_length_path(path: &Path) -> Cow<'_, Path> {
+    use std::ffi::OsString;
+    use std::os::windows::ffi::{OsStrExt, OsStringExt};
+    use std::path::{Component, Prefix};
+
+    if path.as_os_str().encode_wide().count() < MAX_PATH
+        || !path.is_absolute()
+        || path.components().any(|c| c == Component::ParentDir)
+    {
+        return Cow::Borrowed(path);
+    }
+    let unc = match path.components().next() {
+        Some(Component::Prefix(prefix)) => match prefix.kind() {
+            Prefix::Disk(_) => false,
+            Prefix::UNC(..) => true,
+            // Already verbatim, or a device path.
+            _ => return Cow::Borrowed(path),
+        },
+        _ => return Cow::Borrowed(path),
+    };
+
+    // Rebuilding from components drops `.` and turns `/` into `\`.
+    let cleaned: PathBuf = path.components().collect();
+    let wide: Vec<u16> = cleaned.as_os_str().encode_wide().collect();
+    let (prefix, rest) = if unc {
+        // `\\server\share\...` becomes `\\?\UNC\server\share\...`.
+        (r"\\?\UNC", &wide[1..])
+    } else {
+        (r"\\?\", &wide[..])
+    };
+    let mut extended: Vec<u16> = prefix.encode_utf16().collect();
+    extended.extend_from_slice(rest);
+    Cow::Owned(PathBuf::from(OsString::from_wide(&extended)))
+}
+
+#[cfg(not(windows))]
+pub(crate) fn extended_length_path(path: &Path) -> Cow<'_, Path> {
+    Cow::Borrowed(path)
+}
+
+/// Raw digest bytes, most significant byte first.
+///
+/// CRC values are stored big-endian so that the hex form matches the
+/// familiar `{:08X}` formatting.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Digest(pub Vec<u8>);
+
+impl Digest {
+    pub fn from_u32(value: u32) -> Self {
+        Digest(value.to_be_bytes().to_vec())
+    }
+
+    /// The value of a 32-bit checksum digest, or `None` for longer digests.
+    pub fn to_u32(&self) -> Option<u32> {
+        self.0.as_slice().try_into().ok().map(u32::from_be_bytes)
+    }
+
+    /// `ABCD1234`
+    pub fn to_hex_upper(&self) -> String {
+        self.0.iter().map(|b| format!("{b:02X}")).collect()
+    }
+
+    /// `abcd1234`
+    pub fn to_hex_lower(&self) -> String {
+        self.0.iter().map(|b| format!("{b:02x}")).collect()
+    }
+
+    /// `ab:cd:12:34`, as used for certificate and key fingerprints.
+    pub fn to_hex_colon(&self) -> String {
+        self.0
+            .iter()
+            .map(|b| format!("{b:02x}"))
+            .collect::<Vec<_>>()
+            .join(":")
+    }
+}
+
+/// Streaming state of one hash algorithm.
+///
+/// Every [`HashAlgo`] has an implementation, created by [`HashAlgo::hasher`],
+/// and [`hash_reader_into`] runs the read loop shared by all of them, so a
+/// new algorithm is added in this module alone. The CLI's `--algo` hashing
+/// and the GUI go through [`hash_reader`] and [`hash_file`]. SFV
+/// verification and the FFI exports only deal in CRC32 and call the
+/// `crc32_*` helpers, which read through the same loop.
+pub trait FileHasher {
+    fn algo(&self) -> HashAlgo;
+
+    fn update(&mut self, data: &[u8]);
+
+    fn finish(self: Box<Self>) -> Digest;
+
+    /// Finish and format the digest as [`HashAlgo::format`] does.
+    fn finish_hex(self: Box<Self>) -> String {
+        let algo = self.algo();
+        algo.format(&self.finish())
+    }
+}
+
+impl HashAlgo {
+    /// A fresh hasher for this algorithm.
+    pub fn hasher(self) -> Box<dyn FileHasher> {
+        match self {
+            HashAlgo::Crc32 => Box::new(crc32fast::Hasher::new()),
+            HashAlgo::Crc32c => Box::new(Crc32c(0)),
+            HashAlgo::Adler32 => Box::new(adler2::Adler32::new()),
+            HashAlgo::Md5 => Box::new(Md5::new()),
+            HashAlgo::Sha1 => Box::new(Sha1::new()),
+            HashAlgo::Sha256 => Box::new(Sha256::new()),
+        }
+    }
+}
+
+impl FileHasher for crc32fast::Hasher {
+    fn algo(&self) -> HashAlgo {
+        HashAlgo::Crc32
+    }
+
+    fn update(&mut self, data: &[u8]) {
+        crc32fast::Hasher::update(self, data);
+    }
+
+    fn finish(self: Box<Self>) -> Digest {
+        Digest::from_u32(self.finalize())
+    }
+}
+
+/// Running CRC32C; the `crc32c` crate has no hasher type of its own.
+struct Crc32c(u32);
+
+impl FileHasher for Crc32c {
+    fn algo(&self) -> HashAlgo {
+        HashAlgo::Crc32c
+    }
+
+    fn update(&mut self, data: &[u8]) {
+        self.0 = crc32c::crc32c_append(self.0, data);
+    }
+
+    fn finish(self: Box<Self>) -> Digest {
+        Digest::from_u32(self.0)
+    }
+}
+
+impl FileHasher for adler2::Adler32 {
+    fn algo(&self) -> HashAlgo {
+        HashAlgo::Adler32
+    }
+
+    fn update(&mut self, data: &[u8]) {
+        self.write_slice(data);
+    }
+
+    fn finish(self: Box<Self>) -> Digest {
+        Digest::from_u32(self.checksum())
+    }
+}
+
+/// Implement [`FileHasher`] for a RustCrypto digest.
+macro_rules! impl_digest_hasher {
+    ($ty:ty, $algo:expr) => {
+        impl FileHasher for $ty {
+            fn algo(&self) -> HashAlgo {
+                $algo
+            }
+
+            fn update(&mut self, data: &[u8]) {
+                sha2::Digest::update(self, data);
+            }
+
+            fn finish(self: Box<Self>) -> Digest {
+                Digest(sha2::Digest::finalize(*self).to_vec())
+            }
+        }
+    };
+}
+
+impl_digest_hasher!(Md5, HashAlgo::Md5);
+impl_digest_hasher!(Sha1, HashAlgo::Sha1);
+impl_digest_hasher!(Sha256, HashAlgo::Sha256);
+
+/// Feed everything produced by `reader` to `hasher`, returning the number of
+/// bytes read.
+pub fn hash_reader_into<R: Read>(reader: R, hasher: &mut dyn FileHasher) -> io::Result<u64> {
+    for_each_chunk(reader, |chunk| hasher.update(chunk))
+}
+
+/// Hash everything produced by `reader` with several algorithms in one pass,
+/// returning a digest per entry of `algos`, in the same order.
+pub fn hash_reader_multi<R: Read>(reader: R, algos: &[HashAlgo]) -> io::Result<Vec<Digest>> {
+    let mut hashers: Vec<_> = algos.iter().map(|algo| algo.hasher()).collect();
+    for_each_chunk(reader, |chunk| {
+        for hasher in &mut hashers {
+            hasher.update(chunk);
+        }
+    })?;
+    Ok(hashers.into_iter().map(|hasher| hasher.finish()).collect())
+}
+
+/// Read `reader` to the end, passing each chunk to `on_chunk`, and return
+/// the number of bytes read.
+fn for_each_chunk<R: Read>(reader: R, on_chunk: impl FnMut(&[u8])) -> io::Result<u64> {
+    for_each_chunk_in(reader, &mut vec![0u8; crate::hash_buffer_size()], on_chunk)
+}
+
+/// Like [`for_each_chunk`], reading through the caller's `buffer`.
+///
+/// Every hashing loop in the crate, CRC32 and Adler-32 included, ends up
+/// here.
+pub(crate) fn for_each_chunk_in<R: Read>(
+    mut reader: R,
+    buffer: &mut [u8],
+    mut on_chunk: impl FnMut(&[u8]),
+) -> io::Result<u64> {
+    let mut total = 0u64;
+
+    loop {
+        let bytes_read = reader.read(buffer)?;
+        if bytes_read == 0 {
+            break;
+        }
+        on_chunk(&buffer[..bytes_read]);
+        total += bytes_read as u64;
+    }
+
+    Ok(total)
+}
+
+/// Per-call control over a hash in progress, applied by reading through
+/// [`ReadControl::wrap`]. The default interferes with nothing.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ReadControl<'a> {
+    /// Once this flag is set, reads fail with [`HashError::Cancelled`], so a
+    /// file being hashed is given up on rather than finished. The caller
+    /// owns the flag and may set it from another thread.
+    pub cancel: Option<&'a AtomicBool>,
+    /// Called with the size of every read, before the data is hashed; it may
+    /// sleep to pace the reading, as the CLI's `--rate-limit` does.
+    pub on_read: Option<fn(usize)>,
+}
+
+impl<'a> ReadControl<'a> {
+    /// Whether [`cancel`](Self::cancel) has been set.
+    pub fn is_cancelled(&self) -> bool {
+        self.cancel.is_some_and(|flag| flag.load(Ordering::Relaxed))
+    }
+
+    /// `reader`, reading under this control.
+    pub fn wrap<R: Read>(self, reader: R) -> ControlledReader<'a, R> {
+        ControlledReader {
+            inner: reader,
+            control: self,
+        }
+    }
+}
+
+/// A reader wrapped by [`ReadControl::wrap`].
+#[derive(Debug)]
+pub struct ControlledReader<'a, R> {
+    inner: R,
+    control: ReadControl<'a>,
+}
+
+impl<R: Read> Read for ControlledReader<'_, R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if self.control.is_cancelled() {
+            return Err(HashError::Cancelled.into());
+        }
+        let bytes_read = self.inner.read(buf)?;
+        if let Some(on_read) = self.control.on_read {
+            on_read(bytes_read);
+        }
+        Ok(bytes_read)
+    }
+}
+
+/// Whether `error` is a [`HashError::Cancelled`] rather than a failure.
+pub fn is_cancelled(error: &io::Error) -> bool {
+    error
+        .get_ref()
+        .and_then(|e| e.downcast_ref::<HashError>())
+        .is_some_and(|e| *e == HashError::Cancelled)
+}
+
+/// Hash everything produced by `reader` with the given algorithm.
+pub fn hash_reader<R: Read>(reader: R, algo: HashAlgo) -> io::Result<Digest> {
+    hash_reader_with_len(reader, algo).map(|(digest, _)| digest)
+}
+
+/// Like [`hash_reader`], but also returns the number of bytes consumed.
+pub fn hash_reader_with_len<R: Read>(reader: R, algo: HashAlgo) -> io::Result<(Digest, u64)> {
+    let mut hasher = algo.hasher();
+    let total = hash_reader_into(reader, hasher.as_mut())?;
+    Ok((hasher.finish(), total))
+}
+
+/// Like [`hash_reader`], but fail with [`HashError::Truncated`] unless
+/// exactly `expected_len` bytes are read.
+pub fn hash_reader_exact<R: Read>(
+    reader: R,
+    algo: HashAlgo,
+    expected_len: u64,
+) -> io::Result<Digest> {
+    let (digest, actual) = hash_reader_with_len(reader, algo)?;
+    check_len(expected_len, actual)?;
+    Ok(digest)
+}
+
+/// Fail with [`HashError::Truncated`] when `actual` differs from `expected`.
+pub fn check_len(expected: u64, actual: u64) -> io::Result<()> {
+    if actual != expected {
+        return Err(HashError::Truncated { expected, actual }.into());
+    }
+    Ok(())
+}
+
+/// Hash a file with the given algorithm.
+pub fn hash_file(path: impl AsRef<Path>, algo: HashAlgo) -> io::Result<Digest> {
+    let file = open_file(path.as_ref())?;
+    hash_reader(BufReader::new(file), algo)
+}
+
+/// Like [`hash_file`], calling `on_progress` with the total number of bytes
+/// read so far after each chunk.
+pub fn hash_file_with_progress(
+    path: impl AsRef<Path>,
+    algo: HashAlgo,
+    on_progress: impl FnMut(u64),
+) -> io::Result<Digest> {
+    let file = open_file(path.as_ref())?;
+    hash_with_progress(file, algo, on_progress).map(|(digest, _)| digest)
+}
+
+/// Like [`hash_file_strict`], calling `on_progress` as
+/// [`hash_file_with_progress`] does.
+pub fn hash_file_strict_with_progress(
+    path: impl AsRef<Path>,
+    algo: HashAlgo,
+    on_progress: impl FnMut(u64),
+) -> io::Result<Digest> {
+    let file = open_file(path.as_ref())?;
+    let expected_len = file.metadata()?.len();
+    let (digest, actual) = hash_with_progress(file, algo, on_progress)?;
+    check_len(expected_len, actual)?;
+    Ok(digest)
+}
+
+fn hash_with_progress(
+    file: File,
+    algo: HashAlgo,
+    mut on_progress: impl FnMut(u64),
+) -> io::Result<(Digest, u64)> {
+    let mut hasher = algo.hasher();
+    let mut bytes_done = 0u64;
+    let len = for_each_chunk(file, |chunk| {
+        hasher.update(chunk);
+        bytes_done += chunk.len() as u64;
+        on_progress(bytes_done);
+    })?;
+    Ok((hasher.finish(), len))
+}
+
+/// Hash a file with several algorithms while reading it only once; see
+/// [`hash_reader_multi`].
+pub fn hash_file_multi(path: impl AsRef<Path>, algos: &[HashAlgo]) -> io::Result<Vec<Digest>> {
+    let file = open_file(path.as_ref())?;
+    hash_reader_multi(file, algos)
+}
+
+/// Feed the contents of a file to `hasher`, returning the number of bytes
+/// read.
+pub fn hash_file_into(path: impl AsRef<Path>, hasher: &mut dyn FileHasher) -> io::Result<u64> {
+    let file = open_file(path.as_ref())?;
+    hash_reader_into(file, hasher)
+}
+
+/// Like [`hash_file`], but fail with [`HashError::Truncated`] if the number
+/// of bytes read differs from the file's size when it was opened.
+pub fn hash_file_strict(path: impl AsRef<Path>, algo: HashAlgo) -> io::Result<Digest> {
+    let file = open_file(path.as_ref())?;
+    let expected_len = file.metadata()?.len();
+    hash_reader_exact(BufReader::new(file), algo, expected_len)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn hex(algo: HashAlgo, data: &[u8]) -> String {
+        algo.format(&hash_reader(data, algo).unwrap())
+    }
+
+    #[test]
+    fn test_known_vectors() {
+        let data = b"123456789";
+        assert_eq!(hex(HashAlgo::Crc32, data), "CBF43926");
+        assert_eq!(hex(HashAlgo::Crc32c, data), "E3069283");
+        assert_eq!(hex(HashAlgo::Adler32, b"Wikipedia"), "11E60398");
+        assert_eq!(hex(HashAlgo::Md5, data), "25f9e794323b453885f5181f1b624d0b");
+        assert_eq!(
+            hex(HashAlgo::Sha1, data),
+            "f7c3bc1d808e04732adf679965ccc34ca7ae3441"
+        );
+        assert_eq!(
+            hex(HashAlgo::Sha256, data),
+            "15e2b0d3c33891ebb0f1ef609ec419420c20e320ce94c65fbc8c3312448eb225"
+        );
+    }
+
+    #[test]
+    fn test_empty_file_digests() {
+        let dir = crate::test_util::TempDir::new("hash_empty");
+        let path = dir.write("empty.bin", b"");
+        let expected = [
+            (HashAlgo::Crc32, "00000000"),
+            (HashAlgo::Crc32c, "00000000"),
+            (HashAlgo::Adler32, "00000001"),
+            (HashAlgo::Md5, "d41d8cd98f00b204e9800998ecf8427e"),
+            (HashAlgo::Sha1, "da39a3ee5e6b4b0d3255bfef95601890afd80709"),
+            (
+                HashAlgo::Sha256,
+                "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855",
+            ),
+        ];
+        let multi = hash_file_multi(&path, &HashAlgo::ALL).unwrap();
+        for ((algo, hex), from_multi) in expected.into_iter().zip(multi) {
+            assert_eq!(algo.format(&hash_file(&path, algo).unwrap()), hex, "{algo}");
+            assert_eq!(
+                algo.format(&hash_file_strict(&path, algo).unwrap()),
+                hex,
+                "{algo}"
+            );
+            assert_eq!(algo.format(&from_multi), hex, "{algo}");
+        }
+        assert_eq!(crate::crc32_path(&path).unwrap(), 0);
+        assert_eq!(crate::adler32_path(&path).unwrap(), 1);
+    }
+
+    #[test]
+    fn test_hasher_matches_algo() {
+        for algo in HashAlgo::ALL {
+            let mut hasher = algo.hasher();
+            assert_eq!(hasher.algo(), algo);
+            hasher.update(b"1234");
+            hasher.update(b"56789");
+            assert_eq!(hasher.finish_hex(), hex(algo, b"123456789"));
+        }
+    }
+
+    #[test]
+    fn test_hash_file_multi_matches_single_pass() {
+        let dir = crate::test_util::TempDir::new("hash_multi");
+        let data = crate::test_util::patterned(300_000);
+        let path = dir.write("data.bin", &data);
+
+        let digests = hash_file_multi(&path, &HashAlgo::ALL).unwrap();
+        assert_eq!(digests.len(), HashAlgo::ALL.len());
+        for (algo, digest) in HashAlgo::ALL.into_iter().zip(digests) {
+            assert_eq!(digest, hash_file(&path, algo).unwrap(), "{algo}");
+        }
+        assert!(hash_file_multi(&path, &[]).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_hash_file_with_progress() {
+        let dir = crate::test_util::TempDir::new("hash_progress");
+        let data = crate::test_util::patterned(300_000);
+        let path = dir.write("data.bin", &data);
+
+        let mut reported = Vec::new();
+        let digest =
+            hash_file_with_progress(&path, HashAlgo::Sha256, |done| reported.push(done)).unwrap();
+        assert_eq!(digest, hash_file(&path, HashAlgo::Sha256).unwrap());
+        assert!(reported.windows(2).all(|pair| pair[0] < pair[1]));
+        assert_eq!(reported.last(), Some(&(data.len() as u64)));
+    }
+
+    #[test]
+    fn test_digest_hex_formats() {
+        let digest = Digest::from_u32(0xABCD1234);
+        assert_eq!(digest.to_hex_upper(), "ABCD1234");
+        assert_eq!(digest.to_hex_lower(), "abcd1234");
+        assert_eq!(digest.to_hex_colon(), "ab:cd:12:34");
+        assert_eq!(Digest(Vec::new()).to_hex_colon(), "");
+    }
+
+    #[test]
+    fn test_hash_reader_with_len_counts_bytes() {
+        let data = vec![0xABu8; 200_000];
+        let (digest, len) = hash_reader_with_len(data.as_slice(), HashAlgo::Sha1).unwrap();
+        assert_eq!(len, 200_000);
+        assert_eq!(
+            digest,
+            hash_reader(data.as_slice(), HashAlgo::Sha1).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_hash_directory_is_rejected() {
+        let dir = crate::test_util::TempDir::new("hash_dir");
+        for err in [
+            crate::crc32_path(dir.path()).unwrap_err(),
+            hash_file(dir.path(), HashAlgo::Sha1).unwrap_err(),
+        ] {
+            assert_eq!(err.kind(), io::ErrorKind::IsADirectory);
+            assert_eq!(err.to_string(), "is a directory");
+            let inner = err.get_ref().and_then(|e| e.downcast_ref::<HashError>());
+            assert_eq!(
+                inner,
+                Some(&HashError::IsDirectory(dir.path().to_path_buf()))
+            );
+        }
+    }
+
+    #[cfg(windows)]
+    #[test]
+    fn test_long_windows_path() {
+        use std::os::windows::ffi::OsStrExt;
+
+        let dir = crate::test_util::TempDir::new("long_path");
+        let nested: Vec<String> = (0..12)
+            .map(|i| format!("{i:02}_{}", "d".repeat(20)))
+            .collect();
+        let path = dir.write(&format!("{}\\file.bin", nested.join("\\")), b"123456789");
+        assert!(path.as_os_str().len() > MAX_PATH);
+        assert!(extended_length_path(&path)
+            .to_string_lossy()
+            .starts_with(r"\\?\"));
+
+        assert_eq!(crate::crc32_path(&path).unwrap(), 0xCBF43926);
+        let wide: Vec<u16> = path.as_os_str().encode_wide().chain([0]).collect();
+        assert_eq!(
+            unsafe { crate::mtsfv_crc32_file(wide.as_ptr()) },
+            0xCBF43926
+        );
+    }
+
+    #[test]
+    fn test_hash_reader_exact_detects_size_mismatch() {
+        let data = b"123456789";
+        assert_eq!(
+            hash_reader_exact(&data[..], HashAlgo::Crc32, 9).unwrap(),
+            Digest::from_u32(0xCBF43926)
+        );
+
+        // A reader that ends early, as a file truncated mid-hash would.
+        let err = hash_reader_exact(&data[..5], HashAlgo::Crc32, 9).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+        let inner = err.get_ref().and_then(|e| e.downcast_ref::<HashError>());
+        assert_eq!(
+            inner,
+            Some(&HashError::Truncated {
+                expected: 9,
+                actual: 5
+            })
+        );
+    }
+
+    #[test]
+    fn test_hash_reader_injected_faults() {
+        use crate::test_util::{Fault, FaultyReader};
+
+        let data = crate::test_util::patterned(10_000);
+        let expected = hash_reader(&data[..], HashAlgo::Crc32).unwrap();
+
+        // Reads that return less than asked for still cover every byte.
+        let short = FaultyReader::new(&data[..], Fault::ShortReads(7));
+        assert_eq!(
+            hash_reader_exact(short, HashAlgo::Crc32, 10_000).unwrap(),
+            expected
+        );
+
+        // An early zero-byte read is reported as truncation by the strict
+        // reader and silently hashes the prefix otherwise.
+        let early = FaultyReader::new(&data[..], Fault::EofAt(4_096));
+        let err = hash_reader_exact(early, HashAlgo::Crc32, 10_000).unwrap_err();
+        assert_eq!(
+            err.get_ref().and_then(|e| e.downcast_ref::<HashError>()),
+            Some(&HashError::Truncated {
+                expected: 10_000,
+                actual: 4_096
+            })
+        );
+        let early = FaultyReader::new(&data[..], Fault::EofAt(4_096));
+        assert_eq!(
+            hash_reader_with_len(early, HashAlgo::Crc32).unwrap(),
+            (hash_reader(&data[..4_096], HashAlgo::Crc32).unwrap(), 4_096)
+        );
+
+        // A read error part-way through is passed on unchanged.
+        let failing = FaultyReader::new(
+            &data[..],
+            Fault::ErrorAt(5_000, io::ErrorKind::PermissionDenied),
+        );
+        let err = hash_reader(failing, HashAlgo::Crc32).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::PermissionDenied);
+        assert!(err
+            .get_ref()
+            .and_then(|e| e.downcast_ref::<HashError>())
+            .is_none());
+    }
+
+    #[test]
+    fn test_hash_file_strict_matches_hash_file() {
+        let dir = crate::test_util::TempDir::new("hash_strict");
+        let path = dir.write("data.bin", &[7u8; 100_000]);
+        assert_eq!(
+            hash_file_strict(&path, HashAlgo::Sha256).unwrap(),
+            hash_file(&path, HashAlgo::Sha256).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_hash_file_strict_with_progress_detects_growth() {
+        use std::io::Write;
+
+        let dir = crate::test_util::TempDir::new("hash_strict_growth");
+        let path = dir.write("app.log", b"first line\n");
+        let mut appended = false;
+        let err = hash_file_strict_with_progress(&path, HashAlgo::Crc32, |_| {
+            if !std::mem::replace(&mut appended, true) {
+                let mut log = fs::OpenOptions::new().append(true).open(&path).unwrap();
+                log.write_all(b"second line\n").unwrap();
+            }
+        })
+        .unwrap_err();
+        assert_eq!(
+            err.get_ref().and_then(|e| e.downcast_ref::<HashError>()),
+            Some(&HashError::Truncated {
+                expected: 11,
+                actual: 23
+            })
+        );
+        assert!(hash_file_strict_with_progress(&path, HashAlgo::Crc32, |_| {}).is_ok());
+    }
+
+    #[test]
+    fn test_read_control_cancels_part_way() {
+        let data = crate::test_util::patterned(3 * crate::hash_buffer_size());
+        let flag = AtomicBool::new(false);
+        let control = ReadControl {
+            cancel: Some(&flag),
+            ..Default::default()
+        };
+        assert_eq!(
+            hash_reader(control.wrap(&data[..]), HashAlgo::Crc32).unwrap(),
+            hash_reader(&data[..], HashAlgo::Crc32).unwrap()
+        );
+
+        let mut chunks = 0;
+        let err = crate::crc32_reader_with_progress(control.wrap(&data[..]), |_| {
+            chunks += 1;
+            flag.store(true, Ordering::Relaxed);
+        })
+        .unwrap_err();
+        assert_eq!(chunks, 1);
+        assert_eq!(err.kind(), io::ErrorKind::Interrupted);
+        assert!(is_cancelled(&err));
+        assert!(control.is_cancelled());
+        let other: io::Error = HashError::IsDirectory(PathBuf::new()).into();
+        assert!(!is_cancelled(&other));
+    }
+
+    #[test]
+    fn test_read_control_reports_reads() {
+        use std::sync::atomic::AtomicUsize;
+
+        static READ: AtomicUsize = AtomicUsize::new(0);
+        let control = ReadControl {
+            on_read: Some(|bytes| {
+                READ.fetch_add(bytes, Ordering::Relaxed);
+            }),
+            ..Default::default()
+        };
+        let data = crate::test_util::patterned(100_000);
+        hash_reader(control.wrap(&data[..]), HashAlgo::Crc32).unwrap();
+        assert_eq!(READ.load(Ordering::Relaxed), 100_000);
+    }
+
+    #[test]
+    fn test_parse_algo_names() {
+        assert_eq!("crc32".parse::<HashAlgo>().unwrap(), HashAlgo::Crc32);
+        assert_eq!("SHA256".parse::<HashAlgo>().unwrap(), HashAlgo::Sha256);
+        let err = "whirlpool".parse::<HashAlgo>().unwrap_err();
+        assert!(err.to_string().contains("whirlpool"));
+    }
+
+    #[test]
+    fn test_hash_file_matches_crc32_path() {
+        let dir = crate::test_util::TempDir::new("hash_file");
+        let path = dir.write("data.bin", b"Hello, World!");
+        let digest = hash_file(&path, HashAlgo::Crc32).unwrap();
+        assert_eq!(digest, Digest::from_u32(crate::crc32_path(&path).unwrap()));
+
+        let mut hasher = HashAlgo::Crc32.hasher();
+        assert_eq!(hash_file_into(&path, hasher.as_mut()).unwrap(), 13);
+        assert_eq!(hasher.finish(), digest);
+    }
+}
@@ -0,0 +1,264 @@
+use crate::hash::HashAlgo;
+use crate::sfv::{parse_sfv, SfvParseError};
+use std::path::Path;
+
+/// How GNU `md5sum`, `sha1sum` and `sha256sum` say a file was read, marked
+/// by the character between the digest and the filename.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HashsumMode {
+    /// `*`: the file was hashed as raw bytes.
+    Binary,
+    /// A space: the file was read in text mode. On Unix this is the same
+    /// as binary; on Windows the tool translated CRLF line endings first.
+    Text,
+}
+
+/// One `<digest> <mode><filename>` line of a coreutils checksum file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HashsumEntry {
+    /// Lowercase hex, as [`crate::Digest::to_hex_lower`] formats it.
+    pub digest: String,
+    pub mode: HashsumMode,
+    pub filename: String,
+}
+
+/// Parse the text of a coreutils checksum file such as `SHA256SUMS`.
+///
+/// Blank lines and `#` comments are skipped, and lines starting with `\`
+/// have their filename escapes (`\\`, `\n`, `\r`) decoded as `sha256sum`
+/// writes them. The digest must be an even number of hex digits.
+///
+/// MTSFV always hashes raw bytes, which matches binary mode everywhere and
+/// text mode on Unix. A text-mode digest made on Windows covers the file with
+/// its line endings translated and will not match, so each text-mode entry
+/// is reported at `warn` level through the `log` crate.
+pub fn parse_hashsum(text: &str) -> Result<Vec<HashsumEntry>, SfvParseError> {
+    let text = text.strip_prefix('\u{feff}').unwrap_or(text);
+    let mut entries = Vec::new();
+    for (index, line) in text.lines().enumerate() {
+        let line = line.trim_end_matches('\r');
+        if line.trim().is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let entry = parse_hashsum_line(line).map_err(|message| SfvParseError {
+            line: index + 1,
+            message,
+        })?;
+        if entry.mode == HashsumMode::Text {
+            log::warn!(
+                "line {}: {} is listed in text mode; it is checked as raw bytes",
+                index + 1,
+                entry.filename
+            );
+        }
+        entries.push(entry);
+    }
+    Ok(entries)
+}
+
+/// Which parser a checksum file needs, as told by [`detect_checksum_format`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChecksumFormat {
+    /// A QuickSFV manifest of CRC32s, for [`crate::SfvManifest::parse`].
+    Sfv,
+    /// A coreutils checksum file of digests made with this algorithm, for
+    /// [`parse_hashsum`].
+    Hashsum(HashAlgo),
+}
+
+/// Tell what kind of checksum file `path`, whose contents are `text`, is.
+///
+/// The extension decides when it is `.sfv`, `.md5`, `.sha1` or `.sha256`,
+/// as do the coreutils names `MD5SUMS`, `SHA1SUMS` and `SHA256SUMS`. Any
+/// other file is judged by its first line that is not blank or a comment:
+/// a coreutils line by the length of its digest, or an SFV entry. `None`
+/// when neither fits.
+pub fn detect_checksum_format(path: &Path, text: &str) -> Option<ChecksumFormat> {
+    let name = path
+        .file_name()
+        .map(|name| name.to_string_lossy().to_ascii_lowercase())
+        .unwrap_or_default();
+    let extension = name.rsplit_once('.').map(|(_, ext)| ext);
+    match (extension, name.as_str()) {
+        (Some("sfv"), _) => return Some(ChecksumFormat::Sfv),
+        (Some("md5"), _) | (_, "md5sums") => return Some(ChecksumFormat::Hashsum(HashAlgo::Md5)),
+        (Some("sha1"), _) | (_, "sha1sums") => {
+            return Some(ChecksumFormat::Hashsum(HashAlgo::Sha1))
+        }
+        (Some("sha256"), _) | (_, "sha256sums") => {
+            return Some(ChecksumFormat::Hashsum(HashAlgo::Sha256))
+        }
+        _ => {}
+    }
+
+    let text = text.strip_prefix('\u{feff}').unwrap_or(text);
+    let line = text
+        .lines()
+        .map(|line| line.trim_end_matches('\r'))
+        .find(|line| !line.trim().is_empty() && !line.starts_with(['#', ';']))?;
+    if let Ok(entry) = parse_hashsum_line(line) {
+        let algo = match entry.digest.len() {
+            32 => HashAlgo::Md5,
+            40 => HashAlgo::Sha1,
+            64 => HashAlgo::Sha256,
+            _ => return None,
+        };
+        return Some(ChecksumFormat::Hashsum(algo));
+    }
+    parse_sfv(line).ok().map(|_| ChecksumFormat::Sfv)
+}
+
+fn parse_hashsum_line(line: &str) -> Result<HashsumEntry, String> {
+    let (escaped, line) = match line.strip_prefix('\\') {
+        Some(rest) => (true, rest),
+        None => (false, line),
+    };
+    let (digest, rest) = line
+        .split_once(' ')
+        .ok_or_else(|| format!("expected '<digest>  <filename>', got '{}'", line))?;
+    if digest.is_empty() || digest.len() % 2 != 0 || !digest.bytes().all(|b| b.is_ascii_hexdigit())
+    {
+        return Err(format!("invalid digest '{}'", digest));
+    }
+    let (mode, filename) = if let Some(name) = rest.strip_prefix('*') {
+        (HashsumMode::Binary, name)
+    } else if let Some(name) = rest.strip_prefix(' ') {
+        (HashsumMode::Text, name)
+    } else {
+        return Err(format!(
+            "expected ' ' or '*' before the filename in '{}'",
+            line
+        ));
+    };
+    if filename.is_empty() {
+        return Err(format!("missing filename after '{}'", digest));
+    }
+    let filename = if escaped {
+        unescape_filename(filename)?
+    } else {
+        filename.to_string()
+    };
+    Ok(HashsumEntry {
+        digest: digest.to_ascii_lowercase(),
+        mode,
+        filename,
+    })
+}
+
+fn unescape_filename(name: &str) -> Result<String, String> {
+    let mut unescaped = String::with_capacity(name.len());
+    let mut chars = name.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            unescaped.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('\\') => unescaped.push('\\'),
+            Some('n') => unescaped.push('\n'),
+            Some('r') => unescaped.push('\r'),
+            other => {
+                return Err(format!(
+                    "invalid escape '\\{}' in '{}'",
+                    other.map(String::from).unwrap_or_default(),
+                    name
+                ))
+            }
+        }
+    }
+    Ok(unescaped)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_hashsum_modes() {
+        let text = "# sha256sum output\n\
+            e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855 *empty.bin\n\
+            D41D8CD98F00B204E9800998ECF8427E  notes one.txt\r\n\
+            \\d41d8cd98f00b204e9800998ecf8427e  dir\\\\two\\nlines.txt\n";
+        let entries = parse_hashsum(text).unwrap();
+        assert_eq!(
+            entries,
+            vec![
+                HashsumEntry {
+                    digest: "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855"
+                        .to_string(),
+                    mode: HashsumMode::Binary,
+                    filename: "empty.bin".to_string(),
+                },
+                HashsumEntry {
+                    digest: "d41d8cd98f00b204e9800998ecf8427e".to_string(),
+                    mode: HashsumMode::Text,
+                    filename: "notes one.txt".to_string(),
+                },
+                HashsumEntry {
+                    digest: "d41d8cd98f00b204e9800998ecf8427e".to_string(),
+                    mode: HashsumMode::Text,
+                    filename: "dir\\two\nlines.txt".to_string(),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_detect_checksum_format() {
+        let detect = |name: &str, text: &str| detect_checksum_format(Path::new(name), text);
+        let md5_line = "d41d8cd98f00b204e9800998ecf8427e *empty.bin\n";
+        let sha1_line = "da39a3ee5e6b4b0d3255bfef95601890afd80709  empty.bin\n";
+
+        // The name wins over the contents.
+        assert_eq!(
+            detect("dir/release.SFV", md5_line),
+            Some(ChecksumFormat::Sfv)
+        );
+        assert_eq!(
+            detect("release.md5", ""),
+            Some(ChecksumFormat::Hashsum(HashAlgo::Md5))
+        );
+        assert_eq!(
+            detect("release.sha1", ""),
+            Some(ChecksumFormat::Hashsum(HashAlgo::Sha1))
+        );
+        assert_eq!(
+            detect("SHA256SUMS", ""),
+            Some(ChecksumFormat::Hashsum(HashAlgo::Sha256))
+        );
+
+        assert_eq!(
+            detect(
+                "checksums.txt",
+                &format!("# made by sha1sum\n\n{sha1_line}")
+            ),
+            Some(ChecksumFormat::Hashsum(HashAlgo::Sha1))
+        );
+        assert_eq!(
+            detect("checksums.txt", md5_line),
+            Some(ChecksumFormat::Hashsum(HashAlgo::Md5))
+        );
+        assert_eq!(
+            detect("checksums.txt", "; header\r\nfile.bin CBF43926\r\n"),
+            Some(ChecksumFormat::Sfv)
+        );
+        assert_eq!(detect("checksums.txt", "abcd *crc16.bin\n"), None);
+        assert_eq!(detect("notes.txt", "just some text\n"), None);
+        assert_eq!(detect("empty.txt", ""), None);
+    }
+
+    #[test]
+    fn test_parse_hashsum_rejects_malformed_lines() {
+        for line in [
+            "d41d8cd98f00b204e9800998ecf8427e",
+            "d41d8cd98f00b204e9800998ecf8427 *odd.bin",
+            "xyz0 *bad.bin",
+            "d41d8cd98f00b204e9800998ecf8427e -no-mode.bin",
+            "d41d8cd98f00b204e9800998ecf8427e *",
+            "\\d41d8cd98f00b204e9800998ecf8427e  bad\\tescape",
+        ] {
+            let err = parse_hashsum(&format!("\n{}\n", line)).unwrap_err();
+            assert_eq!(err.line, 2, "{line}: {err}");
+        }
+    }
+}
@@ -1,9 +1,132 @@
+//! Checksum core shared by the CLI, the GUI and FFI consumers.
+//!
+//! Without the default `std` feature only the in-memory CRC (the [`crc`]
+//! module, [`crc32_bytes`] and `mtsfv_crc32`) and the self-test and version
+//! entry points are built, and the crate is `no_std` with no `alloc`
+//! requirement.
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+#[cfg(feature = "std")]
+use core::ffi::c_char;
+#[cfg(feature = "std")]
+use core::ffi::c_void;
+use core::ffi::{c_int, c_uint};
+use core::slice;
+#[cfg(feature = "std")]
 use crc32fast::Hasher;
-use std::fs::File;
-use std::io::{BufReader, Read};
-use std::os::raw::c_uint;
+#[cfg(feature = "std")]
+use std::ffi::CString;
+#[cfg(feature = "std")]
+use std::io::{BufReader, Read, Seek, SeekFrom};
+#[cfg(feature = "std")]
 use std::path::{Path, PathBuf};
-use std::slice;
+
+pub mod crc;
+
+#[cfg(feature = "std")]
+pub mod archive;
+#[cfg(feature = "std")]
+pub mod batch;
+#[cfg(feature = "std")]
+pub mod compare;
+#[cfg(feature = "std")]
+pub mod display;
+#[cfg(feature = "std")]
+pub mod filelist;
+#[cfg(feature = "std")]
+pub mod hash;
+#[cfg(feature = "std")]
+pub mod hashsum;
+#[cfg(feature = "std")]
+pub mod merge;
+#[cfg(feature = "net")]
+pub mod net;
+#[cfg(feature = "std")]
+pub mod pieces;
+#[cfg(feature = "session")]
+pub mod session;
+#[cfg(feature = "std")]
+pub mod sfv;
+#[cfg(feature = "std")]
+pub mod split;
+#[cfg(feature = "std")]
+pub mod stream;
+#[cfg(feature = "std")]
+pub mod tree;
+#[cfg(feature = "std")]
+pub mod update;
+#[cfg(feature = "std")]
+pub mod verify;
+
+#[cfg(all(test, feature = "std"))]
+mod test_util;
+
+#[cfg(feature = "std")]
+pub use archive::{crc32_gz_path, verify_sfv_in_zip};
+#[cfg(feature = "std")]
+pub use batch::{crc32_batch, crc32_batch_with_len};
+#[cfg(feature = "std")]
+pub use compare::first_difference;
+#[cfg(feature = "std")]
+pub use display::{format_rfc3339, format_size, format_utc};
+#[cfg(feature = "std")]
+pub use hash::{
+    hash_file, hash_file_into, hash_file_multi, hash_file_strict, hash_file_strict_with_progress,
+    hash_file_with_progress, hash_reader, hash_reader_exact, hash_reader_into, hash_reader_multi,
+    hash_reader_with_len, is_cancelled, ControlledReader, Digest, FileHasher, HashAlgo, HashError,
+    ReadControl,
+};
+#[cfg(feature = "std")]
+pub use hashsum::{
+    detect_checksum_format, parse_hashsum, ChecksumFormat, HashsumEntry, HashsumMode,
+};
+#[cfg(feature = "std")]
+pub use merge::{merge_sfv, MergeCollision, MergeError};
+#[cfg(feature = "net")]
+pub use net::{verify_sfv_url, FetchError};
+#[cfg(feature = "std")]
+pub use pieces::{verify_pieces, verify_pieces_reader};
+#[cfg(feature = "session")]
+pub use session::{Session, SessionEntry, SessionState};
+#[cfg(feature = "std")]
+pub use sfv::{
+    parse_sfv, parse_sfv_bytes, read_sfv, relative_path, store_paths, write_sfv, LineEnding,
+    PathStyle, SfvEntry, SfvManifest,
+};
+#[cfg(feature = "std")]
+pub use split::{crc32_split, split_parts, SplitReader};
+#[cfg(feature = "std")]
+pub use stream::StreamVerifier;
+#[cfg(feature = "std")]
+pub use tree::{
+    diff_dirs, hash_tree, hash_tree_filtered, hash_tree_with, is_hidden, walk_files,
+    walk_files_with, walk_files_with_depth, DirDiff, ExtensionFilter, WalkOptions,
+};
+#[cfg(feature = "std")]
+pub use update::{append_to_sfv, update_sfv, update_sfv_with_pruning};
+#[cfg(feature = "std")]
+pub use verify::{
+    find_and_verify, find_entry_ignore_case, find_entry_normalized, find_sfv, resolve_entry_path,
+    safe_entry_path, verify_entries, verify_entries_with, verify_one, verify_one_with, verify_sfv,
+    verify_sfv_with_base, VerifiedEntry, VerifyCounts, VerifyOptions, VerifyResult,
+};
+
+/// Status codes returned by the `c_int` FFI functions.
+pub const MTSFV_OK: c_int = 0;
+pub const MTSFV_ERR_NULL_POINTER: c_int = -1;
+pub const MTSFV_ERR_INVALID_PATH: c_int = -2;
+pub const MTSFV_ERR_IO: c_int = -3;
+pub const MTSFV_ERR_PARSE: c_int = -4;
+
+/// Per-entry statuses passed to the `mtsfv_verify_sfv_cb` callback, one for
+/// each [`VerifyResult`] variant.
+pub const MTSFV_ENTRY_OK: c_int = 0;
+pub const MTSFV_ENTRY_MISMATCH: c_int = 1;
+pub const MTSFV_ENTRY_MISSING: c_int = 2;
+pub const MTSFV_ENTRY_SIZE_MISMATCH: c_int = 3;
+pub const MTSFV_ENTRY_ERROR: c_int = 4;
+pub const MTSFV_ENTRY_REJECTED: c_int = 5;
 
 /// Compute CRC32 checksum for a byte buffer
 /// 
@@ -21,18 +144,24 @@ pub unsafe extern "C" fn mtsfv_crc32(ptr: *const u8, len: usize) -> c_uint {
 
     // Handle empty buffer - return proper CRC32 initial value
     if len == 0 {
-        let hasher = Hasher::new();
-        return hasher.finalize();
+        return crc32_bytes(&[]);
     }
 
     // SAFETY: Caller guarantees valid pointer/len
     let data = unsafe { slice::from_raw_parts(ptr, len) };
-    
-    let mut hasher = Hasher::new();
-    hasher.update(data);
-    hasher.finalize()
+
+    crc32_bytes(data)
+}
+
+/// Compute CRC32 checksum of a buffer already in memory.
+///
+/// The safe counterpart of `mtsfv_crc32` for Rust callers, as [`crc32_path`]
+/// is for files. Available without the `std` feature.
+pub fn crc32_bytes(data: &[u8]) -> u32 {
+    crc::crc32(data)
 }
 
+#[cfg(feature = "std")]
 /// Compute CRC32 checksum for a file given its UTF-16 path
 /// 
 /// # Safety
@@ -53,6 +182,25 @@ pub unsafe extern "C" fn mtsfv_crc32_file(path_ptr: *const u16) -> c_uint {
     }
 
     // SAFETY: Caller guarantees valid null-terminated UTF-16 string
+    let Some(path_buf) = (unsafe { path_from_utf16(path_ptr) }) else {
+        return 0u32;
+    };
+
+    // Open file and compute CRC32
+    compute_file_crc32(&path_buf).unwrap_or_default()
+}
+
+#[cfg(feature = "std")]
+/// Decode a null-terminated UTF-16 path received over FFI.
+///
+/// Returns `None` if the string is not terminated within the Windows
+/// extended path limit of 32,768 characters.
+///
+/// # Safety
+///
+/// `path_ptr` must be non-null and point to a readable UTF-16 string that is
+/// either null-terminated or at least 32,768 units long.
+unsafe fn path_from_utf16(path_ptr: *const u16) -> Option<PathBuf> {
     // We limit the search to prevent potential issues with non-terminated strings
     let path = unsafe {
         const MAX_PATH_LEN: usize = 32768; // Windows MAX_PATH extended limit
@@ -62,7 +210,7 @@ pub unsafe extern "C" fn mtsfv_crc32_file(path_ptr: *const u16) -> c_uint {
         }
         if len >= MAX_PATH_LEN {
             // Path too long or not null-terminated
-            return 0u32;
+            return None;
         }
         slice::from_raw_parts(path_ptr, len)
     };
@@ -73,35 +221,341 @@ pub unsafe extern "C" fn mtsfv_crc32_file(path_ptr: *const u16) -> c_uint {
         use std::os::windows::ffi::OsStringExt;
         PathBuf::from(std::ffi::OsString::from_wide(path))
     };
-    
+
     #[cfg(not(target_os = "windows"))]
     let path_buf = PathBuf::from(String::from_utf16_lossy(path));
 
-    // Open file and compute CRC32
-    match compute_file_crc32(&path_buf) {
-        Ok(crc) => crc,
-        Err(_) => 0u32,
+    Some(path_buf)
+}
+
+#[cfg(feature = "std")]
+/// Verify every entry of an `.sfv` manifest given its UTF-16 path
+///
+/// Filenames in the manifest are resolved relative to the manifest's own
+/// directory. On success the three counters are filled in and `MTSFV_OK` is
+/// returned; `out_failed` counts both CRC mismatches and read errors.
+///
+/// # Safety
+///
+/// The caller must ensure that:
+/// - `sfv_path_ptr` points to a valid, null-terminated UTF-16 string
+/// - `out_ok`, `out_failed` and `out_missing` are valid, writable pointers
+///
+/// # Returns
+///
+/// `MTSFV_OK` on success, `MTSFV_ERR_NULL_POINTER` if any pointer is null,
+/// `MTSFV_ERR_INVALID_PATH` if the path is not terminated, `MTSFV_ERR_IO` if
+/// the manifest cannot be read and `MTSFV_ERR_PARSE` if it is malformed.
+/// The counters are left untouched on error.
+#[no_mangle]
+pub unsafe extern "C" fn mtsfv_verify_sfv(
+    sfv_path_ptr: *const u16,
+    out_ok: *mut u32,
+    out_failed: *mut u32,
+    out_missing: *mut u32,
+) -> c_int {
+    if sfv_path_ptr.is_null() || out_ok.is_null() || out_failed.is_null() || out_missing.is_null()
+    {
+        return MTSFV_ERR_NULL_POINTER;
     }
+
+    // SAFETY: Caller guarantees valid null-terminated UTF-16 string
+    let Some(sfv_path) = (unsafe { path_from_utf16(sfv_path_ptr) }) else {
+        return MTSFV_ERR_INVALID_PATH;
+    };
+
+    let results = match verify_sfv(&sfv_path) {
+        Ok(results) => results,
+        Err(e) if e.kind() == std::io::ErrorKind::InvalidData => return MTSFV_ERR_PARSE,
+        Err(_) => return MTSFV_ERR_IO,
+    };
+    let counts = VerifyCounts::from_results(&results);
+
+    // SAFETY: Caller guarantees the output pointers are valid for writes
+    unsafe {
+        *out_ok = counts.ok;
+        *out_failed = counts.failed;
+        *out_missing = counts.missing;
+    }
+    MTSFV_OK
 }
 
+/// Callback receiving one manifest entry from `mtsfv_verify_sfv_cb`.
+///
+/// `filename` is the entry's name as written in the manifest, as a
+/// null-terminated UTF-16 string that is only valid during the call.
+/// `status` is one of the `MTSFV_ENTRY_*` codes, `expected` is the CRC from
+/// the manifest and `actual` the CRC of the file, or 0 when the file was not
+/// hashed (missing, wrong size, unreadable or outside the manifest's folder).
+#[cfg(feature = "std")]
+pub type MtsfvVerifyCallback = extern "C" fn(
+    filename: *const u16,
+    status: c_int,
+    expected: c_uint,
+    actual: c_uint,
+    user: *mut c_void,
+);
+
+#[cfg(feature = "std")]
+/// Verify every entry of an `.sfv` manifest, reporting each one to `cb`
+///
+/// Like `mtsfv_verify_sfv`, but `cb` is called once per entry, in manifest
+/// order and as soon as that entry has been checked, so a host can fill its
+/// own list while the rest is still being hashed. `user` is passed through
+/// to every call untouched.
+///
+/// # Safety
+///
+/// The caller must ensure that:
+/// - `sfv_path_ptr` points to a valid, null-terminated UTF-16 string
+/// - `cb`, if given, is safe to call with the arguments described on
+///   [`MtsfvVerifyCallback`] and does not unwind
+///
+/// # Returns
+///
+/// `MTSFV_OK` once every entry has been reported, `MTSFV_ERR_NULL_POINTER`
+/// if the path or `cb` is null, `MTSFV_ERR_INVALID_PATH` if the path is not
+/// terminated, `MTSFV_ERR_IO` if the manifest cannot be read and
+/// `MTSFV_ERR_PARSE` if it is malformed. `cb` is never called on error.
+#[no_mangle]
+pub unsafe extern "C" fn mtsfv_verify_sfv_cb(
+    sfv_path_ptr: *const u16,
+    cb: Option<MtsfvVerifyCallback>,
+    user: *mut c_void,
+) -> c_int {
+    let Some(cb) = cb else {
+        return MTSFV_ERR_NULL_POINTER;
+    };
+    if sfv_path_ptr.is_null() {
+        return MTSFV_ERR_NULL_POINTER;
+    }
+
+    // SAFETY: Caller guarantees valid null-terminated UTF-16 string
+    let Some(sfv_path) = (unsafe { path_from_utf16(sfv_path_ptr) }) else {
+        return MTSFV_ERR_INVALID_PATH;
+    };
+
+    let entries = match read_sfv(&sfv_path) {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == std::io::ErrorKind::InvalidData => return MTSFV_ERR_PARSE,
+        Err(_) => return MTSFV_ERR_IO,
+    };
+    let expected: Vec<u32> = entries.iter().map(|entry| entry.crc).collect();
+    let base_dir = sfv_path.parent().unwrap_or_else(|| Path::new(""));
+
+    for (result, expected) in verify_entries(base_dir, entries).zip(expected) {
+        let (status, actual) = match &result {
+            VerifyResult::Ok { crc, .. } => (MTSFV_ENTRY_OK, *crc),
+            VerifyResult::Mismatch { actual, .. } => (MTSFV_ENTRY_MISMATCH, *actual),
+            VerifyResult::Missing { .. } => (MTSFV_ENTRY_MISSING, 0),
+            VerifyResult::SizeMismatch { .. } => (MTSFV_ENTRY_SIZE_MISMATCH, 0),
+            VerifyResult::Error { .. } => (MTSFV_ENTRY_ERROR, 0),
+            VerifyResult::Rejected { .. } => (MTSFV_ENTRY_REJECTED, 0),
+        };
+        let filename: Vec<u16> = result
+            .filename()
+            .encode_utf16()
+            .chain(std::iter::once(0))
+            .collect();
+        cb(filename.as_ptr(), status, expected, actual, user);
+    }
+    MTSFV_OK
+}
+
+#[cfg(feature = "std")]
 /// Internal function to compute CRC32 of a file
 fn compute_file_crc32(path: &Path) -> std::io::Result<u32> {
-    let file = File::open(path)?;
-    let mut reader = BufReader::new(file);
-    let mut hasher = Hasher::new();
-    let mut buffer = [0u8; 65536]; // 64KB buffer
+    crc32_path_buffered(path, &mut vec![0u8; hash_buffer_size()])
+}
 
-    loop {
-        let bytes_read = reader.read(&mut buffer)?;
-        if bytes_read == 0 {
-            break;
-        }
-        hasher.update(&buffer[..bytes_read]);
+/// Default size of the heap buffer each hashing loop reads into; see
+/// [`hash_buffer_size`].
+///
+/// Reads of this size were the fastest on a cached file in `bench_buffer`.
+/// Much larger buffers no longer fit in L2 cache and are slower.
+#[cfg(feature = "std")]
+pub const HASH_BUFFER_SIZE: usize = 256 * 1024;
+
+/// Smallest read buffer accepted by [`crc32_path_with_buffer`] and
+/// `MTSFV_BUFFER_KB`.
+#[cfg(feature = "std")]
+pub const MIN_HASH_BUFFER_SIZE: usize = 4 * 1024;
+
+/// Largest read buffer accepted by [`crc32_path_with_buffer`] and
+/// `MTSFV_BUFFER_KB`.
+#[cfg(feature = "std")]
+pub const MAX_HASH_BUFFER_SIZE: usize = 64 * 1024 * 1024;
+
+/// Size of the read buffer used by the file and reader hashing helpers.
+///
+/// This is `MTSFV_BUFFER_KB` KiB when that environment variable is set to a
+/// size between [`MIN_HASH_BUFFER_SIZE`] and [`MAX_HASH_BUFFER_SIZE`], and
+/// [`HASH_BUFFER_SIZE`] otherwise. Larger reads can help on high-latency
+/// network storage. The variable is read once per process; an invalid value
+/// is reported at `warn` level through the `log` crate and ignored.
+#[cfg(feature = "std")]
+pub fn hash_buffer_size() -> usize {
+    static SIZE: std::sync::OnceLock<usize> = std::sync::OnceLock::new();
+    *SIZE.get_or_init(|| {
+        let Ok(value) = std::env::var("MTSFV_BUFFER_KB") else {
+            return HASH_BUFFER_SIZE;
+        };
+        parse_buffer_kb(&value).unwrap_or_else(|message| {
+            log::warn!("ignoring MTSFV_BUFFER_KB: {}", message);
+            HASH_BUFFER_SIZE
+        })
+    })
+}
+
+/// Parse a buffer size in KiB and check it is within the accepted bounds.
+#[cfg(feature = "std")]
+fn parse_buffer_kb(value: &str) -> Result<usize, String> {
+    let size = value
+        .trim()
+        .parse::<usize>()
+        .ok()
+        .and_then(|kb| kb.checked_mul(1024))
+        .ok_or_else(|| format!("'{}' is not a size in KiB", value))?;
+    check_buffer_size(size)?;
+    Ok(size)
+}
+
+#[cfg(feature = "std")]
+fn check_buffer_size(size: usize) -> Result<(), String> {
+    if (MIN_HASH_BUFFER_SIZE..=MAX_HASH_BUFFER_SIZE).contains(&size) {
+        Ok(())
+    } else {
+        Err(format!(
+            "{} KiB is outside {}-{} KiB",
+            size / 1024,
+            MIN_HASH_BUFFER_SIZE / 1024,
+            MAX_HASH_BUFFER_SIZE / 1024
+        ))
     }
+}
+
+/// Like [`crc32_path`], reading `buf_size` bytes at a time instead of
+/// [`hash_buffer_size`]. Fails with [`std::io::ErrorKind::InvalidInput`]
+/// when `buf_size` is outside [`MIN_HASH_BUFFER_SIZE`] to
+/// [`MAX_HASH_BUFFER_SIZE`].
+#[cfg(feature = "std")]
+pub fn crc32_path_with_buffer(path: impl AsRef<Path>, buf_size: usize) -> std::io::Result<u32> {
+    check_buffer_size(buf_size)
+        .map_err(|message| std::io::Error::new(std::io::ErrorKind::InvalidInput, message))?;
+    crc32_path_buffered(path.as_ref(), &mut vec![0u8; buf_size])
+}
+
+/// Hash a file reading through the caller's `buffer`, so a worker that
+/// hashes many files can allocate it once.
+#[cfg(feature = "std")]
+pub(crate) fn crc32_path_buffered(path: &Path, buffer: &mut [u8]) -> std::io::Result<u32> {
+    crc32_path_buffered_with_progress(path, buffer, |_| {})
+}
+
+#[cfg(feature = "std")]
+pub(crate) fn crc32_path_buffered_with_len(
+    path: &Path,
+    buffer: &mut [u8],
+) -> std::io::Result<(u32, u64)> {
+    let mut len = 0;
+    let crc = crc32_path_buffered_with_progress(path, buffer, |total| len = total)?;
+    Ok((crc, len))
+}
 
+#[cfg(feature = "std")]
+fn crc32_path_buffered_with_progress(
+    path: &Path,
+    buffer: &mut [u8],
+    mut on_progress: impl FnMut(u64),
+) -> std::io::Result<u32> {
+    // Reads are as large as `buffer`, so a `BufReader` would only add a copy.
+    let file = hash::open_file(path)?;
+    let start = std::time::Instant::now();
+    let mut bytes = 0;
+    let crc = crc32_reader_buffered(file, buffer, |total| {
+        bytes = total;
+        on_progress(total);
+    })?;
+    log::trace!("{}: read {} bytes in {:?}", path.display(), bytes, start.elapsed());
+    Ok(crc)
+}
+
+#[cfg(feature = "std")]
+/// Compute CRC32 checksum of everything produced by a reader.
+///
+/// Works with any `Read` source (a decompressor, an archive entry, a network
+/// stream, a `Cursor`) using the same buffered loop as the file helpers.
+pub fn crc32_reader<R: Read>(reader: R) -> std::io::Result<u32> {
+    crc32_reader_with_progress(reader, |_| {})
+}
+
+/// Like [`crc32_reader`], calling `on_progress` with the running byte count
+/// after each chunk is hashed.
+#[cfg(feature = "std")]
+pub fn crc32_reader_with_progress<R: Read>(
+    reader: R,
+    on_progress: impl FnMut(u64),
+) -> std::io::Result<u32> {
+    crc32_reader_buffered(reader, &mut vec![0u8; hash_buffer_size()], on_progress)
+}
+
+/// CRC32 of everything on standard input, read in chunks of
+/// [`hash_buffer_size`] bytes, so a piped stream of any length is hashed
+/// without being held in memory.
+#[cfg(feature = "std")]
+pub fn crc32_stdin() -> std::io::Result<u32> {
+    crc32_stdin_with_progress(|_| {})
+}
+
+/// Like [`crc32_stdin`], calling `on_progress` with the running byte count
+/// after each chunk is hashed.
+#[cfg(feature = "std")]
+pub fn crc32_stdin_with_progress(on_progress: impl FnMut(u64)) -> std::io::Result<u32> {
+    crc32_reader_with_progress(std::io::stdin().lock(), on_progress)
+}
+
+#[cfg(feature = "std")]
+fn crc32_reader_buffered<R: Read>(
+    reader: R,
+    buffer: &mut [u8],
+    mut on_progress: impl FnMut(u64),
+) -> std::io::Result<u32> {
+    let mut hasher = Hasher::new();
+    let mut total = 0u64;
+    hash::for_each_chunk_in(reader, buffer, |chunk| {
+        hasher.update(chunk);
+        total += chunk.len() as u64;
+        on_progress(total);
+    })?;
     Ok(hasher.finalize())
 }
 
+/// Like [`crc32_path`], reporting progress as [`crc32_reader_with_progress`]
+/// does.
+#[cfg(feature = "std")]
+pub fn crc32_path_with_progress(
+    path: impl AsRef<Path>,
+    on_progress: impl FnMut(u64),
+) -> std::io::Result<u32> {
+    crc32_path_buffered_with_progress(
+        path.as_ref(),
+        &mut vec![0u8; hash_buffer_size()],
+        on_progress,
+    )
+}
+
+/// CRC32 of a file together with its length, both from the same read.
+///
+/// The length is the number of bytes hashed rather than a separate
+/// `metadata()` call, so it always describes the data the CRC covers even
+/// if the file changes while it is read. Use it where a size is recorded
+/// next to the CRC, as in a size-annotated manifest.
+#[cfg(feature = "std")]
+pub fn crc32_with_len(path: impl AsRef<Path>) -> std::io::Result<(u32, u64)> {
+    crc32_path_buffered_with_len(path.as_ref(), &mut vec![0u8; hash_buffer_size()])
+}
+
+#[cfg(feature = "std")]
 /// Compute CRC32 checksum for a file path.
 ///
 /// This safe helper is intended for internal callers that already have a `Path`
@@ -110,6 +564,191 @@ pub fn crc32_path(path: impl AsRef<Path>) -> std::io::Result<u32> {
     compute_file_crc32(path.as_ref())
 }
 
+/// Compute the Adler-32 checksum for a file path.
+#[cfg(feature = "std")]
+pub fn adler32_path(path: impl AsRef<Path>) -> std::io::Result<u32> {
+    let reader = hash::open_file(path.as_ref())?;
+    let mut adler = adler2::Adler32::new();
+    hash::for_each_chunk_in(reader, &mut vec![0u8; hash_buffer_size()], |chunk| {
+        adler.write_slice(chunk)
+    })?;
+    Ok(adler.checksum())
+}
+
+/// Compute CRC32 checksum of `len` bytes of a file starting at `offset`, or
+/// of everything from `offset` to the end when `len` is `None`.
+///
+/// A range that runs past the end of the file is truncated there; an offset
+/// past the end yields the CRC of an empty range.
+#[cfg(feature = "std")]
+pub fn crc32_path_range(
+    path: impl AsRef<Path>,
+    offset: u64,
+    len: Option<u64>,
+) -> std::io::Result<u32> {
+    let mut file = hash::open_file(path.as_ref())?;
+    file.seek(SeekFrom::Start(offset))?;
+    let reader = BufReader::new(file);
+    match len {
+        Some(len) => crc32_reader(reader.take(len)),
+        None => crc32_reader(reader),
+    }
+}
+
+#[cfg(feature = "std")]
+/// Compute CRC32 checksum of a byte range of a file given its UTF-16 path
+///
+/// Hashes `len` bytes starting at `offset`, as [`crc32_path_range`] does, so
+/// a host can check part of a file (a header, one piece) without reading all
+/// of it. A `len` of `u64::MAX` means "to the end of the file". A range that
+/// runs past the end is cut off there, and an `offset` at or past the end
+/// succeeds with the CRC of an empty range, 0.
+///
+/// # Safety
+///
+/// The caller must ensure that:
+/// - `path_ptr` points to a valid, null-terminated UTF-16 string
+/// - `out_crc` is a valid, writable pointer
+///
+/// # Returns
+///
+/// `MTSFV_OK` with the CRC in `*out_crc`, `MTSFV_ERR_NULL_POINTER` if either
+/// pointer is null, `MTSFV_ERR_INVALID_PATH` if the path is not terminated
+/// and `MTSFV_ERR_IO` if the file cannot be opened or read. `*out_crc` is
+/// left untouched on error.
+#[no_mangle]
+pub unsafe extern "C" fn mtsfv_crc32_file_range(
+    path_ptr: *const u16,
+    offset: u64,
+    len: u64,
+    out_crc: *mut c_uint,
+) -> c_int {
+    if path_ptr.is_null() || out_crc.is_null() {
+        return MTSFV_ERR_NULL_POINTER;
+    }
+
+    // SAFETY: Caller guarantees valid null-terminated UTF-16 string
+    let Some(path) = (unsafe { path_from_utf16(path_ptr) }) else {
+        return MTSFV_ERR_INVALID_PATH;
+    };
+
+    let len = (len != u64::MAX).then_some(len);
+    match crc32_path_range(&path, offset, len) {
+        Ok(crc) => {
+            // SAFETY: Caller guarantees `out_crc` is valid for writes
+            unsafe { *out_crc = crc };
+            MTSFV_OK
+        }
+        Err(_) => MTSFV_ERR_IO,
+    }
+}
+
+/// Compute the Adler-32 checksum for a byte buffer
+///
+/// # Safety
+///
+/// Same requirements as `mtsfv_crc32`: `ptr` must point to at least `len`
+/// readable bytes for the duration of the call. A null `ptr` returns 1, the
+/// Adler-32 of empty input.
+#[cfg(feature = "std")]
+#[no_mangle]
+pub unsafe extern "C" fn mtsfv_adler32(ptr: *const u8, len: usize) -> c_uint {
+    if ptr.is_null() || len == 0 {
+        return adler2::adler32_slice(&[]);
+    }
+
+    // SAFETY: Caller guarantees valid pointer/len
+    let data = unsafe { slice::from_raw_parts(ptr, len) };
+    adler2::adler32_slice(data)
+}
+
+/// Compute the Adler-32 checksum for a file given its UTF-16 path
+///
+/// # Safety
+///
+/// Same requirements as `mtsfv_crc32_file`.
+///
+/// # Returns
+///
+/// The checksum on success, or 0 on error (never a valid Adler-32, whose
+/// low half is at least 1).
+#[cfg(feature = "std")]
+#[no_mangle]
+pub unsafe extern "C" fn mtsfv_adler32_file(path_ptr: *const u16) -> c_uint {
+    if path_ptr.is_null() {
+        return 0u32;
+    }
+
+    // SAFETY: Caller guarantees valid null-terminated UTF-16 string
+    let Some(path_buf) = (unsafe { path_from_utf16(path_ptr) }) else {
+        return 0u32;
+    };
+
+    adler32_path(&path_buf).unwrap_or_default()
+}
+
+/// Known-answer vectors checked by `mtsfv_self_test` and the unit tests.
+const KNOWN_ANSWERS: [(&[u8], u32); 3] = [
+    (b"123456789", 0xCBF43926),
+    (b"", 0x00000000),
+    (b"Hello, World!", 0xEC4AC3D0),
+];
+
+/// Check the CRC implementation against built-in known-answer vectors
+///
+/// Intended as a cheap sanity check right after loading the library.
+///
+/// # Returns
+///
+/// `MTSFV_OK` if every vector matches, otherwise the 1-based index of the
+/// first failing vector (1 = `"123456789"`, 2 = empty input,
+/// 3 = `"Hello, World!"`).
+#[no_mangle]
+pub extern "C" fn mtsfv_self_test() -> c_int {
+    for (index, (data, expected)) in KNOWN_ANSWERS.iter().enumerate() {
+        if crc32_bytes(data) != *expected {
+            return index as c_int + 1;
+        }
+    }
+    MTSFV_OK
+}
+
+#[cfg(feature = "std")]
+/// Format a CRC32 as 8 uppercase hex digits, the way the CLI and GUI show it
+///
+/// # Ownership
+///
+/// The returned null-terminated string is allocated by this library. The
+/// caller owns it and must release it with `mtsfv_free_string`, exactly
+/// once, and not with `free` or `delete`.
+///
+/// # Returns
+///
+/// A pointer to a string such as `"CBF43926"`. It is never null.
+#[no_mangle]
+pub extern "C" fn mtsfv_crc32_hex(crc: c_uint) -> *mut c_char {
+    CString::new(format!("{:08X}", crc))
+        .expect("hex digits contain no NUL")
+        .into_raw()
+}
+
+#[cfg(feature = "std")]
+/// Release a string returned by `mtsfv_crc32_hex`
+///
+/// # Safety
+///
+/// `ptr` must be null (nothing is freed) or a pointer obtained from
+/// `mtsfv_crc32_hex` that has not already been freed. It must not be used
+/// afterwards.
+#[no_mangle]
+pub unsafe extern "C" fn mtsfv_free_string(ptr: *mut c_char) {
+    if ptr.is_null() {
+        return;
+    }
+    // SAFETY: Caller guarantees `ptr` came from `CString::into_raw` above
+    drop(unsafe { CString::from_raw(ptr) });
+}
+
 /// Version information
 ///
 /// # Safety
@@ -118,46 +757,185 @@ pub fn crc32_path(path: impl AsRef<Path>) -> std::io::Result<u32> {
 /// must treat it as read-only and must not free or mutate the pointed-to memory.
 #[no_mangle]
 pub unsafe extern "C" fn mtsfv_version() -> *const u8 {
-    "0.1.0\0".as_ptr()
+    concat!(env!("CARGO_PKG_VERSION"), "\0").as_ptr()
+}
+
+/// Numeric version components, from the same manifest version as
+/// `mtsfv_version`
+///
+/// # Safety
+///
+/// `major`, `minor` and `patch` must be valid, writable pointers.
+///
+/// # Returns
+///
+/// `MTSFV_OK`, or `MTSFV_ERR_NULL_POINTER` (writing nothing) if any pointer
+/// is null.
+#[no_mangle]
+pub unsafe extern "C" fn mtsfv_version_parts(
+    major: *mut u32,
+    minor: *mut u32,
+    patch: *mut u32,
+) -> c_int {
+    if major.is_null() || minor.is_null() || patch.is_null() {
+        return MTSFV_ERR_NULL_POINTER;
+    }
+
+    // SAFETY: Caller guarantees the output pointers are valid for writes
+    unsafe {
+        *major = version_component(env!("CARGO_PKG_VERSION_MAJOR"));
+        *minor = version_component(env!("CARGO_PKG_VERSION_MINOR"));
+        *patch = version_component(env!("CARGO_PKG_VERSION_PATCH"));
+    }
+    MTSFV_OK
+}
+
+fn version_component(text: &str) -> u32 {
+    text.parse().unwrap_or_default()
 }
 
-#[cfg(test)]
+#[cfg(all(test, feature = "std"))]
 mod tests {
     use super::*;
 
-    fn crc(ptr: *const u8, len: usize) -> u32 {
-        unsafe { mtsfv_crc32(ptr, len) }
+    #[test]
+    fn test_crc32_path_range() {
+        let dir = test_util::TempDir::new("range");
+        let mut data = b"HEAD".to_vec();
+        data.resize(70_004, b'x');
+        data.extend_from_slice(b"123456789TRAILER");
+        let path = dir.write("data.bin", &data);
+
+        let body = data.len() as u64 - 16;
+        assert_eq!(crc32_path_range(&path, body, Some(9)).unwrap(), 0xCBF43926);
+        assert_eq!(
+            crc32_path_range(&path, 4, Some(70_000)).unwrap(),
+            crc32_reader(&data[4..70_004]).unwrap()
+        );
+        assert_eq!(
+            crc32_path_range(&path, 0, None).unwrap(),
+            crc32_path(&path).unwrap()
+        );
+        assert_eq!(
+            crc32_path_range(&path, body + 9, Some(1_000)).unwrap(),
+            crc32_reader(&b"TRAILER"[..]).unwrap()
+        );
+        assert_eq!(crc32_path_range(&path, 1 << 40, None).unwrap(), 0);
     }
 
     #[test]
     fn test_crc32_empty() {
-        let result = crc(std::ptr::null(), 0);
+        // SAFETY: a null pointer is rejected before it is read
+        let result = unsafe { mtsfv_crc32(std::ptr::null(), 0) };
         assert_eq!(result, 0);
     }
 
     #[test]
     fn test_crc32_known_vectors() {
         // Test vector: "123456789" should produce 0xCBF43926
-        let data = b"123456789";
-        let result = crc(data.as_ptr(), data.len());
-        assert_eq!(result, 0xCBF43926);
+        let (data, expected) = KNOWN_ANSWERS[0];
+        let result = crc32_bytes(data);
+        assert_eq!(result, expected);
     }
 
     #[test]
     fn test_crc32_empty_string() {
-        let data = b"";
-        let result = crc(data.as_ptr(), data.len());
+        let (data, expected) = KNOWN_ANSWERS[1];
+        let result = crc32_bytes(data);
         // Empty buffer should return proper CRC32 initial value (0x00000000)
-        assert_eq!(result, 0x00000000);
+        assert_eq!(result, expected);
     }
 
     #[test]
     fn test_crc32_hello_world() {
         // Test with common string
-        let data = b"Hello, World!";
-        let result = crc(data.as_ptr(), data.len());
+        let (data, expected) = KNOWN_ANSWERS[2];
+        let result = crc32_bytes(data);
         // Pre-computed CRC32 for "Hello, World!"
-        assert_eq!(result, 0xEC4AC3D0);
+        assert_eq!(result, expected);
+    }
+
+    #[test]
+    fn test_crc32_hex_round_trip() {
+        for (crc, expected) in [(0xCBF43926, "CBF43926"), (0x1A, "0000001A")] {
+            let ptr = mtsfv_crc32_hex(crc);
+            assert!(!ptr.is_null());
+            let text = unsafe { std::ffi::CStr::from_ptr(ptr) }.to_str().unwrap();
+            assert_eq!(text, expected);
+            unsafe { mtsfv_free_string(ptr) };
+        }
+        unsafe { mtsfv_free_string(std::ptr::null_mut()) };
+    }
+
+    #[test]
+    fn test_version_matches_parts() {
+        let version = unsafe { std::ffi::CStr::from_ptr(mtsfv_version().cast()) };
+        let (mut major, mut minor, mut patch) = (0, 0, 0);
+        let status = unsafe { mtsfv_version_parts(&mut major, &mut minor, &mut patch) };
+        assert_eq!(status, MTSFV_OK);
+        assert_eq!(
+            version.to_str().unwrap(),
+            format!("{major}.{minor}.{patch}")
+        );
+        assert_eq!(version.to_str().unwrap(), env!("CARGO_PKG_VERSION"));
+
+        let status = unsafe { mtsfv_version_parts(std::ptr::null_mut(), &mut minor, &mut patch) };
+        assert_eq!(status, MTSFV_ERR_NULL_POINTER);
+    }
+
+    #[test]
+    fn test_adler32_vectors() {
+        let data = b"Wikipedia";
+        assert_eq!(
+            unsafe { mtsfv_adler32(data.as_ptr(), data.len()) },
+            0x11E60398
+        );
+        assert_eq!(unsafe { mtsfv_adler32(std::ptr::null(), 0) }, 1);
+
+        let dir = test_util::TempDir::new("adler32");
+        let path = dir.write("wiki.txt", data);
+        assert_eq!(adler32_path(&path).unwrap(), 0x11E60398);
+        let wide = to_utf16(&path);
+        assert_eq!(unsafe { mtsfv_adler32_file(wide.as_ptr()) }, 0x11E60398);
+    }
+
+    #[test]
+    fn test_crc32_reader_with_progress_reports_running_total() {
+        let chunk = hash_buffer_size() as u64;
+        let data = vec![0x5Au8; 2 * hash_buffer_size() + 18_000];
+        let mut seen = Vec::new();
+        let crc = crc32_reader_with_progress(data.as_slice(), |total| seen.push(total)).unwrap();
+        assert_eq!(crc, crc32_reader(data.as_slice()).unwrap());
+        assert_eq!(seen, vec![chunk, 2 * chunk, 2 * chunk + 18_000]);
+    }
+
+    #[test]
+    fn test_buffer_size_does_not_change_crc() {
+        let dir = test_util::TempDir::new("buffer_size");
+        let data = test_util::patterned(1_000_003);
+        let path = dir.write("data.bin", &data);
+        let expected = crc32_bytes(&data);
+        for size in [MIN_HASH_BUFFER_SIZE, 65_536, 100_000, 4 << 20] {
+            assert_eq!(crc32_path_with_buffer(&path, size).unwrap(), expected, "{size}");
+        }
+        for size in [0, MIN_HASH_BUFFER_SIZE - 1, MAX_HASH_BUFFER_SIZE + 1] {
+            let err = crc32_path_with_buffer(&path, size).unwrap_err();
+            assert_eq!(err.kind(), std::io::ErrorKind::InvalidInput, "{size}");
+        }
+    }
+
+    #[test]
+    fn test_parse_buffer_kb() {
+        assert_eq!(parse_buffer_kb("64"), Ok(64 * 1024));
+        assert_eq!(parse_buffer_kb(" 4096\n"), Ok(4 << 20));
+        for value in ["", "64k", "-1", "2", "1000000", "99999999999999999999"] {
+            assert!(parse_buffer_kb(value).is_err(), "{value}");
+        }
+    }
+
+    #[test]
+    fn test_self_test_passes() {
+        assert_eq!(mtsfv_self_test(), MTSFV_OK);
     }
 
     #[test]
@@ -188,12 +966,12 @@ mod tests {
         let data: [u8; 9] = [0x1a, 0x2b, 0x3c, 0x4f, 0x5a, 0x6b, 0x7c, 0x8d, 0x9e];
         
         // Full array should produce 0xb0c3bbc7
-        let result = crc(data.as_ptr(), data.len());
+        let result = crc32_bytes(&data);
         assert_eq!(result, 0xb0c3bbc7, 
             "Full array CRC32 mismatch: expected 0xb0c3bbc7, got 0x{:08x}", result);
         
         // First 5 bytes should produce 0x4a6fa7d5
-        let result5 = crc(data.as_ptr(), 5);
+        let result5 = crc32_bytes(&data[..5]);
         assert_eq!(result5, 0x4a6fa7d5,
             "First 5 bytes CRC32 mismatch: expected 0x4a6fa7d5, got 0x{:08x}", result5);
         
@@ -214,6 +992,67 @@ mod tests {
         assert_eq!(result, 0x00000000);
     }
 
+    #[test]
+    fn test_crc32_reader_cursor() {
+        use std::io::Cursor;
+
+        let small = crc32_reader(Cursor::new(b"123456789".to_vec())).unwrap();
+        assert_eq!(small, 0xCBF43926);
+
+        // Larger than one buffered read must match the one-shot buffer CRC
+        let big = test_util::patterned(600_000);
+        let expected = crc32_bytes(&big);
+        assert_eq!(crc32_reader(Cursor::new(big)).unwrap(), expected);
+
+        assert_eq!(crc32_reader(Cursor::new(Vec::new())).unwrap(), 0);
+    }
+
+    #[test]
+    fn test_crc32_reader_injected_faults() {
+        use std::io::ErrorKind;
+        use test_util::{Fault, FaultyReader};
+
+        let data = test_util::patterned(600_000);
+        let short = FaultyReader::new(&data[..], Fault::ShortReads(1_000));
+        assert_eq!(crc32_reader(short).unwrap(), crc32_bytes(&data));
+
+        let early = FaultyReader::new(&data[..], Fault::EofAt(300_000));
+        assert_eq!(crc32_reader(early).unwrap(), crc32_bytes(&data[..300_000]));
+
+        let mut seen = 0;
+        let failing = FaultyReader::new(&data[..], Fault::ErrorAt(300_000, ErrorKind::Other));
+        let err = crc32_reader_with_progress(failing, |total| seen = total).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::Other);
+        assert_eq!(seen, 300_000);
+    }
+
+    #[test]
+    fn test_crc32_stdin_reads_in_bounded_chunks() {
+        use std::io::Cursor;
+
+        // The loop crc32_stdin runs over a locked stdin, fed from a Cursor.
+        let data: Vec<u8> = (0..1_000_000u32).map(|i| (i % 253) as u8).collect();
+        let mut seen = vec![0];
+        let crc = crc32_reader_with_progress(Cursor::new(&data), |total| seen.push(total)).unwrap();
+        assert_eq!(crc, crc32_bytes(&data));
+        assert_eq!(seen.last(), Some(&(data.len() as u64)));
+        assert!(seen.windows(2).all(|w| w[1] - w[0] <= hash_buffer_size() as u64));
+    }
+
+    #[test]
+    fn test_crc32_with_len() {
+        let dir = test_util::TempDir::new("crc32_with_len");
+        let data = test_util::patterned(600_000);
+        let path = dir.write("data.bin", &data);
+        assert_eq!(
+            crc32_with_len(&path).unwrap(),
+            (crc32_bytes(&data), data.len() as u64)
+        );
+        let empty = dir.write("empty.bin", b"");
+        assert_eq!(crc32_with_len(&empty).unwrap(), (0, 0));
+        assert!(crc32_with_len(dir.path().join("missing.bin")).is_err());
+    }
+
     #[test]
     fn test_crc32_path_helper() {
         use std::fs::File;
@@ -243,4 +1082,115 @@ mod tests {
         let crc = crc32_path(&tmp_path).expect("crc32 calculation");
         assert_eq!(crc, 0xCBF43926);
     }
+
+    fn to_utf16(path: &Path) -> Vec<u16> {
+        path.to_string_lossy()
+            .encode_utf16()
+            .chain(std::iter::once(0))
+            .collect()
+    }
+
+    #[test]
+    fn test_verify_sfv_ffi_counts() {
+        let dir = test_util::TempDir::new("ffi_verify");
+        dir.write("good.txt", b"123456789");
+        dir.write("bad.txt", b"123456789");
+        let sfv = dir.write(
+            "check.sfv",
+            b"good.txt CBF43926\nbad.txt 00000000\nmissing.txt CBF43926\n",
+        );
+        let wide = to_utf16(&sfv);
+
+        let (mut ok, mut failed, mut missing) = (0u32, 0u32, 0u32);
+        let status = unsafe { mtsfv_verify_sfv(wide.as_ptr(), &mut ok, &mut failed, &mut missing) };
+        assert_eq!(status, MTSFV_OK);
+        assert_eq!((ok, failed, missing), (1, 1, 1));
+
+        let status = unsafe {
+            mtsfv_verify_sfv(wide.as_ptr(), std::ptr::null_mut(), &mut failed, &mut missing)
+        };
+        assert_eq!(status, MTSFV_ERR_NULL_POINTER);
+
+        let absent = to_utf16(&dir.path().join("absent.sfv"));
+        let status = unsafe { mtsfv_verify_sfv(absent.as_ptr(), &mut ok, &mut failed, &mut missing) };
+        assert_eq!(status, MTSFV_ERR_IO);
+    }
+
+    #[test]
+    fn test_crc32_file_range_ffi() {
+        let dir = test_util::TempDir::new("ffi_range");
+        let path = dir.write("data.bin", b"HEAD123456789");
+        let wide = to_utf16(&path);
+
+        let mut crc = 0;
+        let status = unsafe { mtsfv_crc32_file_range(wide.as_ptr(), 4, 9, &mut crc) };
+        assert_eq!((status, crc), (MTSFV_OK, 0xCBF43926));
+        let status = unsafe { mtsfv_crc32_file_range(wide.as_ptr(), 4, u64::MAX, &mut crc) };
+        assert_eq!((status, crc), (MTSFV_OK, 0xCBF43926));
+        let status = unsafe { mtsfv_crc32_file_range(wide.as_ptr(), 100, 9, &mut crc) };
+        assert_eq!((status, crc), (MTSFV_OK, 0));
+
+        let status = unsafe { mtsfv_crc32_file_range(wide.as_ptr(), 0, 4, std::ptr::null_mut()) };
+        assert_eq!(status, MTSFV_ERR_NULL_POINTER);
+        let absent = to_utf16(&dir.path().join("absent.bin"));
+        crc = 1;
+        let status = unsafe { mtsfv_crc32_file_range(absent.as_ptr(), 0, 4, &mut crc) };
+        assert_eq!((status, crc), (MTSFV_ERR_IO, 1));
+    }
+
+    extern "C" fn collect_entry(
+        filename: *const u16,
+        status: c_int,
+        expected: c_uint,
+        actual: c_uint,
+        user: *mut c_void,
+    ) {
+        // SAFETY: the tests pass a `Vec` of reported entries as `user`, and
+        // `filename` is null-terminated
+        let (seen, name) = unsafe {
+            let len = (0..).take_while(|&i| *filename.add(i) != 0).count();
+            (
+                &mut *user.cast::<Vec<(String, c_int, u32, u32)>>(),
+                String::from_utf16_lossy(slice::from_raw_parts(filename, len)),
+            )
+        };
+        seen.push((name, status, expected, actual));
+    }
+
+    #[test]
+    fn test_verify_sfv_cb_reports_each_entry() {
+        let dir = test_util::TempDir::new("ffi_verify_cb");
+        dir.write("good.txt", b"123456789");
+        dir.write("bad.txt", b"Hello, World!");
+        dir.write("short.txt", b"1234");
+        let sfv = dir.write(
+            "check.sfv",
+            "good.txt CBF43926\nbad.txt CBF43926\nmissing.txt 0000001A\n\
+             ; size 9\nshort.txt 9BE3E0A3\nnaïve.txt 00000000\n"
+                .as_bytes(),
+        );
+        let wide = to_utf16(&sfv);
+
+        let mut seen: Vec<(String, c_int, u32, u32)> = Vec::new();
+        let user = (&mut seen as *mut Vec<_>).cast::<c_void>();
+        let status = unsafe { mtsfv_verify_sfv_cb(wide.as_ptr(), Some(collect_entry), user) };
+        assert_eq!(status, MTSFV_OK);
+        assert_eq!(
+            seen,
+            vec![
+                ("good.txt".to_string(), MTSFV_ENTRY_OK, 0xCBF43926, 0xCBF43926),
+                ("bad.txt".to_string(), MTSFV_ENTRY_MISMATCH, 0xCBF43926, 0xEC4AC3D0),
+                ("missing.txt".to_string(), MTSFV_ENTRY_MISSING, 0x1A, 0),
+                ("short.txt".to_string(), MTSFV_ENTRY_SIZE_MISMATCH, 0x9BE3E0A3, 0),
+                ("naïve.txt".to_string(), MTSFV_ENTRY_MISSING, 0, 0),
+            ]
+        );
+
+        let status = unsafe { mtsfv_verify_sfv_cb(wide.as_ptr(), None, user) };
+        assert_eq!(status, MTSFV_ERR_NULL_POINTER);
+        let absent = to_utf16(&dir.path().join("absent.sfv"));
+        let status = unsafe { mtsfv_verify_sfv_cb(absent.as_ptr(), Some(collect_entry), user) };
+        assert_eq!(status, MTSFV_ERR_IO);
+        assert_eq!(seen.len(), 5);
+    }
 }
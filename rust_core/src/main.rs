@@ -1,48 +1,2008 @@
 use mtsfv_core::*;
+use serde_json::json;
+use std::collections::{BTreeMap, HashSet};
 use std::env;
 use std::fs;
-use std::io::{self, Read};
+use std::io::{self, BufReader, Read, Write};
+use std::path::{Path, PathBuf};
+use std::process;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, Instant, SystemTime};
+
+/// Every input was hashed (and, with `--verify`, matched its manifest entry).
+const EXIT_OK: i32 = 0;
+/// At least one `--verify` entry mismatched or was missing.
+const EXIT_MISMATCH: i32 = 1;
+/// At least one input, file list or manifest could not be read.
+const EXIT_IO_ERROR: i32 = 2;
+/// The command line was invalid.
+const EXIT_USAGE: i32 = 64;
+/// Ctrl+C stopped the run; what was checked before it is still reported.
+const EXIT_INTERRUPTED: i32 = 130;
+
+/// How digests are printed; `None` in `Options` means the algorithm's
+/// conventional style.
+#[derive(Clone, Copy)]
+enum DigestFormat {
+    Upper,
+    Lower,
+    Colon,
+}
+
+impl DigestFormat {
+    fn parse(name: &str) -> Result<Self, String> {
+        match name.to_ascii_lowercase().as_str() {
+            "upper" => Ok(DigestFormat::Upper),
+            "lower" => Ok(DigestFormat::Lower),
+            "colon" => Ok(DigestFormat::Colon),
+            _ => Err(format!(
+                "unknown format '{}' (expected one of: upper, lower, colon)",
+                name
+            )),
+        }
+    }
+}
+
+struct Options {
+    algo: HashAlgo,
+    format: Option<DigestFormat>,
+    stdin: bool,
+    files: Vec<String>,
+    files_from: Option<String>,
+    verify: Option<String>,
+    verify_stdin: bool,
+    /// `--check-manifest <file.sfv>`: parse and look for the files, no hashing.
+    check_manifest: Option<String>,
+    /// `--report <file.json>`: write a JSON record of a verify run.
+    report: Option<String>,
+    /// `--resume <statefile>`: skip entries a previous verify run recorded
+    /// as OK, and record this run's.
+    resume: Option<String>,
+    base_dir: Option<String>,
+    /// `--relative-to[=<dir>]`: print hashed files' paths relative to `<dir>`,
+    /// the current directory by default.
+    relative_to: Option<String>,
+    /// `--expect <HEX> <file>`: the CRC32 to compare against and the file.
+    expect: Option<(u32, String)>,
+    /// `--create <file.sfv>`: write the hashed files to a manifest.
+    create: Option<String>,
+    /// `--no-clobber`: leave an existing `--create` manifest alone and fail.
+    no_clobber: bool,
+    /// `--append`: merge into an existing `--create` manifest, as
+    /// `update_sfv` does, rather than replacing it.
+    append: bool,
+    /// `--paths <style>`: how `--create` stores filenames; relative to the
+    /// manifest's folder when not given.
+    path_style: Option<PathStyle>,
+    /// `--sizes`: record each file's size in the `--create` manifest.
+    sizes: bool,
+    /// `--comment <text>`: header lines for the `--create` manifest, in order.
+    comments: Vec<String>,
+    /// `--deep-diff <reference> <file>`: the known-good copy and the file.
+    deep_diff: Option<(String, String)>,
+    null_separated: bool,
+    json: bool,
+    /// `--bare`: print only each digest, one line per input in input order.
+    bare: bool,
+    timing: bool,
+    strict: bool,
+    glob: bool,
+    recursive: bool,
+    fail_fast: bool,
+    /// `--split`: treat each name as the base of `name.001`, `name.002`, …
+    split: bool,
+    /// `--ignore-case`: retry missing manifest entries ignoring case.
+    ignore_case: bool,
+    /// `--allow-escape`: verify entries that lead outside the manifest's
+    /// folder, or let `--create` store such entries.
+    allow_escape: bool,
+    /// `--workers N`: files hashed at once; results print as they finish.
+    workers: usize,
+    /// `--rate-limit <MB/s>`: cap on total read throughput, in bytes/s.
+    rate_limit: Option<u64>,
+    /// `--errors-only`: print failures (and the summary) but not successes.
+    errors_only: bool,
+    /// `--quiet`: no closing summary line.
+    quiet: bool,
+    /// `-v` / `-vv`: diagnostics on stderr at debug / trace level.
+    verbosity: u8,
+    /// `--include-ext` / `--exclude-ext`, applied to files found by `--recursive`.
+    extensions: ExtensionFilter,
+    /// `--skip-hidden`: leave hidden files and directories out of `--recursive`.
+    skip_hidden: bool,
+}
+
+/// Result of hashing one input.
+struct Hashed {
+    digest: Digest,
+    bytes: u64,
+    /// Time spent hashing, excluding opening the file and printing.
+    elapsed: Duration,
+}
+
+fn print_usage(program: &str) {
+    println!(
+        "Usage: {} [--algo <name>] <file_path> [file_path...]",
+        program
+    );
+    println!("       or");
+    println!("       {} [--algo <name>] --stdin", program);
+    println!("       or");
+    println!("       {} --verify <file.sfv> [--base-dir <dir>]", program);
+    println!("       or");
+    println!("       {} --verify-stdin [--base-dir <dir>]", program);
+    println!("       or");
+    println!("       {} --check-manifest <file.sfv>", program);
+    println!("       or");
+    println!("       {} --expect <crc32> <file_path>", program);
+    println!("       or");
+    println!("       {} --deep-diff <reference> <file_path>", program);
+    println!();
+    println!("Options:");
+    println!(
+        "  --algo <name>   Hash function: crc32 (default), crc32c, adler32, md5, sha1, sha256"
+    );
+    println!("  --format <style>");
+    println!("                  Digest style: upper, lower or colon (default: upper for crc32,");
+    println!("                  crc32c and adler32, lower for md5 and the sha family)");
+    println!("  --files-from <list>");
+    println!(
+        "                  Also hash every path listed in <list>, one per line ('-' for stdin)"
+    );
+    println!("  --null          Paths in the --files-from list are NUL-separated");
+    println!("  --relative-to[=<dir>]");
+    println!("                  Print each hashed file's path relative to <dir> (default: the");
+    println!("                  current directory), in text and --json output; a path that");
+    println!("                  cannot be made relative is printed absolute. Without the flag,");
+    println!("                  paths are printed as given");
+    println!("  --json          Print one JSON object per input or manifest entry instead of");
+    println!("                  text, then a {{\"summary\": ...}} object unless --quiet is given");
+    println!("  --timing        With --json, add bytes, elapsed_ms and mb_per_sec fields");
+    println!("  --bare          Print only the digest, one line per input in input order");
+    println!("                  whatever --workers is, with an empty line for an input that");
+    println!("                  could not be read (its error goes to stderr); no summary");
+    println!(
+        "  --glob          Expand '*', '?' and '[...]' in file arguments (for shells that don't)"
+    );
+    println!("  --recursive     Hash every file under directory arguments");
+    println!("  --include-ext <list>");
+    println!("                  With --recursive, only hash files with these comma-separated");
+    println!("                  extensions (case-insensitive; files without one are skipped)");
+    println!("  --exclude-ext <list>");
+    println!("                  With --recursive, skip files with these extensions");
+    println!("  --skip-hidden   With --recursive, skip hidden files and directories (names");
+    println!("                  starting with '.' on Unix, the hidden or system attribute on");
+    println!("                  Windows); by default they are hashed like any other file");
+    println!("  --strict        Fail a file whose size changes while it is being hashed");
+    println!("  --split         Hash the numbered parts name.001, name.002, ... of each file");
+    println!("                  argument as one joined file; with --verify, do so for entries");
+    println!("                  whose file is missing");
+    println!("  --ignore-case   When verifying, check a file whose name differs from a missing");
+    println!("                  entry only in case, with a warning");
+    println!("  --allow-escape  When verifying, also open entries that lead outside the");
+    println!("                  manifest's folder ('../x', absolute paths elsewhere), which");
+    println!("                  are otherwise REJECTED; only for manifests you trust. With");
+    println!("                  --create, store files outside the manifest's folder, which");
+    println!("                  is otherwise refused because --verify would reject them");
+    println!("  --workers <n>   Hash up to <n> files at once (default: one per CPU). Results,");
+    println!("                  including --json records, print as each file finishes, so");
+    println!("                  their order can vary; --workers 1 keeps argument order");
+    println!("  --rate-limit <MB/s>");
+    println!("                  Read at most this many MB (MiB) per second in total, leaving");
+    println!("                  disk bandwidth for other programs (default: no limit)");
+    println!("  --fail-fast     Stop at the first mismatch, missing file or read error");
+    println!("                  (default: check everything, then exit nonzero)");
+    println!("  --create <file.sfv>");
+    println!("                  Also write the CRC32 of every hashed file to an SFV manifest,");
+    println!("                  replacing the file if it already exists. SFV only holds");
+    println!("                  CRC32, so --algo must be crc32 (the default)");
+    println!("  --no-clobber    With --create, fail without hashing if the manifest exists");
+    println!("  --append        With --create, merge into an existing manifest: entries for");
+    println!("                  files hashed again get their new CRC, the others are kept,");
+    println!("                  and new files are added at the end");
+    println!("  --paths <style> With --create, store filenames relative to the manifest's");
+    println!("                  folder (relative, the default, as QuickSFV does) or absolute");
+    println!("  --comment <text>");
+    println!("                  With --create, add <text> as '; ' comment lines at the top of the");
+    println!("                  manifest, below the generator line; may be repeated");
+    println!("  --sizes         With --create, also record each file's size; --verify then");
+    println!("                  reports a file whose size differs without hashing it");
+    println!("  --verify <file.sfv>");
+    println!("                  Check every entry of an SFV manifest against the files on disk");
+    println!("  --verify-stdin  Like --verify, reading the manifest from standard input");
+    println!("  --report <file.json>");
+    println!("                  With --verify or --verify-stdin, also write a JSON report of");
+    println!("                  every entry's expected and actual CRC and status, with totals,");
+    println!("                  whether or not verification passes");
+    println!("  --resume <statefile>");
+    println!("                  With --verify or --verify-stdin, skip entries the state file");
+    println!("                  records as verified OK, and add each entry that passes to it");
+    println!("                  as it is checked; delete the file to check everything again");
+    println!("  --check-manifest <file.sfv>");
+    println!("                  Report unparseable lines and missing files without hashing");
+    println!("  --expect <crc32> <file_path>");
+    println!("                  Check one file against a CRC32 given in hex ('0x' optional)");
+    println!("  --deep-diff <reference> <file_path>");
+    println!("                  Find the first byte where a file differs from a known-good copy");
+    println!("                  and show a hex dump of both around it");
+    println!("  --errors-only   Print only mismatches, missing files and read errors, then the");
+    println!("                  summary; with --json, only the records of failed inputs");
+    println!("  -q, --quiet     Don't print the closing summary (file count, size, time)");
+    println!("  -v, --verbose   Explain on stderr how paths are resolved and opened; -vv also");
+    println!("                  reports bytes read and time taken for every file");
+    println!("  --base-dir <dir>");
+    println!("                  Resolve manifest paths against <dir> instead of the manifest's");
+    println!("                  folder with --verify or --check-manifest, or the current");
+    println!("                  directory with --verify-stdin; entries outside <dir> are REJECTED");
+    println!();
+    println!("Exit status:");
+    println!("  {}   success", EXIT_OK);
+    println!(
+        "  {}   a --verify entry mismatched or was missing",
+        EXIT_MISMATCH
+    );
+    println!(
+        "  {}   an input could not be read (takes precedence over {})",
+        EXIT_IO_ERROR, EXIT_MISMATCH
+    );
+    println!("  {}  invalid command line", EXIT_USAGE);
+    println!(
+        "  {} interrupted by Ctrl+C (a summary of what was checked is still printed;",
+        EXIT_INTERRUPTED
+    );
+    println!("      press Ctrl+C again to quit at once)");
+    println!();
+    println!("Environment:");
+    println!("  MTSFV_BUFFER_KB Read buffer size in KiB, 4 to 65536 (default: 256); larger");
+    println!("                  reads can help on high-latency network storage");
+    println!();
+    println!("Examples:");
+    println!("  {} test.txt", program);
+    println!("  {} --algo sha256 test.txt", program);
+    println!("  echo -n '123456789' | {} --stdin", program);
+    println!("  {} --recursive --include-ext mkv,iso media", program);
+    println!("  {} --create release.sfv *.rar", program);
+    println!("  {} --verify release.sfv", program);
+    println!("  {} --check-manifest release.sfv", program);
+    println!("  {} --expect 0xCBF43926 test.txt", program);
+    println!("  {} --deep-diff good/disc.iso downloads/disc.iso", program);
+    println!(
+        "  curl -s https://example.com/release.sfv | {} --verify-stdin --base-dir downloads",
+        program
+    );
+    println!(
+        "  find . -type f -print0 | {} --null --files-from -",
+        program
+    );
+}
+
+fn parse_args(args: &[String]) -> Result<Options, String> {
+    let mut options = Options {
+        algo: HashAlgo::Crc32,
+        format: None,
+        stdin: false,
+        files: Vec::new(),
+        files_from: None,
+        verify: None,
+        verify_stdin: false,
+        check_manifest: None,
+        report: None,
+        resume: None,
+        base_dir: None,
+        relative_to: None,
+        expect: None,
+        deep_diff: None,
+        create: None,
+        no_clobber: false,
+        append: false,
+        path_style: None,
+        sizes: false,
+        comments: Vec::new(),
+        null_separated: false,
+        json: false,
+        bare: false,
+        timing: false,
+        strict: false,
+        glob: false,
+        recursive: false,
+        fail_fast: false,
+        split: false,
+        ignore_case: false,
+        allow_escape: false,
+        errors_only: false,
+        quiet: false,
+        verbosity: 0,
+        workers: batch::default_workers(),
+        rate_limit: None,
+        extensions: ExtensionFilter::default(),
+        skip_hidden: false,
+    };
+
+    let mut iter = args.iter();
+    while let Some(arg) = iter.next() {
+        match arg.as_str() {
+            "--stdin" => options.stdin = true,
+            "--algo" => {
+                let name = iter.next().ok_or("--algo requires a value")?;
+                options.algo = name.parse().map_err(|e: hash::UnknownAlgo| e.to_string())?;
+            }
+            "--format" => {
+                let name = iter.next().ok_or("--format requires a value")?;
+                options.format = Some(DigestFormat::parse(name)?);
+            }
+            "--files-from" => {
+                let list = iter.next().ok_or("--files-from requires a value")?;
+                options.files_from = Some(list.clone());
+            }
+            "--verify" => {
+                let sfv = iter.next().ok_or("--verify requires a value")?;
+                options.verify = Some(sfv.clone());
+            }
+            "--verify-stdin" => options.verify_stdin = true,
+            "--report" => {
+                let file = iter.next().ok_or("--report requires a value")?;
+                options.report = Some(file.clone());
+            }
+            "--resume" => {
+                let file = iter.next().ok_or("--resume requires a value")?;
+                options.resume = Some(file.clone());
+            }
+            "--check-manifest" => {
+                let sfv = iter.next().ok_or("--check-manifest requires a value")?;
+                options.check_manifest = Some(sfv.clone());
+            }
+            "--expect" => {
+                let hex = iter.next().ok_or("--expect requires a CRC and a file")?;
+                let file = iter.next().ok_or("--expect requires a CRC and a file")?;
+                options.expect = Some((parse_crc(hex)?, file.clone()));
+            }
+            "--create" => {
+                let sfv = iter.next().ok_or("--create requires a value")?;
+                options.create = Some(sfv.clone());
+            }
+            "--no-clobber" => options.no_clobber = true,
+            "--append" => options.append = true,
+            "--sizes" => options.sizes = true,
+            "--comment" => {
+                let text = iter.next().ok_or("--comment requires a value")?;
+                options.comments.push(text.clone());
+            }
+            "--paths" => {
+                let style = iter.next().ok_or("--paths requires a value")?;
+                options.path_style = Some(match style.as_str() {
+                    "relative" => PathStyle::Relative,
+                    "absolute" => PathStyle::Absolute,
+                    _ => {
+                        return Err(format!(
+                            "unknown path style '{}' (expected relative or absolute)",
+                            style
+                        ))
+                    }
+                });
+            }
+            "--deep-diff" => {
+                let reference = iter
+                    .next()
+                    .ok_or("--deep-diff requires a reference and a file")?;
+                let file = iter
+                    .next()
+                    .ok_or("--deep-diff requires a reference and a file")?;
+                options.deep_diff = Some((reference.clone(), file.clone()));
+            }
+            "--base-dir" => {
+                let dir = iter.next().ok_or("--base-dir requires a value")?;
+                options.base_dir = Some(dir.clone());
+            }
+            // The folder is attached with '=' so a bare flag followed by
+            // file arguments means the current directory.
+            "--relative-to" => options.relative_to = Some(".".to_string()),
+            arg if arg.starts_with("--relative-to=") => {
+                let dir = &arg["--relative-to=".len()..];
+                if dir.is_empty() {
+                    return Err("--relative-to= requires a folder".to_string());
+                }
+                options.relative_to = Some(dir.to_string());
+            }
+            "--null" => options.null_separated = true,
+            "--json" => options.json = true,
+            "--bare" => options.bare = true,
+            "--timing" => options.timing = true,
+            "--strict" => options.strict = true,
+            "--glob" => options.glob = true,
+            "--recursive" => options.recursive = true,
+            "--skip-hidden" => options.skip_hidden = true,
+            "--fail-fast" => options.fail_fast = true,
+            "--split" => options.split = true,
+            "--ignore-case" => options.ignore_case = true,
+            "--allow-escape" => options.allow_escape = true,
+            "--errors-only" => options.errors_only = true,
+            "-q" | "--quiet" => options.quiet = true,
+            "-v" | "--verbose" => options.verbosity += 1,
+            "-vv" => options.verbosity += 2,
+            "--workers" => {
+                let count = iter.next().ok_or("--workers requires a value")?;
+                options.workers = match count.parse() {
+                    Ok(count) if count > 0 => count,
+                    _ => return Err(format!("invalid worker count '{}'", count)),
+                };
+            }
+            "--rate-limit" => {
+                let rate = iter.next().ok_or("--rate-limit requires a value")?;
+                options.rate_limit = match rate.parse::<f64>() {
+                    Ok(mb) if mb.is_finite() && mb * 1024.0 * 1024.0 >= 1.0 => {
+                        Some((mb * 1024.0 * 1024.0) as u64)
+                    }
+                    _ => return Err(format!("invalid rate limit '{}' (MB/s)", rate)),
+                };
+            }
+            "--include-ext" => {
+                let list = iter.next().ok_or("--include-ext requires a value")?;
+                options
+                    .extensions
+                    .include
+                    .extend(ExtensionFilter::parse_list(list));
+            }
+            "--exclude-ext" => {
+                let list = iter.next().ok_or("--exclude-ext requires a value")?;
+                options
+                    .extensions
+                    .exclude
+                    .extend(ExtensionFilter::parse_list(list));
+            }
+            _ => options.files.push(arg.clone()),
+        }
+    }
+
+    if options.stdin && options.files_from.as_deref() == Some("-") {
+        return Err("--stdin and --files-from - cannot both read standard input".to_string());
+    }
+    if options.verify.is_some()
+        && (options.stdin || options.files_from.is_some() || !options.files.is_empty())
+    {
+        return Err(
+            "--verify cannot be combined with --stdin, --files-from or file arguments".to_string(),
+        );
+    }
+    if options.verify_stdin
+        && (options.stdin
+            || options.verify.is_some()
+            || options.files_from.is_some()
+            || !options.files.is_empty())
+    {
+        return Err(
+            "--verify-stdin cannot be combined with --stdin, --verify, --files-from or file arguments"
+                .to_string(),
+        );
+    }
+    if options.expect.is_some()
+        && (options.stdin
+            || options.verify.is_some()
+            || options.verify_stdin
+            || options.files_from.is_some()
+            || !options.files.is_empty())
+    {
+        return Err(
+            "--expect checks a single file and cannot be combined with other inputs".to_string(),
+        );
+    }
+    if options.deep_diff.is_some()
+        && (options.stdin
+            || options.verify.is_some()
+            || options.verify_stdin
+            || options.expect.is_some()
+            || options.files_from.is_some()
+            || !options.files.is_empty())
+    {
+        return Err(
+            "--deep-diff compares two files and cannot be combined with other inputs".to_string(),
+        );
+    }
+    if options.check_manifest.is_some()
+        && (options.stdin
+            || options.verify.is_some()
+            || options.verify_stdin
+            || options.expect.is_some()
+            || options.deep_diff.is_some()
+            || options.files_from.is_some()
+            || !options.files.is_empty())
+    {
+        return Err(
+            "--check-manifest checks a single manifest and cannot be combined with other inputs"
+                .to_string(),
+        );
+    }
+    if options.create.is_some()
+        && (options.stdin
+            || options.verify.is_some()
+            || options.verify_stdin
+            || options.expect.is_some()
+            || options.json)
+    {
+        return Err(
+            "--create cannot be combined with --stdin, --verify, --verify-stdin, --expect or --json"
+                .to_string(),
+        );
+    }
+    if options.json && (options.check_manifest.is_some() || options.deep_diff.is_some()) {
+        return Err("--json cannot be combined with --check-manifest or --deep-diff".to_string());
+    }
+    if options.relative_to.is_some()
+        && (options.stdin
+            || options.verify.is_some()
+            || options.verify_stdin
+            || options.check_manifest.is_some()
+            || options.expect.is_some()
+            || options.deep_diff.is_some())
+    {
+        return Err(
+            "--relative-to only applies to hashed files and cannot be combined with --stdin, \
+             --verify, --verify-stdin, --check-manifest, --expect or --deep-diff"
+                .to_string(),
+        );
+    }
+    if options.bare
+        && (options.json
+            || options.errors_only
+            || options.verify.is_some()
+            || options.verify_stdin
+            || options.check_manifest.is_some()
+            || options.expect.is_some()
+            || options.deep_diff.is_some())
+    {
+        return Err(
+            "--bare cannot be combined with --json, --errors-only, --verify, --verify-stdin, \
+             --check-manifest, --expect or --deep-diff"
+                .to_string(),
+        );
+    }
+    if options.create.is_some() && options.algo != HashAlgo::Crc32 {
+        return Err("--create only supports --algo crc32".to_string());
+    }
+    if options.path_style.is_some() && options.create.is_none() {
+        return Err("--paths requires --create".to_string());
+    }
+    if options.no_clobber && options.create.is_none() {
+        return Err("--no-clobber requires --create".to_string());
+    }
+    if options.append && options.create.is_none() {
+        return Err("--append requires --create".to_string());
+    }
+    if options.no_clobber && options.append {
+        return Err("--no-clobber and --append cannot be combined".to_string());
+    }
+    if options.sizes && options.create.is_none() {
+        return Err("--sizes requires --create".to_string());
+    }
+    if !options.comments.is_empty() && options.create.is_none() {
+        return Err("--comment requires --create".to_string());
+    }
+    if options.expect.is_some() && options.algo != HashAlgo::Crc32 {
+        return Err("--expect only supports --algo crc32".to_string());
+    }
+    if options.report.is_some() && options.verify.is_none() && !options.verify_stdin {
+        return Err("--report requires --verify or --verify-stdin".to_string());
+    }
+    if options.resume.is_some() && options.verify.is_none() && !options.verify_stdin {
+        return Err("--resume requires --verify or --verify-stdin".to_string());
+    }
+    if options.base_dir.is_some()
+        && options.verify.is_none()
+        && !options.verify_stdin
+        && options.check_manifest.is_none()
+    {
+        return Err("--base-dir requires --verify, --verify-stdin or --check-manifest".to_string());
+    }
+    if options.ignore_case
+        && options.verify.is_none()
+        && !options.verify_stdin
+        && options.check_manifest.is_none()
+    {
+        return Err(
+            "--ignore-case requires --verify, --verify-stdin or --check-manifest".to_string(),
+        );
+    }
+    if options.allow_escape
+        && options.verify.is_none()
+        && !options.verify_stdin
+        && options.check_manifest.is_none()
+        && options.create.is_none()
+    {
+        return Err(
+            "--allow-escape requires --verify, --verify-stdin, --check-manifest or --create"
+                .to_string(),
+        );
+    }
+    if options.extensions != ExtensionFilter::default() && !options.recursive {
+        return Err("--include-ext and --exclude-ext require --recursive".to_string());
+    }
+    if options.skip_hidden && !options.recursive {
+        return Err("--skip-hidden requires --recursive".to_string());
+    }
+
+    Ok(options)
+}
+
+/// Replace each argument containing a wildcard with the files it matches,
+/// in sorted order. Returns the expanded list and the patterns that matched
+/// no files.
+fn expand_globs(args: &[String]) -> Result<(Vec<String>, Vec<String>), String> {
+    let mut files = Vec::new();
+    let mut unmatched = Vec::new();
+    for arg in args {
+        if !arg.contains(['*', '?', '[']) {
+            files.push(arg.clone());
+            continue;
+        }
+        let paths = glob::glob(arg).map_err(|e| format!("invalid pattern '{}': {}", arg, e))?;
+        let before = files.len();
+        files.extend(
+            paths
+                .filter_map(Result::ok)
+                .filter(|path| path.is_file())
+                .map(|path| path.to_string_lossy().into_owned()),
+        );
+        if files.len() == before {
+            unmatched.push(arg.clone());
+        }
+    }
+    Ok((files, unmatched))
+}
+
+/// Replace each directory argument with the files beneath it that `walk`
+/// lists and that pass `filter`, in sorted order. Returns the expanded list and the directories
+/// that could not be walked.
+fn expand_dirs(
+    args: &[String],
+    filter: &ExtensionFilter,
+    walk: WalkOptions,
+) -> (Vec<String>, Vec<(String, io::Error)>) {
+    let mut files = Vec::new();
+    let mut failed = Vec::new();
+    for arg in args {
+        let root = Path::new(arg);
+        if !root.is_dir() {
+            files.push(arg.clone());
+            continue;
+        }
+        match walk_files_with(root, walk) {
+            Ok(found) => files.extend(
+                found
+                    .iter()
+                    .filter(|path| filter.matches(path))
+                    .map(|path| root.join(path).to_string_lossy().into_owned()),
+            ),
+            Err(e) => failed.push((arg.clone(), e)),
+        }
+    }
+    (files, failed)
+}
+
+/// Append the paths listed in the `--files-from` list to `options.files`.
+fn load_files_from(options: &mut Options) -> io::Result<()> {
+    let Some(list) = &options.files_from else {
+        return Ok(());
+    };
+    let contents = if list == "-" {
+        let mut buffer = Vec::new();
+        io::stdin().read_to_end(&mut buffer)?;
+        buffer
+    } else {
+        fs::read(list)?
+    };
+    options
+        .files
+        .extend(filelist::parse_file_list(&contents, options.null_separated));
+    Ok(())
+}
+
+/// Set by the first Ctrl+C; every file is read under [`read_control`], so
+/// hashing stops once it is set.
+static INTERRUPTED: AtomicBool = AtomicBool::new(false);
+
+/// On the first Ctrl+C, stop hashing so the run can report what it checked
+/// and exit with [`EXIT_INTERRUPTED`]; on the second, exit at once.
+fn install_interrupt_handler() {
+    let installed = ctrlc::set_handler(|| {
+        if INTERRUPTED.swap(true, Ordering::Relaxed) {
+            process::exit(EXIT_INTERRUPTED);
+        }
+        eprintln!("Interrupted; stopping (press Ctrl+C again to quit at once)");
+    });
+    if let Err(e) = installed {
+        log::debug!("Ctrl+C handler not installed: {}", e);
+    }
+}
 
 fn main() {
     let args: Vec<String> = env::args().collect();
-    
+
     if args.len() < 2 {
-        println!("Usage: {} <file_path> [file_path...]", args[0]);
-        println!("       or");
-        println!("       {} --stdin", args[0]);
-        println!();
-        println!("Examples:");
-        println!("  {} test.txt", args[0]);
-        println!("  echo -n '123456789' | {} --stdin", args[0]);
-        std::process::exit(1);
-    }
-    
-    if args[1] == "--stdin" {
-        // Read from stdin and compute CRC32
-        let mut buffer = Vec::new();
-        io::stdin().read_to_end(&mut buffer).expect("Failed to read from stdin");
-        
-        let crc = unsafe {
-            // Safety: buffer is an owned Vec<u8> with contiguous storage that lives for the duration of the call.
-            mtsfv_crc32(buffer.as_ptr(), buffer.len())
-        };
-        
-        println!("CRC32: {:08X}", crc);
+        print_usage(&args[0]);
+        process::exit(EXIT_USAGE);
+    }
+
+    let mut options = match parse_args(&args[1..]) {
+        Ok(options) => options,
+        Err(e) => {
+            eprintln!("Error: {}", e);
+            process::exit(EXIT_USAGE);
+        }
+    };
+
+    init_logging(options.verbosity);
+    RATE_LIMIT.store(options.rate_limit.unwrap_or(0), Ordering::Relaxed);
+
+    if let Some(sfv) = &options.check_manifest {
+        process::exit(run_check_manifest(Path::new(sfv), &options));
+    }
+    if let Some((reference, file)) = &options.deep_diff {
+        process::exit(run_deep_diff(Path::new(reference), Path::new(file)));
+    }
+
+    // Everything below reads files under `read_control`.
+    install_interrupt_handler();
+    if let Some(sfv) = &options.verify {
+        process::exit(run_verify(Path::new(sfv), &options));
+    }
+    if let Some((expected, file)) = &options.expect {
+        process::exit(run_expect(file, *expected, &options));
+    }
+    if options.verify_stdin {
+        let base_dir = options.base_dir.as_deref().unwrap_or(".");
+        process::exit(run_verify_stdin(Path::new(base_dir), &options));
+    }
+
+    let mut exit_code = EXIT_OK;
+
+    if options.glob {
+        match expand_globs(&options.files) {
+            Ok((files, unmatched)) => {
+                for pattern in &unmatched {
+                    eprintln!("{}: no matches", pattern);
+                    exit_code = EXIT_IO_ERROR;
+                }
+                options.files = files;
+            }
+            Err(e) => {
+                eprintln!("Error: {}", e);
+                process::exit(EXIT_USAGE);
+            }
+        }
+    }
+
+    if let Err(e) = load_files_from(&mut options) {
+        eprintln!(
+            "Error reading file list {}: {}",
+            options.files_from.as_deref().unwrap_or_default(),
+            e
+        );
+        process::exit(EXIT_IO_ERROR);
+    }
+
+    if options.recursive {
+        let walk = WalkOptions {
+            skip_hidden: options.skip_hidden,
+            ..Default::default()
+        };
+        let (files, failed) = expand_dirs(&options.files, &options.extensions, walk);
+        for (dir, e) in &failed {
+            eprintln!("Error reading directory {}: {}", dir, e);
+            exit_code = EXIT_IO_ERROR;
+        }
+        options.files = files;
+    }
+
+    if options.stdin {
+        let label = options.algo.name().to_uppercase();
+        let result = if options.algo == HashAlgo::Crc32 {
+            let start = Instant::now();
+            let mut bytes = 0;
+            let stdin = read_control().wrap(io::stdin().lock());
+            crc32_reader_with_progress(stdin, |total| bytes = total).map(|crc| Hashed {
+                digest: Digest::from_u32(crc),
+                bytes,
+                elapsed: start.elapsed(),
+            })
+        } else {
+            let start = Instant::now();
+            let stdin = read_control().wrap(io::stdin().lock());
+            hash_reader_with_len(stdin, options.algo).map(|(digest, bytes)| Hashed {
+                digest,
+                bytes,
+                elapsed: start.elapsed(),
+            })
+        };
+
+        if matches!(&result, Err(e) if is_cancelled(e)) {
+            eprintln!("Interrupted");
+            process::exit(EXIT_INTERRUPTED);
+        }
+        if result.is_err() {
+            exit_code = EXIT_IO_ERROR;
+        }
+        if options.json {
+            println!("{}", json_record("-", &options, &result));
+        } else {
+            match result {
+                Ok(hashed) => {
+                    let digest = format_digest(&options, &hashed.digest);
+                    if options.bare {
+                        println!("{}", digest);
+                    } else {
+                        println!("{}: {}", label, digest);
+                    }
+                }
+                Err(e) => eprintln!("Error reading from stdin: {}", e),
+            }
+        }
     } else {
         // Process files
-        for file_path in &args[1..] {
-            match fs::read(file_path) {
-                Ok(data) => {
-                    let crc = unsafe {
-                        // Safety: data is read from disk into an owned Vec<u8> with contiguous storage that lives for the duration of the call.
-                        mtsfv_crc32(data.as_ptr(), data.len())
-                    };
-                    println!("{}: {:08X}", file_path, crc);
+        let existing = match &options.create {
+            Some(sfv) if Path::new(sfv).exists() && options.no_clobber => {
+                eprintln!("{} already exists; not replacing it (--no-clobber)", sfv);
+                process::exit(EXIT_IO_ERROR);
+            }
+            // Read before hashing, so a manifest that does not parse is
+            // reported before the work rather than after it.
+            Some(sfv) if Path::new(sfv).exists() && options.append => {
+                match SfvManifest::read(Path::new(sfv)) {
+                    Ok(existing) => Some(existing),
+                    Err(e) => {
+                        eprintln!("Error reading {}: {}", sfv, e);
+                        process::exit(EXIT_IO_ERROR);
+                    }
+                }
+            }
+            _ => None,
+        };
+        if let Some(sfv) = &options.create {
+            let path_style = options.path_style.unwrap_or(PathStyle::Relative);
+            match escaping_entry(Path::new(sfv), &options.files, path_style) {
+                Ok(None) => {}
+                Ok(Some((file, stored))) if options.allow_escape => {
+                    eprintln!(
+                        "Warning: {} is stored as {}, outside the manifest's folder; \
+                         verify {} with --allow-escape",
+                        file, stored, sfv
+                    );
+                }
+                Ok(Some((file, stored))) => {
+                    eprintln!(
+                        "{} would be stored as {}, outside the manifest's folder, which \
+                         --verify rejects; pass --allow-escape to write it anyway",
+                        file, stored
+                    );
+                    process::exit(EXIT_USAGE);
                 }
                 Err(e) => {
-                    eprintln!("Error reading {}: {}", file_path, e);
+                    eprintln!("Error resolving paths for {}: {}", sfv, e);
+                    process::exit(EXIT_IO_ERROR);
                 }
             }
         }
+        let mut manifest = SfvManifest {
+            header_comments: vec![format!(" Generated by MTSFV {}", env!("CARGO_PKG_VERSION"))],
+            ..Default::default()
+        };
+        for comment in &options.comments {
+            manifest.add_header_comment(comment);
+        }
+        // Manifest entries are keyed by argument position so that --create
+        // writes them in argument order whatever order the workers finish in.
+        let mut hashed_crcs = Vec::new();
+        // --bare lines that arrived ahead of an earlier input still hashing,
+        // keyed by argument position, and the position to print next.
+        let mut bare_lines = BTreeMap::new();
+        let mut next_bare = 0;
+        let (mut ok, mut failed, mut bytes) = (0u64, 0u64, 0u64);
+        let mut interrupted = false;
+        let start = Instant::now();
+        let hash_one = |file_path: &String| hash_path(file_path, &options);
+        batch::for_each_parallel(
+            &options.files,
+            options.workers,
+            hash_one,
+            |index, result| {
+                // A file cut short by Ctrl+C was not hashed, so it is left out.
+                if matches!(&result, Err(e) if is_cancelled(e)) {
+                    interrupted = true;
+                    return false;
+                }
+                let file_path = &display_path(&options.files[index], &options);
+                let is_err = result.is_err();
+                match &result {
+                    Ok(hashed) => {
+                        ok += 1;
+                        bytes += hashed.bytes;
+                    }
+                    Err(_) => {
+                        failed += 1;
+                        exit_code = EXIT_IO_ERROR;
+                    }
+                }
+                if options.json {
+                    if is_err || !options.errors_only {
+                        println!("{}", json_record(file_path, &options, &result));
+                    }
+                } else {
+                    match result {
+                        Ok(hashed) => {
+                            let digest = format_digest(&options, &hashed.digest);
+                            if options.bare {
+                                bare_lines.insert(index, digest);
+                            } else if !options.errors_only {
+                                println!("{}: {}", file_path, digest);
+                            }
+                            if let Some(crc) = hashed.digest.to_u32() {
+                                hashed_crcs.push((index, crc, hashed.bytes));
+                            }
+                        }
+                        Err(e) => {
+                            eprintln!("Error reading {}: {}", file_path, e);
+                            if options.bare {
+                                bare_lines.insert(index, String::new());
+                            }
+                        }
+                    }
+                    while let Some(line) = bare_lines.remove(&next_bare) {
+                        println!("{}", line);
+                        next_bare += 1;
+                    }
+                }
+                if is_err && options.fail_fast {
+                    eprintln!("Stopped at first error (--fail-fast)");
+                    return false;
+                }
+                true
+            },
+        );
+        let elapsed = start.elapsed();
+        if options.json && !options.quiet {
+            let summary = json!({
+                "files": ok + failed,
+                "bytes": bytes,
+                "elapsed_ms": elapsed.as_secs_f64() * 1000.0,
+                "ok": ok,
+                "failed": failed,
+                "interrupted": interrupted,
+            });
+            println!("{}", json!({ "summary": summary }));
+        } else if !options.quiet && !options.bare {
+            eprintln!(
+                "{}: {} OK, {} failed{}",
+                summary_head("Hashed", ok + failed, bytes, elapsed),
+                ok,
+                failed,
+                if interrupted {
+                    " (interrupted, remaining files not hashed)"
+                } else {
+                    ""
+                }
+            );
+        }
+        if interrupted {
+            exit_code = EXIT_INTERRUPTED;
+        }
+        // Like update_sfv, keep sizes coming when the manifest appended to
+        // records them.
+        let sizes = options.sizes
+            || existing
+                .iter()
+                .flat_map(|existing| &existing.entries)
+                .any(|entry| entry.size.is_some());
+        hashed_crcs.sort_unstable_by_key(|&(index, ..)| index);
+        manifest.entries = hashed_crcs
+            .into_iter()
+            .map(|(index, crc, bytes)| SfvEntry {
+                filename: options.files[index].clone(),
+                crc,
+                comments: Vec::new(),
+                size: sizes.then_some(bytes),
+            })
+            .collect();
+
+        if let Some(sfv) = &options.create {
+            let written = match &existing {
+                Some(existing) => append_manifest(Path::new(sfv), existing, manifest, &options),
+                None => write_sfv(
+                    Path::new(sfv),
+                    &manifest,
+                    LineEnding::Crlf,
+                    options.path_style.unwrap_or(PathStyle::Relative),
+                ),
+            };
+            if let Err(e) = written {
+                eprintln!("Error writing {}: {}", sfv, e);
+                exit_code = EXIT_IO_ERROR;
+            }
+        }
+    }
+
+    process::exit(exit_code);
+}
+
+/// The first of `files` that `--create` would store outside the folder of
+/// the manifest at `sfv`, with the name it would be stored under: `--verify`
+/// rejects such an entry without `--allow-escape`.
+fn escaping_entry(
+    sfv: &Path,
+    files: &[String],
+    path_style: PathStyle,
+) -> io::Result<Option<(String, String)>> {
+    let mut entries: Vec<SfvEntry> = files
+        .iter()
+        .map(|file| SfvEntry {
+            filename: file.clone(),
+            crc: 0,
+            comments: Vec::new(),
+            size: None,
+        })
+        .collect();
+    store_paths(&mut entries, sfv, path_style)?;
+    let base_dir = sfv.parent().unwrap_or_else(|| Path::new(""));
+    Ok(files
+        .iter()
+        .zip(entries)
+        .find(|(_, entry)| safe_entry_path(base_dir, &entry.filename).is_err())
+        .map(|(file, entry)| (file.clone(), entry.filename)))
+}
+
+/// `--append`: merge the entries of `hashed` into `existing`, the manifest
+/// already at `sfv`, and write the result back. `--comment` lines are added
+/// below its header; the rest of the header is kept.
+fn append_manifest(
+    sfv: &Path,
+    existing: &SfvManifest,
+    hashed: SfvManifest,
+    options: &Options,
+) -> io::Result<()> {
+    let mut entries = hashed.entries;
+    store_paths(
+        &mut entries,
+        sfv,
+        options.path_style.unwrap_or(PathStyle::Relative),
+    )?;
+    let mut merged = append_to_sfv(existing, entries);
+    for comment in &options.comments {
+        merged.add_header_comment(comment);
+    }
+    write_sfv(sfv, &merged, LineEnding::Crlf, PathStyle::AsGiven)
+}
+
+/// Prints `log` records to stderr, leaving stdout to the results.
+struct StderrLogger;
+
+impl log::Log for StderrLogger {
+    fn enabled(&self, metadata: &log::Metadata) -> bool {
+        metadata.level() <= log::max_level()
+    }
+
+    fn log(&self, record: &log::Record) {
+        if self.enabled(record.metadata()) {
+            eprintln!(
+                "[{}] {}",
+                record.level().as_str().to_lowercase(),
+                record.args()
+            );
+        }
+    }
+
+    fn flush(&self) {}
+}
+
+/// Route diagnostics to stderr: nothing by default, debug records with one
+/// `-v` and trace records with two or more.
+fn init_logging(verbosity: u8) {
+    let level = match verbosity {
+        0 => return,
+        1 => log::LevelFilter::Debug,
+        _ => log::LevelFilter::Trace,
+    };
+    if log::set_logger(&StderrLogger).is_ok() {
+        log::set_max_level(level);
+    }
+}
+
+/// How every file is read: paced to `--rate-limit` and stopping once Ctrl+C
+/// is pressed.
+fn read_control() -> ReadControl<'static> {
+    ReadControl {
+        cancel: Some(&INTERRUPTED),
+        on_read: Some(throttle),
+    }
+}
+
+/// `--rate-limit` in bytes per second; 0 means unlimited.
+static RATE_LIMIT: AtomicU64 = AtomicU64::new(0);
+
+/// When the current rate-limited stretch of reading began, and the bytes
+/// read since, shared by every worker thread.
+static PACE: Mutex<Option<(Instant, u64)>> = Mutex::new(None);
+
+/// Account for `bytes` just read, sleeping if that puts the run ahead of
+/// `--rate-limit`.
+///
+/// Reads sleep between chunks once they get ahead of the rate, so other
+/// programs keep some disk bandwidth during a long background run. With
+/// several workers the cap applies to their total. Time spent idle earns at
+/// most a second of catch-up at full speed.
+fn throttle(bytes: usize) {
+    let limit = RATE_LIMIT.load(Ordering::Relaxed);
+    if limit == 0 {
+        return;
+    }
+    let wait = {
+        let mut pace = PACE.lock().unwrap_or_else(|e| e.into_inner());
+        pace_wait(&mut pace, Instant::now(), bytes as u64, limit)
+    };
+    if !wait.is_zero() {
+        std::thread::sleep(wait);
+    }
+}
+
+/// How long to sleep after reading `bytes` more at `now` to stay at `limit`
+/// bytes per second, given the `(start, bytes read since)` of the current
+/// stretch, which is updated.
+fn pace_wait(pace: &mut Option<(Instant, u64)>, now: Instant, bytes: u64, limit: u64) -> Duration {
+    let due_after = |bytes: u64| Duration::from_secs_f64(bytes as f64 / limit as f64);
+    let (start, done) = pace.get_or_insert((now, 0));
+    if *start + due_after(*done) + Duration::from_secs(1) < now {
+        (*start, *done) = (now, 0);
+    }
+    *done += bytes;
+    (*start + due_after(*done)).saturating_duration_since(now)
+}
+
+/// The library-side verification settings chosen on the command line.
+fn verify_options(options: &Options) -> VerifyOptions<'static> {
+    VerifyOptions {
+        split_parts: options.split,
+        ignore_case: options.ignore_case,
+        allow_escape: options.allow_escape,
+        read: read_control(),
+    }
+}
+
+/// Warn when an entry was only found under a differently cased name, and
+/// pass its result on.
+fn warn_found_as(entry: VerifiedEntry) -> VerifiedEntry {
+    if let Some(found) = &entry.found_as {
+        eprintln!(
+            "Warning: {} not found, checked {} instead (case differs)",
+            entry.result.filename(),
+            found.display()
+        );
+    }
+    entry
+}
+
+/// The directory `sfv`'s entries resolve against: `--base-dir` when given,
+/// otherwise the manifest's own folder.
+fn manifest_base_dir<'a>(sfv: &'a Path, options: &'a Options) -> &'a Path {
+    match &options.base_dir {
+        Some(dir) => Path::new(dir),
+        None => sfv.parent().unwrap_or_else(|| Path::new("")),
+    }
+}
+
+/// Check every entry of `sfv` against files next to it.
+fn run_verify(sfv: &Path, options: &Options) -> i32 {
+    let entries = match read_sfv(sfv) {
+        Ok(entries) => entries,
+        Err(e) => {
+            eprintln!("Error reading {}: {}", sfv.display(), e);
+            return EXIT_IO_ERROR;
+        }
+    };
+    let base_dir = manifest_base_dir(sfv, options);
+    let source = ReportSource {
+        manifest: &sfv.to_string_lossy(),
+        base_dir,
+    };
+    verify_and_report(base_dir, entries, options, source)
+}
+
+/// Report every line of `sfv` that does not parse and every entry whose file
+/// cannot be found, without hashing anything.
+///
+/// Files are looked for the way `--verify` would, including the `--split`
+/// and `--ignore-case` fallbacks, and entries leading outside the manifest's
+/// folder are rejected unless `--allow-escape` is given. Unparseable lines
+/// exit like an unreadable manifest, missing files like a failed
+/// verification.
+fn run_check_manifest(sfv: &Path, options: &Options) -> i32 {
+    let (manifest, errors) = match SfvManifest::read_lenient(sfv) {
+        Ok(parsed) => parsed,
+        Err(e) => {
+            eprintln!("Error reading {}: {}", sfv.display(), e);
+            return EXIT_IO_ERROR;
+        }
+    };
+    for err in &errors {
+        println!("{}: {}", sfv.display(), err);
+    }
+
+    let base_dir = manifest_base_dir(sfv, options);
+    let (mut missing, mut rejected) = (0, 0);
+    for entry in &manifest.entries {
+        if !options.allow_escape {
+            if let Err(reason) = safe_entry_path(base_dir, &entry.filename) {
+                println!("{}: REJECTED ({})", entry.filename, reason);
+                rejected += 1;
+                continue;
+            }
+        }
+        let path = resolve_entry_path(base_dir, &entry.filename);
+        let found = path.is_file()
+            || find_entry_normalized(base_dir, &entry.filename).is_some_and(|p| p.is_file())
+            || (options.ignore_case && find_entry_ignore_case(base_dir, &entry.filename).is_some())
+            || (options.split && split_parts(&path).is_ok());
+        if !found {
+            println!("{}: MISSING", entry.filename);
+            missing += 1;
+        }
+    }
+
+    if errors.is_empty() && missing == 0 && rejected == 0 {
+        println!(
+            "{}: manifest OK ({} {}, all files present)",
+            sfv.display(),
+            manifest.entries.len(),
+            if manifest.entries.len() == 1 {
+                "entry"
+            } else {
+                "entries"
+            }
+        );
+        return EXIT_OK;
+    }
+    if !options.quiet {
+        eprintln!(
+            "{}: {} unparseable line(s), {} of {} file(s) missing, {} rejected",
+            sfv.display(),
+            errors.len(),
+            missing,
+            manifest.entries.len(),
+            rejected
+        );
+    }
+    if errors.is_empty() {
+        EXIT_MISMATCH
+    } else {
+        EXIT_IO_ERROR
+    }
+}
+
+/// Parse a CRC32 given on the command line: up to 8 hex digits, any case,
+/// with an optional `0x` prefix.
+fn parse_crc(text: &str) -> Result<u32, String> {
+    let digits = text
+        .strip_prefix("0x")
+        .or_else(|| text.strip_prefix("0X"))
+        .unwrap_or(text);
+    if digits.is_empty() || digits.len() > 8 || !digits.bytes().all(|b| b.is_ascii_hexdigit()) {
+        return Err(format!("invalid CRC32 '{}'", text));
+    }
+    u32::from_str_radix(digits, 16).map_err(|_| format!("invalid CRC32 '{}'", text))
+}
+
+/// Check a single file against `expected`.
+fn run_expect(file: &str, expected: u32, options: &Options) -> i32 {
+    let entry =
+        verify_one_with(Path::new(file), expected, read_control()).map(|result| VerifiedEntry {
+            result,
+            expected,
+            found_as: None,
+            bytes: fs::metadata(file).map_or(0, |metadata| metadata.len()),
+        });
+    report_results(entry.into_iter(), options, None)
+}
+
+/// Bytes shown before the first difference; the dump covers twice this.
+const DIFF_CONTEXT: u64 = 32;
+
+/// Compare `file` with the known-good `reference` and show where they first
+/// differ.
+fn run_deep_diff(reference: &Path, file: &Path) -> i32 {
+    let offset = match first_difference(reference, file) {
+        Ok(Some(offset)) => offset,
+        Ok(None) => {
+            println!("{}: identical to {}", file.display(), reference.display());
+            return EXIT_OK;
+        }
+        Err(e) => {
+            eprintln!(
+                "Error comparing {} with {}: {}",
+                file.display(),
+                reference.display(),
+                e
+            );
+            return EXIT_IO_ERROR;
+        }
+    };
+
+    let start = offset.saturating_sub(DIFF_CONTEXT) / 16 * 16;
+    let len = (offset - start + DIFF_CONTEXT) as usize;
+    let (expected, actual) = match (
+        compare::read_range(reference, start, len),
+        compare::read_range(file, start, len),
+    ) {
+        (Ok(expected), Ok(actual)) => (expected, actual),
+        (Err(e), _) | (_, Err(e)) => {
+            eprintln!("Error reading bytes around offset {}: {}", offset, e);
+            return EXIT_IO_ERROR;
+        }
+    };
+
+    println!(
+        "{}: first difference at offset {} (0x{:X})",
+        file.display(),
+        offset,
+        offset
+    );
+    if expected.len() != actual.len() {
+        let shorter = if actual.len() < expected.len() {
+            file
+        } else {
+            reference
+        };
+        println!("{} ends there", shorter.display());
+    }
+    print_diff_dump(start, &expected, &actual);
+    EXIT_MISMATCH
+}
+
+/// Print both byte ranges as 16-byte hex rows, the reference ("expected")
+/// above the file ("actual"), with `^^` under each byte that differs. Bytes
+/// past the end of the shorter range are left blank.
+fn print_diff_dump(start: u64, expected: &[u8], actual: &[u8]) {
+    let row_of = |bytes: &[u8], row: usize| -> Vec<u8> {
+        bytes.iter().skip(row * 16).take(16).copied().collect()
+    };
+    let hex =
+        |bytes: &[u8]| -> Vec<String> { bytes.iter().map(|b| format!("{:02X}", b)).collect() };
+
+    for row in 0..expected.len().max(actual.len()).div_ceil(16) {
+        let (expected_row, actual_row) = (row_of(expected, row), row_of(actual, row));
+        let markers: Vec<&str> = expected_row
+            .iter()
+            .zip(&actual_row)
+            .map(|(a, b)| if a == b { "  " } else { "^^" })
+            .collect();
+        let lines = [
+            format!(
+                "{:08X}  expected  {}",
+                start + row as u64 * 16,
+                hex(&expected_row).join(" ")
+            ),
+            format!("          actual    {}", hex(&actual_row).join(" ")),
+            format!("                    {}", markers.join(" ")),
+        ];
+        for line in lines {
+            let line = line.trim_end();
+            if !line.is_empty() {
+                println!("{}", line);
+            }
+        }
+    }
+}
+
+/// Read a manifest from stdin and check it against files under `base_dir`.
+fn run_verify_stdin(base_dir: &Path, options: &Options) -> i32 {
+    let mut manifest = Vec::new();
+    if let Err(e) = io::stdin().read_to_end(&mut manifest) {
+        eprintln!("Error reading from stdin: {}", e);
+        return EXIT_IO_ERROR;
+    }
+    let entries = match parse_sfv_bytes(&manifest) {
+        Ok(entries) => entries,
+        Err(e) => {
+            eprintln!("Error parsing manifest from stdin: {}", e);
+            return EXIT_IO_ERROR;
+        }
+    };
+    let source = ReportSource {
+        manifest: "-",
+        base_dir,
+    };
+    verify_and_report(base_dir, entries, options, source)
+}
+
+/// Check `entries` against files below `base_dir` and report the results,
+/// leaving out and recording entries in the `--resume` state file if given.
+fn verify_and_report(
+    base_dir: &Path,
+    mut entries: Vec<SfvEntry>,
+    options: &Options,
+    source: ReportSource,
+) -> i32 {
+    let verify = verify_options(options);
+    let Some(state) = &options.resume else {
+        return report_results(
+            verify_entries_with(base_dir, entries, verify).map(warn_found_as),
+            options,
+            Some(source),
+        );
+    };
+    let mut resume = match ResumeState::open(state) {
+        Ok(resume) => resume,
+        Err(e) => {
+            eprintln!("Error reading resume state {}: {}", state, e);
+            return EXIT_IO_ERROR;
+        }
+    };
+    let total = entries.len();
+    entries.retain(|entry| !resume.is_done(base_dir, entry));
+    if entries.len() < total && !options.quiet {
+        eprintln!(
+            "Resuming from {}: skipping {} of {} entries already verified",
+            state,
+            total - entries.len(),
+            total
+        );
+    }
+    let exit_code = report_results(
+        verify_entries_with(base_dir, entries, verify)
+            .map(warn_found_as)
+            .inspect(|entry| resume.record(base_dir, entry)),
+        options,
+        Some(source),
+    );
+    match resume.error {
+        Some(e) => {
+            eprintln!("Error writing resume state {}: {}", state, e);
+            EXIT_IO_ERROR
+        }
+        None => exit_code,
+    }
+}
+
+/// The `--resume` state file: one line per entry verified OK, each a JSON
+/// [`SessionEntry`] as found in a GUI session (path, expected CRC, algorithm
+/// and result). Lines are appended as entries pass, so a run that is
+/// interrupted loses at most the entry it was checking.
+struct ResumeState {
+    file: fs::File,
+    /// Absolute path and expected CRC of each entry already verified.
+    done: HashSet<(String, u32)>,
+    /// The first failure to append to the file; later entries are not added.
+    error: Option<io::Error>,
+}
+
+impl ResumeState {
+    /// Read the state file at `path`, creating it if it does not exist. A
+    /// line that does not parse, such as one cut short by a crash, is skipped.
+    fn open(path: &str) -> io::Result<Self> {
+        let text = match fs::read_to_string(path) {
+            Ok(text) => text,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => String::new(),
+            Err(e) => return Err(e),
+        };
+        let mut done = HashSet::new();
+        for (index, line) in text.lines().enumerate() {
+            let Ok(saved) = serde_json::from_str::<SessionEntry>(line) else {
+                log::warn!("{}: line {} is not a saved entry, ignored", path, index + 1);
+                continue;
+            };
+            let verified =
+                saved.algo == HashAlgo::Crc32 && matches!(saved.state, SessionState::Done { .. });
+            if let (true, Some(expected)) = (verified, saved.expected) {
+                done.insert((saved.path.to_string_lossy().into_owned(), expected));
+            }
+        }
+
+        let mut file = fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)?;
+        if !text.is_empty() && !text.ends_with('\n') {
+            // Finish a line cut short so the next entry starts on its own.
+            file.write_all(b"\n")?;
+        }
+        Ok(ResumeState {
+            file,
+            done,
+            error: None,
+        })
+    }
+
+    /// The path an entry is recorded under, absolute so that a run from
+    /// another directory still finds it.
+    fn key(base_dir: &Path, filename: &str) -> String {
+        let path = resolve_entry_path(base_dir, filename);
+        std::path::absolute(&path)
+            .unwrap_or(path)
+            .to_string_lossy()
+            .into_owned()
+    }
+
+    fn is_done(&self, base_dir: &Path, entry: &SfvEntry) -> bool {
+        self.done
+            .contains(&(Self::key(base_dir, &entry.filename), entry.crc))
+    }
+
+    /// Append `entry` if it verified OK.
+    fn record(&mut self, base_dir: &Path, entry: &VerifiedEntry) {
+        let VerifyResult::Ok { filename, crc } = &entry.result else {
+            return;
+        };
+        if self.error.is_some() {
+            return;
+        }
+        let saved = SessionEntry {
+            path: PathBuf::from(Self::key(base_dir, filename)),
+            expected: Some(entry.expected),
+            expected_digest: None,
+            algo: HashAlgo::Crc32,
+            state: SessionState::Done { crc: *crc },
+        };
+        let line = serde_json::to_string(&saved).map_err(io::Error::from);
+        // One write per line, so a crash cannot interleave a partial entry.
+        let written = line.and_then(|line| self.file.write_all(format!("{}\n", line).as_bytes()));
+        if let Err(e) = written {
+            self.error = Some(e);
+        }
+    }
+}
+
+/// Where the entries of a verify run came from, for the `--report` file.
+struct ReportSource<'a> {
+    /// The manifest path as given, or `-` for standard input.
+    manifest: &'a str,
+    base_dir: &'a Path,
+}
+
+/// Print one line per result as soon as it is available, then a summary.
+///
+/// Returns the process exit code: read errors win over mismatches so that a
+/// gate can tell "the data is bad" apart from "the check could not run".
+/// With `--fail-fast` the remaining entries are not checked after the first
+/// failure, and the summary says so. With `--report` and a `source`, the
+/// results are also written to the report file, whatever the outcome.
+fn report_results(
+    entries: impl Iterator<Item = VerifiedEntry>,
+    options: &Options,
+    source: Option<ReportSource>,
+) -> i32 {
+    let started_at = SystemTime::now();
+    let start = Instant::now();
+    let mut exit_code = EXIT_OK;
+    let mut counts = VerifyCounts::default();
+    let mut bytes = 0;
+    let mut stopped = false;
+    let mut records = Vec::new();
+    for entry in entries {
+        if options.report.is_some() {
+            records.push(report_record(&entry));
+        }
+        if options.json {
+            if let Some(record) = verify_json_record(&entry, options) {
+                println!("{}", record);
+            }
+        }
+        let VerifiedEntry {
+            result,
+            bytes: read,
+            ..
+        } = entry;
+        counts.add(&result);
+        bytes += read;
+        let line = match &result {
+            VerifyResult::Ok { filename, .. } => format!("{}: OK", filename),
+            VerifyResult::Mismatch {
+                filename,
+                expected,
+                actual,
+            } => {
+                exit_code = exit_code.max(EXIT_MISMATCH);
+                format!(
+                    "{}: MISMATCH (expected {:08X}, got {:08X})",
+                    filename, expected, actual
+                )
+            }
+            VerifyResult::Missing { filename } => {
+                exit_code = exit_code.max(EXIT_MISMATCH);
+                format!("{}: MISSING", filename)
+            }
+            VerifyResult::SizeMismatch {
+                filename,
+                expected,
+                actual,
+            } => {
+                exit_code = exit_code.max(EXIT_MISMATCH);
+                format!(
+                    "{}: MISMATCH (expected {} bytes, got {})",
+                    filename, expected, actual
+                )
+            }
+            VerifyResult::Error { filename, message } => {
+                exit_code = EXIT_IO_ERROR;
+                format!("{}: ERROR ({})", filename, message)
+            }
+            VerifyResult::Rejected { filename, reason } => {
+                exit_code = exit_code.max(EXIT_MISMATCH);
+                format!("{}: REJECTED ({})", filename, reason)
+            }
+        };
+        let filtered = options.errors_only && result.is_ok();
+        if !options.json && !filtered {
+            println!("{}", line);
+        }
+        if options.fail_fast && exit_code != EXIT_OK {
+            stopped = true;
+            break;
+        }
+    }
+    // After Ctrl+C the entries end early, without the one it cut short.
+    let interrupted = !stopped && INTERRUPTED.load(Ordering::Relaxed);
+
+    let files = counts.ok + counts.failed + counts.missing;
+    if options.json && !options.quiet {
+        let summary = json!({
+            "files": files,
+            "bytes": bytes,
+            "elapsed_ms": start.elapsed().as_secs_f64() * 1000.0,
+            "ok": counts.ok,
+            "failed": counts.failed,
+            "missing": counts.missing,
+            "stopped_early": stopped || interrupted,
+            "interrupted": interrupted,
+        });
+        println!("{}", json!({ "summary": summary }));
+    } else if !options.quiet {
+        eprintln!(
+            "{}: {} OK, {} failed, {} missing{}",
+            summary_head("Verified", files.into(), bytes, start.elapsed()),
+            counts.ok,
+            counts.failed,
+            counts.missing,
+            if interrupted {
+                " (interrupted, remaining entries not checked)"
+            } else if stopped {
+                " (stopped at first failure, remaining entries not checked)"
+            } else {
+                ""
+            }
+        );
+    }
+    if interrupted {
+        exit_code = EXIT_INTERRUPTED;
+    }
+
+    if let (Some(report), Some(source)) = (&options.report, source) {
+        let base_dir = if source.base_dir.as_os_str().is_empty() {
+            Path::new(".")
+        } else {
+            source.base_dir
+        };
+        let document = json!({
+            "report_version": 1,
+            "tool": "mtsfv",
+            "version": env!("CARGO_PKG_VERSION"),
+            "started_at": format_rfc3339(started_at),
+            "manifest": source.manifest,
+            "base_dir": std::path::absolute(base_dir)
+                .unwrap_or_else(|_| base_dir.to_path_buf())
+                .to_string_lossy(),
+            "options": {
+                "split": options.split,
+                "ignore_case": options.ignore_case,
+                "allow_escape": options.allow_escape,
+                "fail_fast": options.fail_fast,
+            },
+            "results": records,
+            "totals": {
+                "files": files,
+                "ok": counts.ok,
+                "failed": counts.failed,
+                "missing": counts.missing,
+                "bytes": bytes,
+                "elapsed_ms": start.elapsed().as_secs_f64() * 1000.0,
+            },
+            "stopped_early": stopped || interrupted,
+            "interrupted": interrupted,
+            "exit_code": exit_code,
+        });
+        let written = serde_json::to_string_pretty(&document)
+            .map_err(io::Error::from)
+            .and_then(|text| fs::write(report, text + "\n"));
+        if let Err(e) = written {
+            eprintln!("Error writing report {}: {}", report, e);
+            exit_code = EXIT_IO_ERROR;
+        }
+    }
+    exit_code
+}
+
+/// The `--json` object printed for a verified entry: its `--report` record,
+/// or `None` when `--errors-only` leaves a passing entry out.
+fn verify_json_record(entry: &VerifiedEntry, options: &Options) -> Option<serde_json::Value> {
+    if options.errors_only && entry.result.is_ok() {
+        return None;
+    }
+    Some(report_record(entry))
+}
+
+/// One entry of the `--report` file. CRCs are 8 uppercase hex digits;
+/// `actual` is null when the file was not hashed.
+fn report_record(entry: &VerifiedEntry) -> serde_json::Value {
+    let hex = |crc: u32| format!("{:08X}", crc);
+    let mut record = json!({
+        "filename": entry.result.filename(),
+        "expected": hex(entry.expected),
+        "actual": null,
+        "bytes": entry.bytes,
+    });
+    match &entry.result {
+        VerifyResult::Ok { crc, .. } => {
+            record["status"] = json!("ok");
+            record["actual"] = json!(hex(*crc));
+        }
+        VerifyResult::Mismatch { actual, .. } => {
+            record["status"] = json!("mismatch");
+            record["actual"] = json!(hex(*actual));
+        }
+        VerifyResult::Missing { .. } => record["status"] = json!("missing"),
+        VerifyResult::SizeMismatch {
+            expected, actual, ..
+        } => {
+            record["status"] = json!("size_mismatch");
+            record["expected_size"] = json!(expected);
+            record["actual_size"] = json!(actual);
+        }
+        VerifyResult::Error { message, .. } => {
+            record["status"] = json!("error");
+            record["message"] = json!(message);
+        }
+        VerifyResult::Rejected { reason, .. } => {
+            record["status"] = json!("rejected");
+            record["message"] = json!(reason);
+        }
+    }
+    if let Some(found) = &entry.found_as {
+        record["checked_path"] = json!(found.to_string_lossy());
+    }
+    record
+}
+
+/// The start of the closing summary, e.g. "Verified 342 files (12.3 GiB) in 4.1s".
+fn summary_head(verb: &str, files: u64, bytes: u64, elapsed: Duration) -> String {
+    format!(
+        "{} {} file{} ({}) in {:.1}s",
+        verb,
+        files,
+        if files == 1 { "" } else { "s" },
+        format_size(bytes),
+        elapsed.as_secs_f64()
+    )
+}
+
+fn hash_path(path: &str, options: &Options) -> io::Result<Hashed> {
+    let control = read_control();
+    if control.is_cancelled() {
+        return Err(HashError::Cancelled.into());
+    }
+    let (reader, expected_len): (Box<dyn Read>, u64) = if options.split {
+        let parts = split_parts(Path::new(path))?;
+        let mut len = 0;
+        for part in &parts {
+            len += fs::metadata(part)?.len();
+        }
+        (Box::new(SplitReader::new(parts)), len)
+    } else {
+        let file = hash::open_file(Path::new(path))?;
+        let len = file.metadata()?.len();
+        (Box::new(file), len)
+    };
+    let start = Instant::now();
+    let (digest, bytes) = hash_reader_with_len(BufReader::new(control.wrap(reader)), options.algo)?;
+    log::trace!("{}: read {} bytes in {:?}", path, bytes, start.elapsed());
+    if options.strict {
+        hash::check_len(expected_len, bytes)?;
+    }
+    Ok(Hashed {
+        digest,
+        bytes,
+        elapsed: start.elapsed(),
+    })
+}
+
+/// `path` as printed for a hashed file: as given, or relative to
+/// `--relative-to` when set. A path that cannot be made relative (on another
+/// drive) is printed absolute.
+fn display_path(path: &str, options: &Options) -> String {
+    let Some(base) = &options.relative_to else {
+        return path.to_string();
+    };
+    let (Ok(absolute), Ok(base)) = (std::path::absolute(path), std::path::absolute(base)) else {
+        return path.to_string();
+    };
+    relative_path(&absolute, &base)
+        .unwrap_or(absolute)
+        .to_string_lossy()
+        .into_owned()
+}
+
+fn format_digest(options: &Options, digest: &Digest) -> String {
+    match options.format {
+        Some(DigestFormat::Upper) => digest.to_hex_upper(),
+        Some(DigestFormat::Lower) => digest.to_hex_lower(),
+        Some(DigestFormat::Colon) => digest.to_hex_colon(),
+        None => options.algo.format(digest),
+    }
+}
+
+fn json_record(path: &str, options: &Options, result: &io::Result<Hashed>) -> serde_json::Value {
+    match result {
+        Ok(hashed) => {
+            let mut record = json!({
+                "path": path,
+                "algo": options.algo.name(),
+                "digest": format_digest(options, &hashed.digest),
+            });
+            if options.timing {
+                let secs = hashed.elapsed.as_secs_f64();
+                let mb_per_sec = if secs > 0.0 {
+                    hashed.bytes as f64 / (1024.0 * 1024.0) / secs
+                } else {
+                    0.0
+                };
+                record["bytes"] = json!(hashed.bytes);
+                record["elapsed_ms"] = json!(secs * 1000.0);
+                record["mb_per_sec"] = json!(mb_per_sec);
+            }
+            record
+        }
+        Err(e) => json!({ "path": path, "error": e.to_string() }),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(args: &[&str]) -> Result<Options, String> {
+        let args: Vec<String> = args.iter().map(|arg| arg.to_string()).collect();
+        parse_args(&args)
+    }
+
+    /// A fresh directory under the system temp dir, removed on drop.
+    struct TempDir(std::path::PathBuf);
+
+    impl TempDir {
+        fn new(name: &str) -> Self {
+            let dir = env::temp_dir().join(format!("mtsfv_cli_{}_{}", name, process::id()));
+            let _ = fs::remove_dir_all(&dir);
+            fs::create_dir_all(&dir).unwrap();
+            TempDir(dir)
+        }
+    }
+
+    impl Drop for TempDir {
+        fn drop(&mut self) {
+            let _ = fs::remove_dir_all(&self.0);
+        }
+    }
+
+    #[test]
+    fn test_verify_json_errors_only() {
+        let dir = TempDir::new("verify_json");
+        fs::write(dir.0.join("good.bin"), b"123456789").unwrap();
+        fs::write(dir.0.join("bad.bin"), b"12345678").unwrap();
+        let entries =
+            parse_sfv("good.bin CBF43926\nbad.bin CBF43926\ngone.bin 00000000\n").unwrap();
+        let records = |options: &Options| {
+            verify_entries_with(&dir.0, entries.clone(), verify_options(options))
+                .filter_map(|entry| verify_json_record(&entry, options))
+                .map(|record| record["status"].as_str().unwrap().to_string())
+                .collect::<Vec<_>>()
+        };
+
+        let all = parse(&["--json", "--verify", "x.sfv"]).unwrap();
+        assert_eq!(records(&all), ["ok", "mismatch", "missing"]);
+        let failures = parse(&["--json", "--errors-only", "--verify", "x.sfv"]).unwrap();
+        assert_eq!(records(&failures), ["mismatch", "missing"]);
+    }
+
+    #[test]
+    fn test_json_rejected_where_it_does_nothing() {
+        assert!(parse(&["--json", "--check-manifest", "x.sfv"]).is_err());
+        assert!(parse(&["--json", "--deep-diff", "a.bin", "b.bin"]).is_err());
+    }
+
+    #[test]
+    fn test_verify_rejects_other_inputs() {
+        assert!(parse(&["--verify", "m.sfv"]).is_ok());
+        assert!(parse(&["--verify", "m.sfv", "--files-from", "list.txt"]).is_err());
+        assert!(parse(&["--verify", "m.sfv", "--stdin"]).is_err());
+        assert!(parse(&["--verify", "m.sfv", "a.bin"]).is_err());
+    }
+
+    #[test]
+    fn test_paths_requires_create() {
+        for style in ["relative", "absolute"] {
+            assert!(parse(&["--paths", style, "a.bin"]).is_err());
+            let options = parse(&["--create", "r.sfv", "--paths", style, "a.bin"]).unwrap();
+            assert!(options.path_style.is_some());
+        }
+        assert!(parse(&["--create", "r.sfv", "--algo", "md5", "a.bin"]).is_err());
+    }
+
+    #[test]
+    fn test_parse_crc() {
+        assert_eq!(parse_crc("CBF43926"), Ok(0xCBF43926));
+        assert_eq!(parse_crc("0xcbf43926"), Ok(0xCBF43926));
+        assert_eq!(parse_crc("0X1"), Ok(1));
+        for bad in ["", "0x", "CBF439261", "CBF4392G", "-1", "+1", " 1"] {
+            assert!(parse_crc(bad).is_err(), "{bad:?}");
+        }
+    }
+
+    #[test]
+    fn test_expand_globs() {
+        let dir = TempDir::new("globs");
+        for name in ["b.bin", "a.bin", "c.txt"] {
+            fs::write(dir.0.join(name), b"x").unwrap();
+        }
+        fs::create_dir(dir.0.join("sub.bin")).unwrap();
+        let in_dir = |name: &str| dir.0.join(name).to_string_lossy().into_owned();
+
+        let args = [in_dir("*.bin"), "plain.txt".to_string(), in_dir("*.iso")];
+        let (files, unmatched) = expand_globs(&args).unwrap();
+        assert_eq!(
+            files,
+            [in_dir("a.bin"), in_dir("b.bin"), "plain.txt".to_string()]
+        );
+        assert_eq!(unmatched, [in_dir("*.iso")]);
+
+        assert!(expand_globs(&[in_dir("[")]).is_err());
+    }
+
+    #[test]
+    fn test_create_finds_escaping_entries() {
+        let dir = TempDir::new("create_escape");
+        let a = dir.0.join("a.bin").to_string_lossy().into_owned();
+        let b = dir
+            .0
+            .join("out")
+            .join("b.bin")
+            .to_string_lossy()
+            .into_owned();
+        let inside = dir.0.join("m.sfv");
+        let below = dir.0.join("out").join("m.sfv");
+
+        for style in [PathStyle::Relative, PathStyle::Absolute] {
+            let files = [a.clone(), b.clone()];
+            assert_eq!(escaping_entry(&inside, &files, style).unwrap(), None);
+            let (file, stored) = escaping_entry(&below, &files, style).unwrap().unwrap();
+            assert_eq!(file, a);
+            assert!(safe_entry_path(below.parent().unwrap(), &stored).is_err());
+        }
+        let (_, stored) = escaping_entry(&below, &[a], PathStyle::Relative)
+            .unwrap()
+            .unwrap();
+        assert_eq!(stored, Path::new("..").join("a.bin").to_string_lossy());
+    }
+
+    #[test]
+    fn test_pace_wait() {
+        let start = Instant::now();
+        let mut pace = None;
+        // 1 MiB at 1 MiB/s is due a second after the stretch began.
+        let wait = pace_wait(&mut pace, start, 1 << 20, 1 << 20);
+        assert_eq!(wait, Duration::from_secs(1));
+        let later = start + Duration::from_millis(1500);
+        assert_eq!(
+            pace_wait(&mut pace, later, 1 << 19, 1 << 20),
+            Duration::ZERO
+        );
+        // Ten idle seconds do not buy ten seconds at full speed.
+        let idle = later + Duration::from_secs(10);
+        assert_eq!(
+            pace_wait(&mut pace, idle, 1 << 19, 1 << 20),
+            Duration::from_millis(500)
+        );
+        assert_eq!(pace, Some((idle, 1 << 19)));
     }
 }
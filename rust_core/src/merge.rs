@@ -0,0 +1,203 @@
+use crate::sfv::{SfvEntry, SfvManifest};
+use crate::verify::entry_components;
+use std::collections::HashMap;
+use std::fmt;
+use std::io;
+use std::path::{Component, Path, PathBuf};
+
+/// Two of the merged manifests list the same file with different CRCs.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MergeCollision {
+    /// The file's name relative to the base directory.
+    pub filename: String,
+    /// The manifest whose entry was kept.
+    pub first: PathBuf,
+    /// The manifest whose entry conflicts with it.
+    pub second: PathBuf,
+}
+
+/// Why [`merge_sfv`] could not produce a manifest.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MergeError {
+    /// A manifest lives outside the base directory, so its entries cannot
+    /// be listed relative to it.
+    OutsideBase(PathBuf),
+    /// Files listed with different CRCs, in the order they were found.
+    Collisions(Vec<MergeCollision>),
+}
+
+impl fmt::Display for MergeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MergeError::OutsideBase(path) => {
+                write!(f, "{} is outside the base directory", path.display())
+            }
+            MergeError::Collisions(collisions) => {
+                f.write_str("conflicting entries:")?;
+                for collision in collisions {
+                    write!(
+                        f,
+                        " {} ({} vs {})",
+                        collision.filename,
+                        collision.first.display(),
+                        collision.second.display()
+                    )?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+impl std::error::Error for MergeError {}
+
+impl From<MergeError> for io::Error {
+    fn from(err: MergeError) -> Self {
+        io::Error::new(io::ErrorKind::InvalidData, err)
+    }
+}
+
+/// Combine manifests from several directories into one listing every file
+/// relative to `base`.
+///
+/// Each manifest is given with the path it was read from, and its relative
+/// filenames are re-based from that file's directory onto `base`, which must
+/// contain it; absolute filenames are kept as they are. Entries are
+/// concatenated in input order with their comments and sizes. A file listed
+/// more than once with the same CRC is kept once; with different CRCs it is
+/// a collision, and every collision is reported rather than just the first.
+/// Paths use `\` if any input entry does, `/` otherwise. Header and trailing
+/// comments of the inputs are not copied.
+pub fn merge_sfv(
+    manifests: &[(PathBuf, SfvManifest)],
+    base: &Path,
+) -> Result<SfvManifest, MergeError> {
+    let base = std::path::absolute(base).unwrap_or_else(|_| base.to_path_buf());
+    let separator = if manifests
+        .iter()
+        .flat_map(|(_, manifest)| &manifest.entries)
+        .any(|entry| entry.filename.contains('\\'))
+    {
+        "\\"
+    } else {
+        "/"
+    };
+
+    let mut merged = SfvManifest::default();
+    let mut seen: HashMap<String, (u32, &Path)> = HashMap::new();
+    let mut collisions = Vec::new();
+    for (path, manifest) in manifests {
+        let prefix =
+            manifest_prefix(path, &base).ok_or_else(|| MergeError::OutsideBase(path.clone()))?;
+        for entry in &manifest.entries {
+            let filename = if Path::new(&entry.filename).is_absolute() {
+                entry.filename.clone()
+            } else {
+                rebase(&prefix, &entry.filename).join(separator)
+            };
+            match seen.get(&filename) {
+                Some(&(crc, _)) if crc == entry.crc => {}
+                Some(&(_, first)) => collisions.push(MergeCollision {
+                    filename,
+                    first: first.to_path_buf(),
+                    second: path.clone(),
+                }),
+                None => {
+                    seen.insert(filename.clone(), (entry.crc, path));
+                    merged.entries.push(SfvEntry {
+                        filename,
+                        ..entry.clone()
+                    });
+                }
+            }
+        }
+    }
+
+    if collisions.is_empty() {
+        Ok(merged)
+    } else {
+        Err(MergeError::Collisions(collisions))
+    }
+}
+
+/// Components of the directory holding the manifest at `path`, relative to
+/// `base`; `None` when it is not inside `base`.
+fn manifest_prefix(path: &Path, base: &Path) -> Option<Vec<String>> {
+    let path = std::path::absolute(path).ok()?;
+    let dir = path.parent()?;
+    let mut prefix = Vec::new();
+    for component in dir.strip_prefix(base).ok()?.components() {
+        match component {
+            Component::Normal(part) => prefix.push(part.to_string_lossy().into_owned()),
+            Component::ParentDir => prefix.push("..".to_string()),
+            _ => {}
+        }
+    }
+    Some(prefix)
+}
+
+/// `filename` from a manifest in `prefix`, as components relative to the
+/// base, with `..` folded into the prefix where it can be.
+fn rebase(prefix: &[String], filename: &str) -> Vec<String> {
+    let mut parts = prefix.to_vec();
+    for part in entry_components(filename) {
+        if part == ".." && parts.last().is_some_and(|last| last != "..") {
+            parts.pop();
+        } else {
+            parts.push(part.to_string());
+        }
+    }
+    parts
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::TempDir;
+
+    #[test]
+    fn test_merge_sibling_manifests() {
+        let dir = TempDir::new("merge_sfv");
+        let a =
+            SfvManifest::parse("; size 9\r\nx.bin CBF43926\r\nshared.bin 00000001\r\n").unwrap();
+        let b = SfvManifest::parse("y.bin 00000002\r\n../a/shared.bin 00000001\r\n").unwrap();
+        let manifests = [
+            (dir.path().join("a/a.sfv"), a),
+            (dir.path().join("b/b.sfv"), b),
+        ];
+
+        let merged = merge_sfv(&manifests, dir.path()).unwrap();
+        let names: Vec<_> = merged.entries.iter().map(|e| e.filename.as_str()).collect();
+        assert_eq!(names, ["a/x.bin", "a/shared.bin", "b/y.bin"]);
+        assert_eq!(merged.entries[0].size, Some(9));
+        assert_eq!(merged.entries[2].crc, 2);
+        assert!(merged.header_comments.is_empty());
+
+        let round_trip = SfvManifest::parse(&merged.to_string()).unwrap();
+        assert_eq!(round_trip.entries, merged.entries);
+    }
+
+    #[test]
+    fn test_merge_reports_collisions_and_outside_base() {
+        let dir = TempDir::new("merge_sfv_collision");
+        let a = SfvManifest::parse("sub\\x.bin 00000001\r\n").unwrap();
+        let b = SfvManifest::parse("x.bin 00000002\r\n").unwrap();
+        let manifests = [
+            (dir.path().join("all.sfv"), a),
+            (dir.path().join("sub/sub.sfv"), b),
+        ];
+
+        assert_eq!(
+            merge_sfv(&manifests, dir.path()),
+            Err(MergeError::Collisions(vec![MergeCollision {
+                filename: "sub\\x.bin".to_string(),
+                first: dir.path().join("all.sfv"),
+                second: dir.path().join("sub/sub.sfv"),
+            }]))
+        );
+        assert_eq!(
+            merge_sfv(&manifests, &dir.path().join("sub")),
+            Err(MergeError::OutsideBase(dir.path().join("all.sfv")))
+        );
+    }
+}
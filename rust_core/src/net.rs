@@ -0,0 +1,158 @@
+//! Verifying against a manifest fetched over HTTP(S).
+
+use crate::sfv::parse_sfv_bytes;
+use crate::verify::{verify_entries, VerifyResult};
+use std::fmt;
+use std::io::{self, Read};
+use std::path::Path;
+
+/// Largest manifest [`verify_sfv_url`] will download. Real manifests are a
+/// few KB; this only stops a wrong URL from streaming a whole release.
+pub const MAX_MANIFEST_BYTES: u64 = 16 << 20;
+
+/// Why a manifest could not be fetched.
+///
+/// Carried inside the `io::Error` returned by [`verify_sfv_url`], as
+/// [`crate::HashError`] is for hashing, so it can be told apart from a
+/// manifest that was fetched but does not parse.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FetchError {
+    /// The server could not be reached or the transfer broke off.
+    Network(String),
+    /// The server answered with an error status.
+    Status(u16),
+    /// The response was larger than [`MAX_MANIFEST_BYTES`].
+    TooLarge,
+}
+
+impl fmt::Display for FetchError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FetchError::Network(message) => write!(f, "network error: {}", message),
+            FetchError::Status(code) => write!(f, "server returned HTTP {}", code),
+            FetchError::TooLarge => write!(
+                f,
+                "manifest is larger than {} MiB",
+                MAX_MANIFEST_BYTES >> 20
+            ),
+        }
+    }
+}
+
+impl std::error::Error for FetchError {}
+
+impl From<FetchError> for io::Error {
+    fn from(err: FetchError) -> Self {
+        let kind = match err {
+            FetchError::Network(_) | FetchError::Status(_) => io::ErrorKind::Other,
+            FetchError::TooLarge => io::ErrorKind::FileTooLarge,
+        };
+        io::Error::new(kind, err)
+    }
+}
+
+/// Download the `.sfv` manifest at `url` and verify its entries against the
+/// files under `base_dir`.
+///
+/// Filenames resolve against `base_dir` as [`verify_entries`] describes; the
+/// URL's own path plays no part. Fetching or parsing the manifest fails the
+/// whole call, with a [`FetchError`] inside the `io::Error` for the former
+/// and [`io::ErrorKind::InvalidData`] for the latter; per-file problems are
+/// reported in the returned results as for [`crate::verify_sfv`].
+pub fn verify_sfv_url(url: &str, base_dir: &Path) -> io::Result<Vec<VerifyResult>> {
+    let manifest = fetch(url)?;
+    let entries = parse_sfv_bytes(&manifest)?;
+    Ok(verify_entries(base_dir, entries).collect())
+}
+
+/// The body of a GET of `url`, up to [`MAX_MANIFEST_BYTES`].
+fn fetch(url: &str) -> io::Result<Vec<u8>> {
+    log::debug!("fetching manifest from {}", url);
+    let response = match ureq::get(url).call() {
+        Ok(response) => response,
+        Err(ureq::Error::Status(code, _)) => return Err(FetchError::Status(code).into()),
+        Err(e) => return Err(FetchError::Network(e.to_string()).into()),
+    };
+    let mut body = Vec::new();
+    response
+        .into_reader()
+        .take(MAX_MANIFEST_BYTES + 1)
+        .read_to_end(&mut body)
+        .map_err(|e| FetchError::Network(e.to_string()))?;
+    if body.len() as u64 > MAX_MANIFEST_BYTES {
+        return Err(FetchError::TooLarge.into());
+    }
+    Ok(body)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::TempDir;
+    use std::io::Write;
+    use std::net::TcpListener;
+
+    /// Serve one HTTP response on a local port, returning the URL to fetch.
+    fn serve_once(status: &'static str, body: &'static [u8]) -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/release.sfv", listener.local_addr().unwrap());
+        std::thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let mut request = [0u8; 1024];
+            let _ = stream.read(&mut request);
+            let head = format!(
+                "HTTP/1.1 {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+                status,
+                body.len()
+            );
+            let _ = stream.write_all(head.as_bytes());
+            let _ = stream.write_all(body);
+        });
+        url
+    }
+
+    #[test]
+    fn test_verify_sfv_url() {
+        let dir = TempDir::new("verify_url");
+        dir.write("good.bin", b"123456789");
+        let url = serve_once("200 OK", b"good.bin CBF43926\r\ngone.bin 00000000\r\n");
+
+        let results = verify_sfv_url(&url, dir.path()).unwrap();
+        assert!(results[0].is_ok());
+        assert_eq!(
+            results[1],
+            VerifyResult::Missing {
+                filename: "gone.bin".to_string()
+            }
+        );
+    }
+
+    #[test]
+    fn test_verify_sfv_url_errors() {
+        let dir = TempDir::new("verify_url_errors");
+        let fetch_error = |err: io::Error| {
+            err.get_ref()
+                .and_then(|e| e.downcast_ref::<FetchError>())
+                .cloned()
+        };
+
+        let url = serve_once("404 Not Found", b"");
+        let err = verify_sfv_url(&url, dir.path()).unwrap_err();
+        assert_eq!(fetch_error(err), Some(FetchError::Status(404)));
+
+        let url = serve_once("200 OK", b"not a manifest line\r\n");
+        let err = verify_sfv_url(&url, dir.path()).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+        assert_eq!(fetch_error(err), None);
+
+        // Nothing listens on a port just released.
+        let port = TcpListener::bind("127.0.0.1:0")
+            .unwrap()
+            .local_addr()
+            .unwrap()
+            .port();
+        let err =
+            verify_sfv_url(&format!("http://127.0.0.1:{}/x.sfv", port), dir.path()).unwrap_err();
+        assert!(matches!(fetch_error(err), Some(FetchError::Network(_))));
+    }
+}
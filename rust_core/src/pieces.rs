@@ -0,0 +1,116 @@
+use crate::hash::open_file;
+use sha1::{Digest as _, Sha1};
+use std::io::{self, Read};
+use std::path::Path;
+
+/// Check `path` against BitTorrent piece hashes: the SHA-1 of each
+/// consecutive `piece_len`-byte piece, as listed in a `.torrent` file.
+///
+/// Returns one flag per entry of `expected`, true where the piece is intact,
+/// so a partial download reports which pieces it still needs. Only the last
+/// piece may be shorter than `piece_len`; pieces that the file is too short
+/// to hold in full are bad. Bytes after the last piece are not checked.
+///
+/// Fails with [`io::ErrorKind::InvalidInput`] when `piece_len` is 0.
+pub fn verify_pieces(
+    path: &Path,
+    piece_len: usize,
+    expected: &[[u8; 20]],
+) -> io::Result<Vec<bool>> {
+    verify_pieces_reader(open_file(path)?, piece_len, expected)
+}
+
+/// Like [`verify_pieces`], for the data produced by a reader. A multi-file
+/// torrent hashes its files as one stream, so pass them chained in the
+/// order the torrent lists them.
+pub fn verify_pieces_reader<R: Read>(
+    mut reader: R,
+    piece_len: usize,
+    expected: &[[u8; 20]],
+) -> io::Result<Vec<bool>> {
+    if piece_len == 0 {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "piece length must be at least 1 byte",
+        ));
+    }
+    let mut good = Vec::with_capacity(expected.len());
+    let mut piece = Vec::with_capacity(piece_len);
+    for (index, hash) in expected.iter().enumerate() {
+        piece.clear();
+        (&mut reader)
+            .take(piece_len as u64)
+            .read_to_end(&mut piece)?;
+        let is_last = index + 1 == expected.len();
+        let complete = piece.len() == piece_len || (is_last && !piece.is_empty());
+        good.push(complete && Sha1::digest(&piece)[..] == hash[..]);
+        if piece.len() < piece_len {
+            log::debug!("data ends in piece {} of {}", index + 1, expected.len());
+            good.resize(expected.len(), false);
+            break;
+        }
+    }
+    Ok(good)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::TempDir;
+
+    fn piece_hashes(data: &[u8], piece_len: usize) -> Vec<[u8; 20]> {
+        data.chunks(piece_len)
+            .map(|piece| Sha1::digest(piece).into())
+            .collect()
+    }
+
+    #[test]
+    fn test_verify_pieces_short_last_piece() {
+        let dir = TempDir::new("pieces");
+        let data = crate::test_util::patterned(100_000);
+        let expected = piece_hashes(&data, 16_384);
+        assert_eq!(expected.len(), 7);
+        let path = dir.write("complete.bin", &data);
+        assert_eq!(
+            verify_pieces(&path, 16_384, &expected).unwrap(),
+            vec![true; 7]
+        );
+
+        let mut corrupt = data.clone();
+        corrupt[40_000] ^= 0xFF;
+        corrupt[99_999] ^= 0xFF;
+        let path = dir.write("corrupt.bin", &corrupt);
+        assert_eq!(
+            verify_pieces(&path, 16_384, &expected).unwrap(),
+            vec![true, true, false, true, true, true, false]
+        );
+
+        // A partial download: the third piece is cut short, the rest absent.
+        let path = dir.write("partial.bin", &data[..40_000]);
+        assert_eq!(
+            verify_pieces(&path, 16_384, &expected).unwrap(),
+            vec![true, true, false, false, false, false, false]
+        );
+    }
+
+    #[test]
+    fn test_verify_pieces_exact_multiple_and_errors() {
+        let data = [7u8; 64];
+        let expected = piece_hashes(&data, 32);
+        assert_eq!(
+            verify_pieces_reader(&data[..], 32, &expected).unwrap(),
+            vec![true, true]
+        );
+        assert_eq!(
+            verify_pieces_reader(&data[..32], 32, &expected).unwrap(),
+            vec![true, false]
+        );
+        assert!(verify_pieces_reader(&data[..], 32, &[]).unwrap().is_empty());
+
+        let err = verify_pieces_reader(&data[..], 0, &expected).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+        let dir = TempDir::new("pieces_missing");
+        let err = verify_pieces(&dir.path().join("absent.bin"), 32, &expected).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::NotFound);
+    }
+}
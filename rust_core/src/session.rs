@@ -0,0 +1,95 @@
+//! The file list saved by the GUI's "Save session...", whose entries are
+//! also the lines of the CLI's `--resume` state file.
+
+use crate::hash::HashAlgo;
+use std::path::PathBuf;
+
+/// A saved file list: enough to restore each row's path, expected digest,
+/// algorithm and result, but not its metadata.
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct Session {
+    pub entries: Vec<SessionEntry>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct SessionEntry {
+    pub path: PathBuf,
+    pub expected: Option<u32>,
+    /// An expected digest longer than 32 bits, as lowercase hex.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub expected_digest: Option<String>,
+    #[serde(default = "algo_name::crc32", with = "algo_name")]
+    pub algo: HashAlgo,
+    pub state: SessionState,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "snake_case", tag = "status")]
+pub enum SessionState {
+    /// Still hashing when saved; hashed again on restore.
+    Pending,
+    Missing,
+    Done {
+        crc: u32,
+    },
+    /// A digest longer than 32 bits, as lowercase hex.
+    Hashed {
+        digest: String,
+    },
+    Error {
+        message: String,
+    },
+}
+
+/// Stores a [`HashAlgo`] by its command-line name, for use with
+/// `#[serde(with = "...")]`.
+pub mod algo_name {
+    use crate::hash::HashAlgo;
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(algo: &HashAlgo, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(algo.name())
+    }
+
+    /// An unknown name falls back to CRC32 rather than discarding the other
+    /// saved settings.
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<HashAlgo, D::Error> {
+        let name = String::deserialize(deserializer)?;
+        Ok(name.parse().unwrap_or(HashAlgo::Crc32))
+    }
+
+    /// Sessions saved before rows had an algorithm hold CRC32 results.
+    pub fn crc32() -> HashAlgo {
+        HashAlgo::Crc32
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_session_entry_round_trip() {
+        let entry = SessionEntry {
+            path: PathBuf::from("/data/a.bin"),
+            expected: Some(0xCBF43926),
+            expected_digest: None,
+            algo: HashAlgo::Crc32,
+            state: SessionState::Done { crc: 0xCBF43926 },
+        };
+        let line = serde_json::to_string(&entry).unwrap();
+        assert_eq!(
+            line,
+            r#"{"path":"/data/a.bin","expected":3421780262,"algo":"crc32","state":{"status":"done","crc":3421780262}}"#
+        );
+        assert_eq!(serde_json::from_str::<SessionEntry>(&line).unwrap(), entry);
+    }
+
+    #[test]
+    fn test_session_entry_without_algo_is_crc32() {
+        let line = r#"{"path":"a.bin","expected":null,"state":{"status":"missing"}}"#;
+        let entry: SessionEntry = serde_json::from_str(line).unwrap();
+        assert_eq!(entry.algo, HashAlgo::Crc32);
+        assert_eq!(entry.state, SessionState::Missing);
+    }
+}
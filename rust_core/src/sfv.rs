@@ -0,0 +1,717 @@
+use crate::verify::fold_dots;
+use std::borrow::Cow;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::{Component, Path, PathBuf};
+
+/// One `filename CRC32` line from an `.sfv` manifest.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SfvEntry {
+    pub filename: String,
+    pub crc: u32,
+    /// Comment lines directly above this entry, without the leading `;`.
+    pub comments: Vec<String>,
+    /// Size of the file in bytes, when the manifest records it; written as a
+    /// `; size <bytes>` comment line just above the entry.
+    pub size: Option<u64>,
+}
+
+/// Line terminator used when writing a manifest.
+///
+/// Only the line breaks change; filenames and CRC values are written the same
+/// way whichever is chosen.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum LineEnding {
+    /// `\r\n`, as QuickSFV and WIN-SFV32 write.
+    #[default]
+    Crlf,
+    /// `\n`.
+    Lf,
+    /// `\r\n` on Windows and `\n` elsewhere.
+    Native,
+}
+
+impl LineEnding {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            LineEnding::Crlf => "\r\n",
+            LineEnding::Lf => "\n",
+            LineEnding::Native if cfg!(windows) => "\r\n",
+            LineEnding::Native => "\n",
+        }
+    }
+}
+
+/// How [`write_sfv`] stores entry filenames.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum PathStyle {
+    /// Write filenames unchanged.
+    #[default]
+    AsGiven,
+    /// Take each filename as a path from the current directory and store it
+    /// relative to the directory the manifest is written to. This is what
+    /// QuickSFV does, and keeps the manifest valid when the folder moves.
+    Relative,
+    /// Take each filename as a path from the current directory and store it
+    /// as an absolute path with the platform's separators.
+    Absolute,
+}
+
+/// A whole `.sfv` manifest, including its comment lines.
+///
+/// Comments before the first entry form the header (generators put their
+/// banner and per-file size/timestamp lines there); later comments belong to
+/// the entry that follows them. Blank lines are not preserved.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SfvManifest {
+    pub header_comments: Vec<String>,
+    pub entries: Vec<SfvEntry>,
+    /// Comments after the last entry.
+    pub trailing_comments: Vec<String>,
+}
+
+impl SfvManifest {
+    /// Parse the text of an `.sfv` manifest; see [`parse_sfv`].
+    pub fn parse(text: &str) -> Result<Self, SfvParseError> {
+        let (manifest, errors) = SfvManifest::parse_lines(text, false);
+        match errors.into_iter().next() {
+            Some(err) => Err(err),
+            None => Ok(manifest),
+        }
+    }
+
+    /// Like [`parse`](Self::parse), but skip the lines that cannot be
+    /// understood instead of failing, and return an error for each of them
+    /// in line order.
+    pub fn parse_lenient(text: &str) -> (Self, Vec<SfvParseError>) {
+        SfvManifest::parse_lines(text, true)
+    }
+
+    fn parse_lines(text: &str, keep_going: bool) -> (Self, Vec<SfvParseError>) {
+        let mut manifest = SfvManifest::default();
+        let mut errors = Vec::new();
+        let mut pending = Vec::new();
+
+        // Some Windows editors start UTF-8 files with a byte order mark.
+        let text = text.strip_prefix('\u{feff}').unwrap_or(text);
+        for (index, line) in text.lines().enumerate() {
+            let line = line.trim_end_matches('\r');
+            if let Some(comment) = line.strip_prefix(';') {
+                pending.push(comment.to_string());
+                continue;
+            }
+            if line.trim().is_empty() {
+                continue;
+            }
+
+            let (filename, crc) = match parse_entry_line(line, index + 1) {
+                Ok(parsed) => parsed,
+                Err(err) => {
+                    errors.push(err);
+                    if keep_going {
+                        continue;
+                    }
+                    break;
+                }
+            };
+            let size = pending.last().and_then(|comment| parse_entry_size(comment));
+            if size.is_some() {
+                pending.pop();
+            }
+            if manifest.entries.is_empty() {
+                manifest.header_comments = std::mem::take(&mut pending);
+            }
+            manifest.entries.push(SfvEntry {
+                filename,
+                crc,
+                comments: std::mem::take(&mut pending),
+                size,
+            });
+        }
+
+        if manifest.entries.is_empty() {
+            manifest.header_comments = pending;
+        } else {
+            manifest.trailing_comments = pending;
+        }
+        (manifest, errors)
+    }
+
+    /// Parse raw manifest bytes; see [`parse_sfv_bytes`].
+    pub fn parse_bytes(bytes: &[u8]) -> Result<Self, SfvParseError> {
+        SfvManifest::parse(&decode(bytes))
+    }
+
+    /// Read and parse an `.sfv` manifest from disk.
+    pub fn read(path: &Path) -> io::Result<Self> {
+        let bytes = fs::read(path)?;
+        Ok(SfvManifest::parse_bytes(&bytes)?)
+    }
+
+    /// Read an `.sfv` manifest from disk and parse it with
+    /// [`parse_lenient`](Self::parse_lenient). Only reading the file can fail.
+    pub fn read_lenient(path: &Path) -> io::Result<(Self, Vec<SfvParseError>)> {
+        let bytes = fs::read(path)?;
+        Ok(SfvManifest::parse_lenient(&decode(&bytes)))
+    }
+
+    /// Add `text` to the header, one comment line per line of text, each
+    /// after a space as generators write their banners (`; Release 1.0`).
+    /// The lines come back as header comments when the manifest is parsed.
+    pub fn add_header_comment(&mut self, text: &str) {
+        for line in text.lines() {
+            let line = line.trim_end_matches('\r');
+            self.header_comments.push(if line.is_empty() {
+                String::new()
+            } else {
+                format!(" {}", line)
+            });
+        }
+    }
+
+    /// Write the manifest to `path` with CRLF line endings, as QuickSFV does.
+    pub fn write(&self, path: &Path) -> io::Result<()> {
+        write_sfv(path, self, LineEnding::Crlf, PathStyle::AsGiven)
+    }
+
+    /// The manifest text with each line ended by `line_ending`. The
+    /// [`Display`](fmt::Display) impl uses [`LineEnding::Crlf`].
+    pub fn to_string_with(&self, line_ending: LineEnding) -> String {
+        let eol = line_ending.as_str();
+        let mut text = String::new();
+        for comment in &self.header_comments {
+            text.push_str(&format!(";{}{}", comment, eol));
+        }
+        for entry in &self.entries {
+            for comment in &entry.comments {
+                text.push_str(&format!(";{}{}", comment, eol));
+            }
+            if let Some(size) = entry.size {
+                text.push_str(&format!("; size {}{}", size, eol));
+            }
+            text.push_str(&format!("{} {:08X}{}", entry.filename, entry.crc, eol));
+        }
+        for comment in &self.trailing_comments {
+            text.push_str(&format!(";{}{}", comment, eol));
+        }
+        text
+    }
+
+    /// Size of `filename` as recorded by a header comment of the form
+    /// `; <size> <time> <date> <filename>`, written by WIN-SFV32 and
+    /// QuickSFV. Comments in any other format are ignored.
+    pub fn recorded_size(&self, filename: &str) -> Option<u64> {
+        self.header_comments
+            .iter()
+            .find_map(|comment| parse_size_comment(comment, filename))
+    }
+
+    /// The entries, with [`SfvEntry::size`] filled in from a WIN-SFV32 header
+    /// comment (see [`recorded_size`](Self::recorded_size)) where the entry
+    /// has no size of its own.
+    pub fn into_entries(self) -> Vec<SfvEntry> {
+        let sizes: Vec<_> = self
+            .entries
+            .iter()
+            .map(|entry| entry.size.or_else(|| self.recorded_size(&entry.filename)))
+            .collect();
+        let mut entries = self.entries;
+        for (entry, size) in entries.iter_mut().zip(sizes) {
+            entry.size = size;
+        }
+        entries
+    }
+}
+
+impl fmt::Display for SfvManifest {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.to_string_with(LineEnding::Crlf))
+    }
+}
+
+/// The size in a `; size <bytes>` entry comment, given without the `;`.
+fn parse_entry_size(comment: &str) -> Option<u64> {
+    let size = comment.strip_prefix(" size ")?;
+    if size.is_empty() || !size.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    size.parse().ok()
+}
+
+fn parse_size_comment(comment: &str, filename: &str) -> Option<u64> {
+    let rest = comment.trim_start();
+    let (size, rest) = rest.split_once(char::is_whitespace)?;
+    let size = size.parse().ok()?;
+    let (_time, rest) = rest.trim_start().split_once(char::is_whitespace)?;
+    let (_date, name) = rest.trim_start().split_once(char::is_whitespace)?;
+    (name.trim() == filename).then_some(size)
+}
+
+/// A line of an `.sfv` manifest that could not be understood.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SfvParseError {
+    /// 1-based line number.
+    pub line: usize,
+    pub message: String,
+}
+
+impl fmt::Display for SfvParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "line {}: {}", self.line, self.message)
+    }
+}
+
+impl std::error::Error for SfvParseError {}
+
+impl From<SfvParseError> for io::Error {
+    fn from(err: SfvParseError) -> Self {
+        io::Error::new(io::ErrorKind::InvalidData, err)
+    }
+}
+
+/// Parse the text of an `.sfv` manifest.
+///
+/// Blank lines and `;` comments are skipped. Each remaining line is split at
+/// its last run of whitespace, so filenames may themselves contain spaces.
+/// Trailing spaces and tabs, a missing final newline and a UTF-8 byte order
+/// mark are tolerated; a CRC that is not exactly 8 hex digits is not.
+/// Recorded file sizes are filled in as [`SfvManifest::into_entries`] does.
+/// Use [`SfvManifest::parse`] to keep the comments.
+pub fn parse_sfv(text: &str) -> Result<Vec<SfvEntry>, SfvParseError> {
+    SfvManifest::parse(text).map(SfvManifest::into_entries)
+}
+
+fn parse_entry_line(line: &str, line_no: usize) -> Result<(String, u32), SfvParseError> {
+    let Some((filename, crc_text)) = line.trim_end().rsplit_once(char::is_whitespace) else {
+        return Err(SfvParseError {
+            line: line_no,
+            message: "expected '<filename> <crc32>'".to_string(),
+        });
+    };
+    let filename = filename.trim_end();
+    if filename.is_empty() {
+        return Err(SfvParseError {
+            line: line_no,
+            message: "missing filename".to_string(),
+        });
+    }
+    if crc_text.len() != 8 || !crc_text.bytes().all(|b| b.is_ascii_hexdigit()) {
+        return Err(SfvParseError {
+            line: line_no,
+            message: format!("invalid CRC32 '{}'", crc_text),
+        });
+    }
+    let crc = u32::from_str_radix(crc_text, 16).map_err(|_| SfvParseError {
+        line: line_no,
+        message: format!("invalid CRC32 '{}'", crc_text),
+    })?;
+    Ok((filename.to_string(), crc))
+}
+
+/// Manifest text from its raw bytes, as [`parse_sfv_bytes`] describes.
+fn decode(bytes: &[u8]) -> Cow<'_, str> {
+    match std::str::from_utf8(bytes) {
+        Ok(text) => Cow::Borrowed(text),
+        Err(_) => Cow::Owned(decode_ansi(bytes)),
+    }
+}
+
+/// Parse the raw bytes of an `.sfv` manifest.
+///
+/// Manifests are decoded as UTF-8 when valid. Older tools wrote them in the
+/// ANSI code page instead, so anything else is decoded with the system code
+/// page on Windows and as Windows-1252 elsewhere.
+pub fn parse_sfv_bytes(bytes: &[u8]) -> Result<Vec<SfvEntry>, SfvParseError> {
+    SfvManifest::parse_bytes(bytes).map(SfvManifest::into_entries)
+}
+
+/// Read and parse an `.sfv` manifest from disk.
+pub fn read_sfv(path: &Path) -> io::Result<Vec<SfvEntry>> {
+    SfvManifest::read(path).map(SfvManifest::into_entries)
+}
+
+/// Write `manifest` to `path`, ending each line with `line_ending` and
+/// storing filenames as `path_style` says.
+///
+/// Pick [`LineEnding::Crlf`] for QuickSFV compatibility, or match what the
+/// repository the manifest lives in expects so version control does not see
+/// line-ending churn. A [`PathStyle::Relative`] filename that shares no root
+/// with the manifest (another drive on Windows) is written absolute.
+pub fn write_sfv(
+    path: &Path,
+    manifest: &SfvManifest,
+    line_ending: LineEnding,
+    path_style: PathStyle,
+) -> io::Result<()> {
+    if path_style == PathStyle::AsGiven {
+        return fs::write(path, manifest.to_string_with(line_ending));
+    }
+
+    let mut restyled = manifest.clone();
+    store_paths(&mut restyled.entries, path, path_style)?;
+    fs::write(path, restyled.to_string_with(line_ending))
+}
+
+/// Rewrite the filenames of `entries` as [`write_sfv`] would store them in a
+/// manifest at `path`, for entries about to be merged into one already there.
+pub fn store_paths(entries: &mut [SfvEntry], path: &Path, path_style: PathStyle) -> io::Result<()> {
+    if path_style == PathStyle::AsGiven {
+        return Ok(());
+    }
+    let manifest_path = fold_dots(&std::path::absolute(path)?);
+    let base_dir = manifest_path.parent().unwrap_or(Path::new(""));
+    for entry in entries {
+        let absolute = fold_dots(&std::path::absolute(&entry.filename)?);
+        let stored = match path_style {
+            PathStyle::Relative => relative_path(&absolute, base_dir).unwrap_or(absolute),
+            _ => absolute,
+        };
+        entry.filename = stored.to_string_lossy().into_owned();
+    }
+    Ok(())
+}
+
+/// `path` relative to `base_dir`, both absolute, going up with `..` where
+/// needed. `.` and `..` in either are folded first, without looking at the
+/// filesystem. `None` when they do not share a root.
+pub fn relative_path(path: &Path, base_dir: &Path) -> Option<PathBuf> {
+    let (path, base_dir) = (fold_dots(path), fold_dots(base_dir));
+    let mut path_parts = path.components().peekable();
+    let mut base_parts = base_dir.components().peekable();
+    if path_parts.peek() != base_parts.peek() {
+        return None;
+    }
+    while path_parts.peek().is_some() && path_parts.peek() == base_parts.peek() {
+        path_parts.next();
+        base_parts.next();
+    }
+    let mut relative: PathBuf = base_parts.map(|_| Component::ParentDir).collect();
+    relative.extend(path_parts);
+    Some(relative)
+}
+
+#[cfg(windows)]
+fn decode_ansi(bytes: &[u8]) -> String {
+    const CP_ACP: u32 = 0;
+
+    #[link(name = "kernel32")]
+    extern "system" {
+        fn MultiByteToWideChar(
+            code_page: u32,
+            flags: u32,
+            multi_byte: *const u8,
+            multi_byte_len: i32,
+            wide: *mut u16,
+            wide_len: i32,
+        ) -> i32;
+    }
+
+    let Ok(len) = i32::try_from(bytes.len()) else {
+        return decode_cp1252(bytes);
+    };
+    if len == 0 {
+        return String::new();
+    }
+    // Safety: the input pointer and length describe `bytes`; the first call
+    // only measures, and the second writes at most `wide.len()` units.
+    unsafe {
+        let needed = MultiByteToWideChar(CP_ACP, 0, bytes.as_ptr(), len, std::ptr::null_mut(), 0);
+        if needed <= 0 {
+            return decode_cp1252(bytes);
+        }
+        let mut wide = vec![0u16; needed as usize];
+        let written =
+            MultiByteToWideChar(CP_ACP, 0, bytes.as_ptr(), len, wide.as_mut_ptr(), needed);
+        if written <= 0 {
+            return decode_cp1252(bytes);
+        }
+        wide.truncate(written as usize);
+        String::from_utf16_lossy(&wide)
+    }
+}
+
+#[cfg(not(windows))]
+fn decode_ansi(bytes: &[u8]) -> String {
+    decode_cp1252(bytes)
+}
+
+/// Decode Windows-1252, the ANSI code page of Western-European Windows.
+fn decode_cp1252(bytes: &[u8]) -> String {
+    // 0x80..=0x9F; the five unassigned bytes map to the matching C1 control,
+    // as MultiByteToWideChar does.
+    const HIGH: [char; 32] = [
+        '\u{20AC}', '\u{81}', '\u{201A}', '\u{192}', '\u{201E}', '\u{2026}', '\u{2020}',
+        '\u{2021}', '\u{2C6}', '\u{2030}', '\u{160}', '\u{2039}', '\u{152}', '\u{8D}', '\u{17D}',
+        '\u{8F}', '\u{90}', '\u{2018}', '\u{2019}', '\u{201C}', '\u{201D}', '\u{2022}', '\u{2013}',
+        '\u{2014}', '\u{2DC}', '\u{2122}', '\u{161}', '\u{203A}', '\u{153}', '\u{9D}', '\u{17E}',
+        '\u{178}',
+    ];
+    bytes
+        .iter()
+        .map(|&b| match b {
+            0x80..=0x9F => HIGH[usize::from(b - 0x80)],
+            _ => char::from(b),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_sfv_basic() {
+        let text = "; Generated by QuickSFV\r\n\r\nfile one.bin CBF43926\r\nother.txt ec4ac3d0\r\n";
+        let entries = parse_sfv(text).unwrap();
+        assert_eq!(
+            entries,
+            vec![
+                SfvEntry {
+                    filename: "file one.bin".to_string(),
+                    crc: 0xCBF43926,
+                    comments: Vec::new(),
+                    size: None,
+                },
+                SfvEntry {
+                    filename: "other.txt".to_string(),
+                    crc: 0xEC4AC3D0,
+                    comments: Vec::new(),
+                    size: None,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_decode_cp1252() {
+        assert_eq!(decode_cp1252(b"caf\xE9 \x80\x96 \x9Cuvre"), "café €– œuvre");
+    }
+
+    #[cfg(not(windows))]
+    #[test]
+    fn test_parse_sfv_bytes_cp1252_fallback() {
+        let dir = crate::test_util::TempDir::new("sfv_cp1252");
+        let expected_path = dir.write("Déjà vu.txt", b"123456789");
+        let sfv = dir.write("legacy.sfv", b"D\xE9j\xE0 vu.txt CBF43926\r\n");
+
+        let entries = read_sfv(&sfv).unwrap();
+        assert_eq!(entries[0].filename, "Déjà vu.txt");
+        assert_eq!(dir.path().join(&entries[0].filename), expected_path);
+        assert!(crate::verify_sfv(&sfv).unwrap()[0].is_ok());
+    }
+
+    #[test]
+    fn test_parse_sfv_bytes_prefers_utf8() {
+        let entries = parse_sfv_bytes("Déjà vu.txt CBF43926\n".as_bytes()).unwrap();
+        assert_eq!(entries[0].filename, "Déjà vu.txt");
+    }
+
+    #[test]
+    fn test_manifest_comments_round_trip() {
+        let text = "; Generated by WIN-SFV32\r\n;\r\n;         9  12:00.00 2024-01-02 a file.bin\r\n\r\na file.bin CBF43926\r\n; second part\r\nb.bin EC4AC3D0\r\n; end\r\n";
+        let manifest = SfvManifest::parse(text).unwrap();
+        assert_eq!(
+            manifest.header_comments,
+            vec![
+                " Generated by WIN-SFV32",
+                "",
+                "         9  12:00.00 2024-01-02 a file.bin",
+            ]
+        );
+        assert!(manifest.entries[0].comments.is_empty());
+        assert_eq!(manifest.entries[1].comments, vec![" second part"]);
+        assert_eq!(manifest.trailing_comments, vec![" end"]);
+        assert_eq!(manifest.recorded_size("a file.bin"), Some(9));
+        assert_eq!(manifest.recorded_size("b.bin"), None);
+
+        let written = manifest.to_string();
+        assert_eq!(written, text.replace("\r\n\r\n", "\r\n"));
+        assert_eq!(SfvManifest::parse(&written).unwrap(), manifest);
+    }
+
+    #[test]
+    fn test_add_header_comment_round_trips() {
+        let mut manifest = SfvManifest {
+            header_comments: vec![" Generated by MTSFV".to_string()],
+            entries: parse_sfv("a.bin CBF43926\n").unwrap(),
+            ..Default::default()
+        };
+        manifest.add_header_comment("Some.Release-GRP\r\n\n2024-05-01");
+        assert_eq!(
+            manifest.to_string(),
+            "; Generated by MTSFV\r\n; Some.Release-GRP\r\n;\r\n; 2024-05-01\r\na.bin CBF43926\r\n"
+        );
+        assert_eq!(SfvManifest::parse(&manifest.to_string()).unwrap(), manifest);
+    }
+
+    #[test]
+    fn test_entry_sizes() {
+        let text = "; header\r\n;         9  12:00.00 2024-01-02 old.bin\r\n\
+            old.bin CBF43926\r\n; note\r\n; size 13\r\nnew.bin EC4AC3D0\r\nbare.bin 00000000\r\n";
+        let manifest = SfvManifest::parse(text).unwrap();
+        assert_eq!(manifest.entries[0].size, None);
+        assert_eq!(manifest.entries[1].size, Some(13));
+        assert_eq!(manifest.entries[1].comments, vec![" note"]);
+        assert_eq!(manifest.to_string(), text);
+
+        let sizes: Vec<_> = parse_sfv(text)
+            .unwrap()
+            .into_iter()
+            .map(|entry| entry.size)
+            .collect();
+        assert_eq!(sizes, [Some(9), Some(13), None]);
+
+        // Not a size comment, so it stays a plain comment.
+        let entries = parse_sfv("a.bin 00000000\n; size 12kB\nb.bin 00000000\n").unwrap();
+        assert_eq!(entries[1].size, None);
+        assert_eq!(entries[1].comments, vec![" size 12kB"]);
+    }
+
+    #[test]
+    fn test_line_endings() {
+        let manifest = SfvManifest::parse("; header\na.bin CBF43926\n").unwrap();
+        assert_eq!(manifest.to_string(), "; header\r\na.bin CBF43926\r\n");
+        assert_eq!(
+            manifest.to_string_with(LineEnding::Lf),
+            "; header\na.bin CBF43926\n"
+        );
+        let native = manifest.to_string_with(LineEnding::Native);
+        assert_eq!(native.contains('\r'), cfg!(windows));
+        assert_eq!(SfvManifest::parse(&native).unwrap(), manifest);
+    }
+
+    #[test]
+    fn test_parse_sfv_lenient_whitespace() {
+        let text = "\u{feff}; made elsewhere \r\na.bin\tCBF43926  \r\nb c.bin \t EC4AC3D0\t";
+        let entries = parse_sfv(text).unwrap();
+        let parsed: Vec<_> = entries
+            .iter()
+            .map(|e| (e.filename.as_str(), e.crc))
+            .collect();
+        assert_eq!(parsed, [("a.bin", 0xCBF43926), ("b c.bin", 0xEC4AC3D0)]);
+        assert_eq!(
+            SfvManifest::parse(text).unwrap().header_comments,
+            [" made elsewhere "]
+        );
+    }
+
+    #[test]
+    fn test_write_sfv_path_styles_round_trip() {
+        let dir = crate::test_util::TempDir::new("path_style");
+        let a = dir.write("a.bin", b"123456789");
+        let b = dir.write("sub/b c.bin", b"Hello, World!");
+        let manifest = SfvManifest {
+            entries: [(&a, 0xCBF43926), (&b, 0xEC4AC3D0)]
+                .into_iter()
+                .map(|(path, crc)| SfvEntry {
+                    filename: path.to_string_lossy().into_owned(),
+                    crc,
+                    comments: Vec::new(),
+                    size: None,
+                })
+                .collect(),
+            ..Default::default()
+        };
+        let sfv = dir.path().join("out").join("release.sfv");
+        fs::create_dir_all(sfv.parent().unwrap()).unwrap();
+
+        write_sfv(&sfv, &manifest, LineEnding::Crlf, PathStyle::Relative).unwrap();
+        let names: Vec<_> = read_sfv(&sfv)
+            .unwrap()
+            .into_iter()
+            .map(|e| e.filename)
+            .collect();
+        let up = Path::new("..");
+        assert_eq!(
+            names,
+            [
+                up.join("a.bin").to_string_lossy(),
+                up.join("sub").join("b c.bin").to_string_lossy()
+            ]
+        );
+        // The files are outside the manifest's folder; the CLI only writes
+        // such a manifest with --allow-escape, which verifying it needs too.
+        let verify = |sfv: &Path| {
+            let options = crate::VerifyOptions {
+                allow_escape: true,
+                ..Default::default()
+            };
+            crate::verify_entries_with(sfv.parent().unwrap(), read_sfv(sfv).unwrap(), options)
+                .all(|entry| entry.result.is_ok())
+        };
+        assert!(verify(&sfv));
+
+        write_sfv(&sfv, &manifest, LineEnding::Crlf, PathStyle::Absolute).unwrap();
+        let entries = read_sfv(&sfv).unwrap();
+        assert!(entries.iter().all(|e| Path::new(&e.filename).is_absolute()));
+        assert_eq!(
+            entries[1].filename,
+            std::path::absolute(&b).unwrap().to_string_lossy()
+        );
+        assert!(verify(&sfv));
+    }
+
+    #[test]
+    fn test_write_sfv_relative_through_parent_dir() {
+        let dir = crate::test_util::TempDir::new("path_style_dotdot");
+        dir.write("cwd/a.bin", b"123456789");
+        let cwd = dir.path().join("cwd");
+        let manifest = SfvManifest {
+            entries: vec![SfvEntry {
+                filename: cwd
+                    .join("..")
+                    .join("cwd")
+                    .join("a.bin")
+                    .to_string_lossy()
+                    .into_owned(),
+                crc: 0xCBF43926,
+                comments: Vec::new(),
+                size: None,
+            }],
+            ..Default::default()
+        };
+        let name = |sfv: &Path| read_sfv(sfv).unwrap()[0].filename.clone();
+
+        // As `--create ../rel.sfv a.bin` run from `cwd`.
+        let sfv = cwd.join("..").join("rel.sfv");
+        write_sfv(&sfv, &manifest, LineEnding::Crlf, PathStyle::Relative).unwrap();
+        assert_eq!(name(&sfv), Path::new("cwd").join("a.bin").to_string_lossy());
+        assert!(crate::verify_sfv(&sfv).unwrap()[0].is_ok());
+
+        fs::create_dir(dir.path().join("out")).unwrap();
+        let sfv = cwd.join("..").join("out").join("r2.sfv");
+        write_sfv(&sfv, &manifest, LineEnding::Crlf, PathStyle::Relative).unwrap();
+        let expected = Path::new("..").join("cwd").join("a.bin");
+        assert_eq!(name(&sfv), expected.to_string_lossy());
+    }
+
+    #[test]
+    fn test_parse_sfv_rejects_bad_crc() {
+        let err = parse_sfv("ok.bin CBF43926\nbad.bin XYZ12345\n").unwrap_err();
+        assert_eq!(err.line, 2);
+
+        let err = parse_sfv("nocrc\n").unwrap_err();
+        assert_eq!(err.line, 1);
+
+        let err = parse_sfv("signed.bin +BF43926\n").unwrap_err();
+        assert_eq!(err.line, 1);
+    }
+
+    #[test]
+    fn test_parse_lenient_reports_every_bad_line() {
+        let text =
+            "; banner\nok.bin CBF43926\nbad.bin XYZ12345\n; for next\nnocrc\nnext.bin 0000001A\n";
+        let (manifest, errors) = SfvManifest::parse_lenient(text);
+        let lines: Vec<usize> = errors.iter().map(|err| err.line).collect();
+        assert_eq!(lines, [3, 5]);
+        let names: Vec<&str> = manifest
+            .entries
+            .iter()
+            .map(|e| e.filename.as_str())
+            .collect();
+        assert_eq!(names, ["ok.bin", "next.bin"]);
+        assert_eq!(manifest.entries[1].comments, [" for next"]);
+        assert_eq!(SfvManifest::parse(text).unwrap_err(), errors[0]);
+    }
+}
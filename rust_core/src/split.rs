@@ -0,0 +1,164 @@
+use crate::crc32_reader;
+use crate::hash::open_file;
+use std::collections::BTreeMap;
+use std::fs::{self, File};
+use std::io::{self, Read};
+use std::path::{Path, PathBuf};
+
+/// The numbered parts of a split file, in order: `movie.mkv.001`,
+/// `movie.mkv.002`, … for the base name `movie.mkv`.
+///
+/// Any run of digits is accepted as the part number, as long as the parts
+/// are numbered from 1 without gaps. Fails with [`io::ErrorKind::NotFound`]
+/// when there are no parts and with [`io::ErrorKind::InvalidData`], naming
+/// the first missing part, when there is a gap.
+pub fn split_parts(base: &Path) -> io::Result<Vec<PathBuf>> {
+    let not_found = || {
+        io::Error::new(
+            io::ErrorKind::NotFound,
+            format!("no numbered parts of {}", base.display()),
+        )
+    };
+    let name = base.file_name().ok_or_else(not_found)?.to_string_lossy();
+    let dir = match base.parent() {
+        Some(dir) if !dir.as_os_str().is_empty() => dir,
+        _ => Path::new("."),
+    };
+    let prefix = format!("{}.", name);
+
+    let mut parts = BTreeMap::new();
+    for entry in fs::read_dir(dir)? {
+        let entry = entry?;
+        let file_name = entry.file_name();
+        let Some(digits) = file_name.to_str().and_then(|n| n.strip_prefix(&prefix)) else {
+            continue;
+        };
+        if !digits.bytes().all(|b| b.is_ascii_digit()) {
+            continue;
+        }
+        let Ok(number) = digits.parse::<u64>() else {
+            continue;
+        };
+        if let Some(other) = parts.insert(number, entry.path()) {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!(
+                    "{} and {} are both part {}",
+                    other.display(),
+                    entry.path().display(),
+                    number
+                ),
+            ));
+        }
+    }
+
+    let last = *parts.keys().next_back().ok_or_else(not_found)?;
+    if let Some(gap) = (1..=last).find(|number| !parts.contains_key(number)) {
+        let width = parts[&last].extension().map_or(3, |ext| ext.len());
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!(
+                "part {}.{:0width$} is missing (found up to .{:0width$})",
+                base.display(),
+                gap,
+                last
+            ),
+        ));
+    }
+    log::debug!("{}: found {} parts", base.display(), last);
+    Ok(parts.into_values().collect())
+}
+
+/// Reads the parts of a split file one after another, as if they had already
+/// been joined. Only one part is open at a time.
+pub struct SplitReader {
+    parts: std::vec::IntoIter<PathBuf>,
+    current: Option<File>,
+}
+
+impl SplitReader {
+    /// Find the parts of `base` with [`split_parts`] and read them in order.
+    pub fn open(base: &Path) -> io::Result<Self> {
+        Ok(SplitReader::new(split_parts(base)?))
+    }
+
+    /// Read `parts` in the given order.
+    pub fn new(parts: Vec<PathBuf>) -> Self {
+        SplitReader {
+            parts: parts.into_iter(),
+            current: None,
+        }
+    }
+}
+
+impl Read for SplitReader {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        loop {
+            if let Some(file) = &mut self.current {
+                let read = file.read(buf)?;
+                if read > 0 || buf.is_empty() {
+                    return Ok(read);
+                }
+            }
+            match self.parts.next() {
+                Some(part) => self.current = Some(open_file(&part)?),
+                None => return Ok(0),
+            }
+        }
+    }
+}
+
+/// CRC32 of the numbered parts of `base` taken together, which is the CRC32
+/// of the joined file.
+pub fn crc32_split(base: &Path) -> io::Result<u32> {
+    crc32_reader(SplitReader::open(base)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::TempDir;
+
+    #[test]
+    fn test_crc32_split_matches_joined_file() {
+        let dir = TempDir::new("split");
+        let data = crate::test_util::patterned(700_000);
+        let joined = dir.write("movie.mkv", &data);
+        for (index, chunk) in data.chunks(300_000).enumerate() {
+            dir.write(&format!("movie.mkv.{:03}", index + 1), chunk);
+        }
+        dir.write("movie.mkv.sfv", b"");
+        dir.write("movie.mkv.part", b"");
+
+        let parts = split_parts(&joined).unwrap();
+        let names: Vec<_> = parts.iter().map(|p| p.file_name().unwrap()).collect();
+        assert_eq!(names, ["movie.mkv.001", "movie.mkv.002", "movie.mkv.003"]);
+        assert_eq!(
+            crc32_split(&joined).unwrap(),
+            crate::crc32_path(&joined).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_split_parts_errors() {
+        let dir = TempDir::new("split_errors");
+        let base = dir.path().join("disc.iso");
+        assert_eq!(
+            split_parts(&base).unwrap_err().kind(),
+            io::ErrorKind::NotFound
+        );
+
+        dir.write("disc.iso.001", b"a");
+        dir.write("disc.iso.002", b"b");
+        dir.write("disc.iso.004", b"d");
+        let err = crc32_split(&base).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+        assert!(err.to_string().contains("disc.iso.003 is missing"), "{err}");
+
+        dir.write("disc.iso.3", b"c");
+        assert!(split_parts(&base).is_ok());
+        dir.write("disc.iso.03", b"c");
+        let err = split_parts(&base).unwrap_err();
+        assert!(err.to_string().contains("are both part 3"), "{err}");
+    }
+}
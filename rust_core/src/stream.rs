@@ -0,0 +1,118 @@
+//! Checking data against a known CRC while it is being written.
+
+use crate::verify::{classify, VerifyResult};
+use crc32fast::Hasher;
+use std::io::{self, Write};
+
+/// An [`io::Write`] that CRCs everything written through it and compares
+/// the result with an expected CRC at the end.
+///
+/// Placed in an [`io::copy`] chain, a download is checked as it arrives with
+/// no second read. [`StreamVerifier::new`] only hashes; with
+/// [`StreamVerifier::wrap`] the bytes also go on to another writer, such as
+/// the file being saved, and only bytes that writer accepted are hashed.
+pub struct StreamVerifier<W = io::Sink> {
+    inner: W,
+    hasher: Hasher,
+    expected: u32,
+    bytes: u64,
+    filename: String,
+}
+
+impl StreamVerifier {
+    /// Check written data against `expected`, discarding it.
+    pub fn new(expected: u32) -> Self {
+        StreamVerifier::wrap(io::sink(), expected)
+    }
+}
+
+impl<W: Write> StreamVerifier<W> {
+    /// Check data written through to `inner` against `expected`.
+    pub fn wrap(inner: W, expected: u32) -> Self {
+        StreamVerifier {
+            inner,
+            hasher: Hasher::new(),
+            expected,
+            bytes: 0,
+            filename: "-".to_string(),
+        }
+    }
+
+    /// Name the result of [`finish`](Self::finish) carries; `-` by default.
+    pub fn with_filename(mut self, filename: impl Into<String>) -> Self {
+        self.filename = filename.into();
+        self
+    }
+
+    /// Bytes written so far.
+    pub fn bytes_written(&self) -> u64 {
+        self.bytes
+    }
+
+    /// Flush the inner writer and compare the CRC of everything written with
+    /// the expected one. A failed flush is reported as
+    /// [`VerifyResult::Error`], since the data may not have reached its
+    /// destination.
+    pub fn finish(mut self) -> VerifyResult {
+        let crc = self.inner.flush().map(|()| self.hasher.finalize());
+        classify(self.filename, self.expected, crc)
+    }
+}
+
+impl<W: Write> Write for StreamVerifier<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let written = self.inner.write(buf)?;
+        self.hasher.update(&buf[..written]);
+        self.bytes += written as u64;
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_stream_verifier_match_and_mismatch() {
+        let data = crate::test_util::patterned(600_000);
+        let expected = crate::crc32_bytes(&data);
+
+        let mut verifier = StreamVerifier::new(expected);
+        assert_eq!(io::copy(&mut &data[..], &mut verifier).unwrap(), 600_000);
+        assert_eq!(verifier.bytes_written(), 600_000);
+        assert_eq!(
+            verifier.finish(),
+            VerifyResult::Ok {
+                filename: "-".to_string(),
+                crc: expected
+            }
+        );
+
+        let mut saved = Vec::new();
+        let mut verifier = StreamVerifier::wrap(&mut saved, expected).with_filename("part.bin");
+        io::copy(&mut &data[..300_000], &mut verifier).unwrap();
+        assert_eq!(
+            verifier.finish(),
+            VerifyResult::Mismatch {
+                filename: "part.bin".to_string(),
+                expected,
+                actual: crate::crc32_bytes(&data[..300_000]),
+            }
+        );
+        assert_eq!(saved, &data[..300_000]);
+    }
+
+    #[test]
+    fn test_stream_verifier_hashes_only_accepted_bytes() {
+        // A writer with room for 4 bytes takes part of a write, then fails.
+        let mut room = [0u8; 4];
+        let mut verifier = StreamVerifier::wrap(&mut room[..], crate::crc32_bytes(b"1234"));
+        assert_eq!(verifier.write(b"123456789").unwrap(), 4);
+        assert!(verifier.write_all(b"5").is_err());
+        assert!(verifier.finish().is_ok());
+    }
+}
@@ -0,0 +1,100 @@
+//! Helpers shared by the unit tests.
+
+use std::fs;
+use std::io::{self, Read};
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// `len` bytes counting up modulo 251, a prime, so the pattern never lines
+/// up with a power-of-two buffer or chunk boundary.
+pub fn patterned(len: usize) -> Vec<u8> {
+    (0..len).map(|i| (i % 251) as u8).collect()
+}
+
+/// Temporary directory that is removed (with its contents) on drop.
+pub struct TempDir(PathBuf);
+
+impl TempDir {
+    pub fn new(label: &str) -> Self {
+        let unique = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_nanos();
+        let path =
+            std::env::temp_dir().join(format!("mtsfv_{}_{}_{}", label, std::process::id(), unique));
+        fs::create_dir_all(&path).expect("create temp dir");
+        TempDir(path)
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.0
+    }
+
+    /// Write `data` to `relative` inside the directory, creating parents.
+    pub fn write(&self, relative: &str, data: &[u8]) -> PathBuf {
+        let path = self.0.join(relative);
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).expect("create parent dirs");
+        }
+        fs::write(&path, data).expect("write temp file");
+        path
+    }
+}
+
+impl Drop for TempDir {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.0);
+    }
+}
+
+/// A failure for [`FaultyReader`] to inject into the reader it wraps.
+#[derive(Clone, Copy, Debug)]
+pub enum Fault {
+    /// Return at most this many bytes from each read.
+    ShortReads(usize),
+    /// Fail with an error of this kind once this many bytes have been read.
+    ErrorAt(u64, io::ErrorKind),
+    /// Report end of input (a zero-byte read) once this many bytes have
+    /// been read, as a file truncated mid-hash would.
+    EofAt(u64),
+}
+
+/// Reader that misbehaves in a chosen, repeatable way, so the read loops'
+/// handling of short reads, errors and early EOF can be tested without
+/// special files.
+pub struct FaultyReader<R> {
+    inner: R,
+    fault: Fault,
+    offset: u64,
+}
+
+impl<R: Read> FaultyReader<R> {
+    pub fn new(inner: R, fault: Fault) -> Self {
+        FaultyReader {
+            inner,
+            fault,
+            offset: 0,
+        }
+    }
+}
+
+impl<R: Read> Read for FaultyReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let limit = match self.fault {
+            Fault::ShortReads(max) => max,
+            Fault::ErrorAt(at, kind) => {
+                if self.offset >= at {
+                    return Err(io::Error::new(kind, "injected read fault"));
+                }
+                usize::try_from(at - self.offset).unwrap_or(usize::MAX)
+            }
+            Fault::EofAt(at) => {
+                usize::try_from(at.saturating_sub(self.offset)).unwrap_or(usize::MAX)
+            }
+        };
+        let len = buf.len().min(limit);
+        let read = self.inner.read(&mut buf[..len])?;
+        self.offset += read as u64;
+        Ok(read)
+    }
+}
@@ -0,0 +1,613 @@
+use crate::batch::crc32_batch;
+use crate::crc32_path;
+use std::collections::{BTreeSet, VecDeque};
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// Recursively list every regular file under `root`.
+///
+/// Paths are returned relative to `root` and sorted so that callers get a
+/// stable order independent of the filesystem's directory iteration order.
+pub fn walk_files(root: &Path) -> io::Result<Vec<PathBuf>> {
+    walk_files_with_depth(root, usize::MAX)
+}
+
+/// Like [`walk_files`], but descend at most `max_depth` directories below
+/// `root`; a depth of 0 lists only the files directly inside it.
+pub fn walk_files_with_depth(root: &Path, max_depth: usize) -> io::Result<Vec<PathBuf>> {
+    walk_files_with(
+        root,
+        WalkOptions {
+            max_depth,
+            ..Default::default()
+        },
+    )
+}
+
+/// What [`walk_files_with`] and [`hash_tree_with`] list. The default
+/// descends without limit and includes hidden files.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct WalkOptions {
+    /// Directories to descend below the root; 0 lists only its own files.
+    pub max_depth: usize,
+    /// Leave out files and whole directories that [`is_hidden`] reports,
+    /// such as `.git` and `.DS_Store`. The root itself is always walked.
+    pub skip_hidden: bool,
+}
+
+impl Default for WalkOptions {
+    fn default() -> Self {
+        WalkOptions {
+            max_depth: usize::MAX,
+            skip_hidden: false,
+        }
+    }
+}
+
+/// Whether `path` is hidden by the platform's convention: its name starts
+/// with `.` on Unix, and it has the hidden or system attribute on Windows
+/// (as `Thumbs.db` and `desktop.ini` usually do).
+#[cfg(not(windows))]
+pub fn is_hidden(path: &Path) -> bool {
+    path.file_name()
+        .is_some_and(|name| name.to_string_lossy().starts_with('.'))
+}
+
+/// Whether `path` is hidden by the platform's convention: its name starts
+/// with `.` on Unix, and it has the hidden or system attribute on Windows
+/// (as `Thumbs.db` and `desktop.ini` usually do).
+#[cfg(windows)]
+pub fn is_hidden(path: &Path) -> bool {
+    use std::os::windows::fs::MetadataExt;
+    const FILE_ATTRIBUTE_HIDDEN: u32 = 0x2;
+    const FILE_ATTRIBUTE_SYSTEM: u32 = 0x4;
+
+    fs::symlink_metadata(path).is_ok_and(|metadata| {
+        metadata.file_attributes() & (FILE_ATTRIBUTE_HIDDEN | FILE_ATTRIBUTE_SYSTEM) != 0
+    })
+}
+
+/// Like [`walk_files`], limited by `options`.
+pub fn walk_files_with(root: &Path, options: WalkOptions) -> io::Result<Vec<PathBuf>> {
+    let mut files = Vec::new();
+    let mut pending = vec![(PathBuf::new(), 0)];
+
+    while let Some((relative, depth)) = pending.pop() {
+        for entry in fs::read_dir(root.join(&relative))? {
+            let entry = entry?;
+            if options.skip_hidden && is_hidden(&entry.path()) {
+                log::debug!("{}: hidden, skipped", entry.path().display());
+                continue;
+            }
+            let file_type = entry.file_type()?;
+            let child = relative.join(entry.file_name());
+            if file_type.is_dir() {
+                if depth < options.max_depth {
+                    pending.push((child, depth + 1));
+                }
+            } else if file_type.is_file() {
+                files.push(child);
+            }
+        }
+    }
+
+    files.sort();
+    Ok(files)
+}
+
+/// Selects files by extension, compared case-insensitively.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ExtensionFilter {
+    /// When non-empty, only files with one of these extensions are kept, so
+    /// files without an extension are dropped.
+    pub include: Vec<String>,
+    /// Files with one of these extensions are dropped.
+    pub exclude: Vec<String>,
+}
+
+impl ExtensionFilter {
+    /// Split a comma-separated list such as `mkv,.iso` into extensions,
+    /// without their leading dots.
+    pub fn parse_list(list: &str) -> Vec<String> {
+        list.split(',')
+            .map(|ext| ext.trim().trim_start_matches('.'))
+            .filter(|ext| !ext.is_empty())
+            .map(str::to_string)
+            .collect()
+    }
+
+    /// Whether `path` passes the filter.
+    pub fn matches(&self, path: &Path) -> bool {
+        let ext = path.extension().map(|ext| ext.to_string_lossy());
+        let listed = |list: &[String]| {
+            ext.as_ref()
+                .is_some_and(|ext| list.iter().any(|item| item.eq_ignore_ascii_case(ext)))
+        };
+        (self.include.is_empty() || listed(&self.include)) && !listed(&self.exclude)
+    }
+}
+
+/// Lazily walk `root` and hash each regular file as the iterator advances.
+///
+/// Unlike [`walk_files`] nothing is collected up front: a directory is only
+/// read once the files before it have been yielded, and each file is hashed
+/// inside `next()`, so callers can report progress or stop early. Paths are
+/// joined onto `root`. Within a directory, files come first in sorted order,
+/// then each subdirectory in sorted order. A directory that cannot be read is
+/// yielded with its error and skipped.
+pub fn hash_tree(root: &Path) -> impl Iterator<Item = (PathBuf, io::Result<u32>)> {
+    hash_tree_filtered(root, ExtensionFilter::default())
+}
+
+/// Like [`hash_tree`], but skip files that do not pass `filter`.
+pub fn hash_tree_filtered(
+    root: &Path,
+    filter: ExtensionFilter,
+) -> impl Iterator<Item = (PathBuf, io::Result<u32>)> {
+    hash_tree_with(root, filter, WalkOptions::default())
+}
+
+/// Like [`hash_tree_filtered`], also limited by `options` as
+/// [`walk_files_with`] is: hidden files and directories are left out when
+/// [`WalkOptions::skip_hidden`] is set.
+pub fn hash_tree_with(
+    root: &Path,
+    filter: ExtensionFilter,
+    options: WalkOptions,
+) -> impl Iterator<Item = (PathBuf, io::Result<u32>)> {
+    HashTree {
+        pending_dirs: vec![(root.to_path_buf(), 0)],
+        queued: VecDeque::new(),
+        filter,
+        options,
+    }
+}
+
+struct HashTree {
+    /// Directories still to be read with their depth below the root, the
+    /// next one last.
+    pending_dirs: Vec<(PathBuf, usize)>,
+    /// Files (to hash) and errors (to report) from the last directory read.
+    queued: VecDeque<(PathBuf, Option<io::Error>)>,
+    filter: ExtensionFilter,
+    options: WalkOptions,
+}
+
+impl HashTree {
+    fn read_next_dir(&mut self) -> Option<()> {
+        let (dir, depth) = self.pending_dirs.pop()?;
+        let entries = match fs::read_dir(&dir) {
+            Ok(entries) => entries,
+            Err(e) => {
+                self.queued.push_back((dir, Some(e)));
+                return Some(());
+            }
+        };
+
+        let mut files = Vec::new();
+        let mut subdirs = Vec::new();
+        for entry in entries {
+            let entry = match entry {
+                Ok(entry) => entry,
+                Err(e) => {
+                    self.queued.push_back((dir.clone(), Some(e)));
+                    continue;
+                }
+            };
+            let path = entry.path();
+            if self.options.skip_hidden && is_hidden(&path) {
+                log::debug!("{}: hidden, skipped", path.display());
+                continue;
+            }
+            match entry.file_type() {
+                Ok(file_type) if file_type.is_dir() => {
+                    if depth < self.options.max_depth {
+                        subdirs.push(path);
+                    }
+                }
+                Ok(file_type) if file_type.is_file() => {
+                    if self.filter.matches(&path) {
+                        files.push(path);
+                    }
+                }
+                Ok(_) => {}
+                Err(e) => self.queued.push_back((path, Some(e))),
+            }
+        }
+
+        files.sort();
+        subdirs.sort();
+        self.queued
+            .extend(files.into_iter().map(|path| (path, None)));
+        self.pending_dirs
+            .extend(subdirs.into_iter().rev().map(|path| (path, depth + 1)));
+        Some(())
+    }
+}
+
+impl Iterator for HashTree {
+    type Item = (PathBuf, io::Result<u32>);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while self.queued.is_empty() {
+            self.read_next_dir()?;
+        }
+        let (path, error) = self.queued.pop_front()?;
+        let result = match error {
+            Some(e) => Err(e),
+            None => crc32_path(&path),
+        };
+        Some((path, result))
+    }
+}
+
+/// A file present in both trees whose contents differ.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CrcMismatch {
+    pub path: PathBuf,
+    pub crc_a: u32,
+    pub crc_b: u32,
+}
+
+/// Differences between two directory trees, keyed by path relative to each root.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct DirDiff {
+    pub only_in_a: Vec<PathBuf>,
+    pub only_in_b: Vec<PathBuf>,
+    pub different: Vec<CrcMismatch>,
+    /// Number of files present in both trees with matching CRCs.
+    pub identical: usize,
+}
+
+impl DirDiff {
+    /// True when both trees contain the same files with the same CRCs.
+    pub fn is_identical(&self) -> bool {
+        self.only_in_a.is_empty() && self.only_in_b.is_empty() && self.different.is_empty()
+    }
+}
+
+impl fmt::Display for DirDiff {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for path in &self.only_in_a {
+            writeln!(f, "only in A: {}", path.display())?;
+        }
+        for path in &self.only_in_b {
+            writeln!(f, "only in B: {}", path.display())?;
+        }
+        for mismatch in &self.different {
+            writeln!(
+                f,
+                "different: {} ({:08X} != {:08X})",
+                mismatch.path.display(),
+                mismatch.crc_a,
+                mismatch.crc_b
+            )?;
+        }
+        write!(
+            f,
+            "{} identical, {} only in A, {} only in B, {} different",
+            self.identical,
+            self.only_in_a.len(),
+            self.only_in_b.len(),
+            self.different.len()
+        )
+    }
+}
+
+/// Compare two directory trees by CRC32.
+///
+/// Every file in both trees is hashed using the parallel batch helper. Any
+/// file that cannot be read aborts the comparison with that error.
+pub fn diff_dirs(a: &Path, b: &Path) -> io::Result<DirDiff> {
+    let files_a = walk_files(a)?;
+    let files_b = walk_files(b)?;
+
+    let mut diff = DirDiff::default();
+    let in_a: BTreeSet<&PathBuf> = files_a.iter().collect();
+    let in_b: BTreeSet<&PathBuf> = files_b.iter().collect();
+    let mut common = Vec::new();
+    for path in &files_a {
+        if in_b.contains(path) {
+            common.push(path.clone());
+        } else {
+            diff.only_in_a.push(path.clone());
+        }
+    }
+    diff.only_in_b = files_b
+        .iter()
+        .filter(|p| !in_a.contains(p))
+        .cloned()
+        .collect();
+
+    let mut full_paths: Vec<PathBuf> = common.iter().map(|p| a.join(p)).collect();
+    full_paths.extend(common.iter().map(|p| b.join(p)));
+    let mut crcs = crc32_batch(&full_paths).into_iter();
+    let crcs_a: Vec<u32> = crcs
+        .by_ref()
+        .take(common.len())
+        .collect::<io::Result<_>>()?;
+    let crcs_b: Vec<u32> = crcs.collect::<io::Result<_>>()?;
+
+    for ((path, crc_a), crc_b) in common.into_iter().zip(crcs_a).zip(crcs_b) {
+        if crc_a == crc_b {
+            diff.identical += 1;
+        } else {
+            diff.different.push(CrcMismatch { path, crc_a, crc_b });
+        }
+    }
+
+    Ok(diff)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::TempDir;
+
+    #[test]
+    fn test_walk_files_recurses_and_sorts() {
+        let dir = TempDir::new("walk");
+        dir.write("b.txt", b"b");
+        dir.write("sub/a.txt", b"a");
+        dir.write("a.txt", b"a");
+
+        let files = walk_files(dir.path()).unwrap();
+        assert_eq!(
+            files,
+            vec![
+                PathBuf::from("a.txt"),
+                PathBuf::from("b.txt"),
+                Path::new("sub").join("a.txt"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_walk_files_with_depth_limit() {
+        let dir = TempDir::new("walk_depth");
+        dir.write("top.txt", b"");
+        dir.write("a/one.txt", b"");
+        dir.write("a/b/two.txt", b"");
+
+        assert_eq!(
+            walk_files_with_depth(dir.path(), 0).unwrap(),
+            vec![PathBuf::from("top.txt")]
+        );
+        assert_eq!(
+            walk_files_with_depth(dir.path(), 1).unwrap(),
+            vec![Path::new("a").join("one.txt"), PathBuf::from("top.txt")]
+        );
+        assert_eq!(walk_files_with_depth(dir.path(), 2).unwrap().len(), 3);
+    }
+
+    #[test]
+    fn test_walk_files_skip_hidden() {
+        let dir = TempDir::new("walk_hidden");
+        dir.write("movie.mkv", b"");
+        dir.write(".DS_Store", b"");
+        dir.write(".git/config", b"");
+        dir.write("extras/.nfo", b"");
+        dir.write("extras/clip.mkv", b"");
+        hide(&dir.path().join(".DS_Store"));
+        hide(&dir.path().join(".git"));
+        hide(&dir.path().join("extras").join(".nfo"));
+
+        assert_eq!(walk_files(dir.path()).unwrap().len(), 5);
+        let options = WalkOptions {
+            skip_hidden: true,
+            ..Default::default()
+        };
+        assert_eq!(
+            walk_files_with(dir.path(), options).unwrap(),
+            vec![
+                Path::new("extras").join("clip.mkv"),
+                PathBuf::from("movie.mkv"),
+            ]
+        );
+    }
+
+    #[cfg(not(windows))]
+    fn hide(path: &Path) {
+        assert!(is_hidden(path), "{}", path.display());
+    }
+
+    #[cfg(windows)]
+    fn hide(path: &Path) {
+        // A leading dot means nothing on Windows; only the attribute counts.
+        assert!(!is_hidden(path), "{}", path.display());
+        let status = std::process::Command::new("attrib")
+            .arg("+h")
+            .arg(path)
+            .status()
+            .unwrap();
+        assert!(status.success());
+        assert!(is_hidden(path), "{}", path.display());
+    }
+
+    #[cfg(not(windows))]
+    #[test]
+    fn test_is_hidden_dotfiles() {
+        assert!(is_hidden(Path::new("media/.DS_Store")));
+        assert!(is_hidden(Path::new(".git")));
+        assert!(!is_hidden(Path::new(".hidden/visible.txt")));
+        assert!(!is_hidden(Path::new("Thumbs.db")));
+    }
+
+    #[cfg(windows)]
+    #[test]
+    fn test_is_hidden_system_attribute() {
+        let dir = TempDir::new("hidden_attrib");
+        let thumbs = dir.write("Thumbs.db", b"");
+        assert!(!is_hidden(&thumbs));
+        let status = std::process::Command::new("attrib")
+            .arg("+s")
+            .arg(&thumbs)
+            .status()
+            .unwrap();
+        assert!(status.success());
+        assert!(is_hidden(&thumbs));
+    }
+
+    #[test]
+    fn test_hash_tree_is_lazy_and_ordered() {
+        let dir = TempDir::new("hash_tree");
+        dir.write("z.txt", b"123456789");
+        dir.write("a/inner.txt", b"Hello, World!");
+        dir.write("a/b/deep.txt", b"");
+        dir.write("b.txt", b"");
+
+        let results: Vec<_> = hash_tree(dir.path())
+            .map(|(path, crc)| (path, crc.unwrap()))
+            .collect();
+        let root = dir.path();
+        assert_eq!(
+            results,
+            vec![
+                (root.join("b.txt"), 0),
+                (root.join("z.txt"), 0xCBF43926),
+                (root.join("a").join("inner.txt"), 0xEC4AC3D0),
+                (root.join("a").join("b").join("deep.txt"), 0),
+            ]
+        );
+
+        // Subdirectories are only read once the iterator reaches them.
+        let mut iter = hash_tree(dir.path());
+        assert_eq!(iter.next().unwrap().0, root.join("b.txt"));
+        fs::remove_dir_all(root.join("a")).unwrap();
+        let rest: Vec<_> = iter.collect();
+        assert_eq!(rest.len(), 2);
+        assert_eq!(rest[0].0, root.join("z.txt"));
+        assert_eq!(rest[1].0, root.join("a"));
+        assert!(rest[1].1.is_err());
+    }
+
+    #[test]
+    fn test_hash_tree_filtered_by_extension() {
+        let dir = TempDir::new("hash_tree_ext");
+        dir.write("movie.MKV", b"");
+        dir.write("disc.iso", b"");
+        dir.write("info.nfo", b"");
+        dir.write("README", b"");
+        dir.write("extras/clip.mkv", b"");
+        let root = dir.path();
+
+        let include = ExtensionFilter {
+            include: ExtensionFilter::parse_list("mkv, .iso"),
+            exclude: Vec::new(),
+        };
+        let paths: Vec<_> = hash_tree_filtered(root, include)
+            .map(|(path, _)| path)
+            .collect();
+        assert_eq!(
+            paths,
+            vec![
+                root.join("disc.iso"),
+                root.join("movie.MKV"),
+                root.join("extras").join("clip.mkv"),
+            ]
+        );
+
+        let exclude = ExtensionFilter {
+            include: Vec::new(),
+            exclude: ExtensionFilter::parse_list("nfo,mkv"),
+        };
+        let paths: Vec<_> = hash_tree_filtered(root, exclude)
+            .map(|(path, _)| path)
+            .collect();
+        assert_eq!(paths, vec![root.join("README"), root.join("disc.iso")]);
+    }
+
+    #[test]
+    fn test_hash_tree_with_skips_hidden() {
+        let dir = TempDir::new("hash_tree_hidden");
+        dir.write("movie.mkv", b"123456789");
+        dir.write(".DS_Store", b"");
+        dir.write(".git/config", b"");
+        dir.write("extras/.nfo", b"");
+        dir.write("extras/clip.mkv", b"");
+        hide(&dir.path().join(".DS_Store"));
+        hide(&dir.path().join(".git"));
+        hide(&dir.path().join("extras").join(".nfo"));
+
+        let paths = |options| -> Vec<_> {
+            hash_tree_with(dir.path(), ExtensionFilter::default(), options)
+                .map(|(path, result)| {
+                    result.unwrap();
+                    path.strip_prefix(dir.path()).unwrap().to_path_buf()
+                })
+                .collect()
+        };
+        assert_eq!(paths(WalkOptions::default()).len(), 5);
+        let skip_hidden = WalkOptions {
+            skip_hidden: true,
+            ..Default::default()
+        };
+        assert_eq!(
+            paths(skip_hidden),
+            vec![
+                PathBuf::from("movie.mkv"),
+                Path::new("extras").join("clip.mkv"),
+            ]
+        );
+        let top_only = WalkOptions {
+            max_depth: 0,
+            skip_hidden: true,
+        };
+        assert_eq!(paths(top_only), vec![PathBuf::from("movie.mkv")]);
+    }
+
+    #[test]
+    fn test_hash_tree_reports_unreadable_root() {
+        let dir = TempDir::new("hash_tree_missing");
+        let missing = dir.path().join("nope");
+        let results: Vec<_> = hash_tree(&missing).collect();
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].0, missing);
+        assert_eq!(
+            results[0].1.as_ref().unwrap_err().kind(),
+            io::ErrorKind::NotFound
+        );
+    }
+
+    #[test]
+    fn test_diff_dirs_reports_all_categories() {
+        let a = TempDir::new("diff_a");
+        let b = TempDir::new("diff_b");
+        a.write("same.txt", b"123456789");
+        b.write("same.txt", b"123456789");
+        a.write("sub/changed.txt", b"123456789");
+        b.write("sub/changed.txt", b"Hello, World!");
+        a.write("only_a.txt", b"a");
+        b.write("only_b.txt", b"b");
+
+        let diff = diff_dirs(a.path(), b.path()).unwrap();
+        assert!(!diff.is_identical());
+        assert_eq!(diff.identical, 1);
+        assert_eq!(diff.only_in_a, vec![PathBuf::from("only_a.txt")]);
+        assert_eq!(diff.only_in_b, vec![PathBuf::from("only_b.txt")]);
+        assert_eq!(
+            diff.different,
+            vec![CrcMismatch {
+                path: Path::new("sub").join("changed.txt"),
+                crc_a: 0xCBF43926,
+                crc_b: 0xEC4AC3D0,
+            }]
+        );
+    }
+
+    #[test]
+    fn test_diff_dirs_identical_copy() {
+        let a = TempDir::new("diff_copy_a");
+        let b = TempDir::new("diff_copy_b");
+        for dir in [&a, &b] {
+            dir.write("x.bin", &[1, 2, 3]);
+            dir.write("nested/y.bin", &[4, 5, 6]);
+        }
+
+        let diff = diff_dirs(a.path(), b.path()).unwrap();
+        assert!(diff.is_identical());
+        assert_eq!(diff.identical, 2);
+    }
+}
@@ -0,0 +1,218 @@
+use crate::batch::crc32_batch_with_len;
+use crate::sfv::{SfvEntry, SfvManifest};
+use crate::tree::walk_files;
+use crate::verify::entry_components;
+use std::collections::{BTreeMap, HashMap};
+use std::io;
+use std::path::Path;
+
+/// Bring `existing` up to date with the files under `dir`, keeping entries
+/// for files that no longer exist; see [`update_sfv_with_pruning`].
+pub fn update_sfv(existing: &SfvManifest, dir: &Path) -> io::Result<SfvManifest> {
+    update_sfv_with_pruning(existing, dir, false)
+}
+
+/// Bring `existing` up to date with the files under `dir`.
+///
+/// Every file is hashed. Entries keep their position and comments, and only
+/// their CRC (and recorded size, if any) changes if the file did. Files the
+/// manifest does not list yet are appended in sorted order, using the
+/// manifest's path separator, with sizes if any existing entry has one.
+/// Entries whose file is gone are dropped when `prune` is set. Header and
+/// trailing comments are copied unchanged. `.sfv` files in `dir` are never
+/// added.
+pub fn update_sfv_with_pruning(
+    existing: &SfvManifest,
+    dir: &Path,
+    prune: bool,
+) -> io::Result<SfvManifest> {
+    let files: Vec<_> = walk_files(dir)?
+        .into_iter()
+        .filter(|path| {
+            !path
+                .extension()
+                .is_some_and(|ext| ext.eq_ignore_ascii_case("sfv"))
+        })
+        .collect();
+    let absolute: Vec<_> = files.iter().map(|path| dir.join(path)).collect();
+
+    let mut hashed = BTreeMap::new();
+    for (path, hashed_file) in files.iter().zip(crc32_batch_with_len(&absolute)) {
+        let key = path
+            .iter()
+            .map(|part| part.to_string_lossy())
+            .collect::<Vec<_>>()
+            .join("/");
+        hashed.insert(key, hashed_file?);
+    }
+
+    let with_sizes = existing.entries.iter().any(|entry| entry.size.is_some());
+    let hashed = hashed.into_iter().map(|(key, (crc, size))| SfvEntry {
+        filename: key,
+        crc,
+        comments: Vec::new(),
+        size: with_sizes.then_some(size),
+    });
+    Ok(merge_entries(existing, hashed, prune))
+}
+
+/// Add freshly hashed `entries` to `existing`, as `--create --append` does.
+///
+/// An entry naming a file the manifest already lists replaces that entry's
+/// CRC in place, keeping its position and comments, and its size only if it
+/// had one and `entries` supplies the new one. The rest are appended in the
+/// order given, relative ones using the manifest's path separator. Header and
+/// trailing comments are copied unchanged.
+pub fn append_to_sfv(existing: &SfvManifest, entries: Vec<SfvEntry>) -> SfvManifest {
+    merge_entries(existing, entries, false)
+}
+
+/// Refresh the entries of `existing` that `hashed` names again and append
+/// the others, matching filenames component by component.
+fn merge_entries(
+    existing: &SfvManifest,
+    hashed: impl IntoIterator<Item = SfvEntry>,
+    prune: bool,
+) -> SfvManifest {
+    let key = |filename: &str| entry_components(filename).collect::<Vec<_>>().join("/");
+    let mut added = Vec::new();
+    let mut by_key = HashMap::new();
+    for entry in hashed {
+        by_key.insert(key(&entry.filename), added.len());
+        added.push(Some(entry));
+    }
+
+    let mut merged = SfvManifest {
+        header_comments: existing.header_comments.clone(),
+        entries: Vec::with_capacity(existing.entries.len()),
+        trailing_comments: existing.trailing_comments.clone(),
+    };
+    for entry in &existing.entries {
+        let fresh = by_key
+            .get(&key(&entry.filename))
+            .and_then(|&index| added[index].take());
+        match fresh {
+            Some(fresh) => merged.entries.push(SfvEntry {
+                crc: fresh.crc,
+                size: entry.size.and(fresh.size),
+                ..entry.clone()
+            }),
+            None if prune => {}
+            None => merged.entries.push(entry.clone()),
+        }
+    }
+
+    let separator = if existing
+        .entries
+        .iter()
+        .any(|entry| entry.filename.contains('\\'))
+    {
+        "\\"
+    } else {
+        "/"
+    };
+    for mut entry in added.into_iter().flatten() {
+        if !Path::new(&entry.filename).is_absolute() {
+            entry.filename = entry.filename.replace(['/', '\\'], separator);
+        }
+        merged.entries.push(entry);
+    }
+    merged
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::TempDir;
+
+    #[test]
+    fn test_update_sfv_keeps_layout_and_refreshes_changed() {
+        let dir = TempDir::new("update_sfv");
+        dir.write("b.bin", b"123456789");
+        dir.write("a.bin", b"changed");
+        dir.write("sub/new.bin", b"123456789");
+        dir.write("old.sfv", b"ignored");
+        let existing = SfvManifest::parse(
+            "; header\r\n\
+             b.bin CBF43926\r\n\
+             ; about a\r\n\
+             a.bin 00000000\r\n\
+             gone.bin 11111111\r\n\
+             ; trailer\r\n",
+        )
+        .unwrap();
+
+        let updated = update_sfv(&existing, dir.path()).unwrap();
+        assert_eq!(updated.header_comments, existing.header_comments);
+        assert_eq!(updated.trailing_comments, existing.trailing_comments);
+        let names: Vec<_> = updated
+            .entries
+            .iter()
+            .map(|e| e.filename.as_str())
+            .collect();
+        assert_eq!(names, ["b.bin", "a.bin", "gone.bin", "sub/new.bin"]);
+        assert_eq!(updated.entries[0], existing.entries[0]);
+        assert_eq!(updated.entries[1].comments, [" about a"]);
+        assert_eq!(updated.entries[1].crc, crate::crc::crc32(b"changed"));
+        assert_eq!(updated.entries[2], existing.entries[2]);
+        assert_eq!(updated.entries[3].crc, 0xCBF43926);
+
+        let pruned = update_sfv_with_pruning(&existing, dir.path(), true).unwrap();
+        let names: Vec<_> = pruned.entries.iter().map(|e| e.filename.as_str()).collect();
+        assert_eq!(names, ["b.bin", "a.bin", "sub/new.bin"]);
+    }
+
+    #[test]
+    fn test_update_sfv_matches_backslash_entries() {
+        let dir = TempDir::new("update_sfv_backslash");
+        dir.write("sub/a.bin", b"123456789");
+        dir.write("sub/b.bin", b"123456789");
+        let existing = SfvManifest::parse("; size 3\r\nsub\\a.bin CBF43926\r\n").unwrap();
+
+        let updated = update_sfv(&existing, dir.path()).unwrap();
+        let sizes: Vec<_> = updated.entries.iter().map(|e| e.size).collect();
+        assert_eq!(sizes, [Some(9), Some(9)]);
+        let names: Vec<_> = updated
+            .entries
+            .iter()
+            .map(|e| e.filename.as_str())
+            .collect();
+        assert_eq!(names, ["sub\\a.bin", "sub\\b.bin"]);
+    }
+
+    #[test]
+    fn test_append_to_sfv() {
+        let existing = SfvManifest::parse(
+            "; header\r\n\
+             ; size 9\r\n\
+             sub\\a.bin 00000000\r\n\
+             b.bin 11111111\r\n",
+        )
+        .unwrap();
+        let entry = |filename: &str, crc, size| SfvEntry {
+            filename: filename.to_string(),
+            crc,
+            comments: Vec::new(),
+            size,
+        };
+
+        let appended = append_to_sfv(
+            &existing,
+            vec![
+                entry("z.bin", 3, None),
+                entry("./sub/a.bin", 1, Some(7)),
+                entry("new/c.bin", 2, Some(5)),
+            ],
+        );
+        assert_eq!(appended.header_comments, existing.header_comments);
+        assert_eq!(
+            appended.entries,
+            [
+                entry("sub\\a.bin", 1, Some(7)),
+                existing.entries[1].clone(),
+                entry("z.bin", 3, None),
+                entry("new\\c.bin", 2, Some(5)),
+            ]
+        );
+    }
+}
@@ -0,0 +1,836 @@
+use crate::crc32_reader_with_progress;
+use crate::hash::{self, is_cancelled, ReadControl};
+use crate::sfv::{read_sfv, SfvEntry};
+use crate::split::{split_parts, SplitReader};
+use std::fs;
+use std::io;
+use std::path::{Component, Path, PathBuf};
+
+/// Outcome of checking one manifest entry against the file on disk.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum VerifyResult {
+    Ok {
+        filename: String,
+        crc: u32,
+    },
+    Mismatch {
+        filename: String,
+        expected: u32,
+        actual: u32,
+    },
+    Missing {
+        filename: String,
+    },
+    /// The manifest records a size and the file has another one, so it was
+    /// not hashed.
+    SizeMismatch {
+        filename: String,
+        expected: u64,
+        actual: u64,
+    },
+    /// The file exists but could not be read.
+    Error {
+        filename: String,
+        message: String,
+    },
+    /// The entry names a file outside the base directory, so it was not
+    /// opened; see [`safe_entry_path`].
+    Rejected {
+        filename: String,
+        reason: String,
+    },
+}
+
+impl VerifyResult {
+    pub fn filename(&self) -> &str {
+        match self {
+            VerifyResult::Ok { filename, .. }
+            | VerifyResult::Mismatch { filename, .. }
+            | VerifyResult::Missing { filename }
+            | VerifyResult::SizeMismatch { filename, .. }
+            | VerifyResult::Error { filename, .. }
+            | VerifyResult::Rejected { filename, .. } => filename,
+        }
+    }
+
+    pub fn is_ok(&self) -> bool {
+        matches!(self, VerifyResult::Ok { .. })
+    }
+}
+
+/// Tally of verification outcomes.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct VerifyCounts {
+    pub ok: u32,
+    /// Mismatches (of CRC or size), read errors and rejected entries.
+    pub failed: u32,
+    pub missing: u32,
+}
+
+impl VerifyCounts {
+    pub fn from_results(results: &[VerifyResult]) -> Self {
+        let mut counts = VerifyCounts::default();
+        for result in results {
+            counts.add(result);
+        }
+        counts
+    }
+
+    /// Count one more result, for callers that consume results as a stream.
+    pub fn add(&mut self, result: &VerifyResult) {
+        match result {
+            VerifyResult::Ok { .. } => self.ok += 1,
+            VerifyResult::Mismatch { .. }
+            | VerifyResult::SizeMismatch { .. }
+            | VerifyResult::Error { .. }
+            | VerifyResult::Rejected { .. } => self.failed += 1,
+            VerifyResult::Missing { .. } => self.missing += 1,
+        }
+    }
+}
+
+/// Optional fallbacks for [`verify_entries_with`], and how it reads files.
+/// The default checks each entry against exactly the file it names, and
+/// rejects entries naming a file outside the base directory.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct VerifyOptions<'a> {
+    /// When an entry's file is missing, hash its numbered parts (`name.001`,
+    /// `name.002`, …) as one file instead; see [`crate::split_parts`].
+    pub split_parts: bool,
+    /// When an entry's file is missing, look for a file whose name differs
+    /// only in case, one directory level at a time below the base directory.
+    /// A level with several such names counts as not found.
+    pub ignore_case: bool,
+    /// Open entries that lead outside the base directory (`../x`, absolute
+    /// paths elsewhere) instead of rejecting them. Only for trusted manifests.
+    pub allow_escape: bool,
+    /// Applied to every file read. Once it is cancelled, the results end
+    /// without the entry being checked at the time.
+    pub read: ReadControl<'a>,
+}
+
+/// One result of [`verify_entries_with`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct VerifiedEntry {
+    pub result: VerifyResult,
+    /// The CRC listed in the manifest, also for entries that were not hashed.
+    pub expected: u32,
+    /// The file that was checked, when it was only found by
+    /// [`VerifyOptions::ignore_case`] under a differently cased name.
+    pub found_as: Option<PathBuf>,
+    /// Bytes read while hashing the entry; 0 when it could not be read.
+    pub bytes: u64,
+}
+
+/// What checking one candidate file found out.
+enum Checked {
+    Crc(u32),
+    /// Its size ruled it out before hashing.
+    SizeMismatch {
+        expected: u64,
+        actual: u64,
+    },
+}
+
+/// Compare the size of `path` with `expected_size`, if any, and hash the file
+/// only if they agree. `bytes` follows the number of bytes hashed.
+fn check_file(
+    path: &Path,
+    expected_size: Option<u64>,
+    read: ReadControl,
+    bytes: &mut u64,
+) -> io::Result<Checked> {
+    if let Some(expected) = expected_size {
+        let metadata = fs::metadata(path)?;
+        if metadata.is_file() && metadata.len() != expected {
+            return Ok(Checked::SizeMismatch {
+                expected,
+                actual: metadata.len(),
+            });
+        }
+    }
+    let file = read.wrap(hash::open_file(path)?);
+    crc32_reader_with_progress(file, |total| *bytes = total).map(Checked::Crc)
+}
+
+/// Like [`check_file`] for the numbered parts of a split file.
+fn check_split(
+    path: &Path,
+    expected_size: Option<u64>,
+    read: ReadControl,
+    bytes: &mut u64,
+) -> io::Result<Checked> {
+    let parts = split_parts(path)?;
+    if let Some(expected) = expected_size {
+        let mut actual = 0;
+        for part in &parts {
+            actual += fs::metadata(part)?.len();
+        }
+        if actual != expected {
+            return Ok(Checked::SizeMismatch { expected, actual });
+        }
+    }
+    let reader = read.wrap(SplitReader::new(parts));
+    crc32_reader_with_progress(reader, |total| *bytes = total).map(Checked::Crc)
+}
+
+/// Classify the outcome of [`check_file`] or [`check_split`] for one entry.
+fn classify_checked(filename: String, expected: u32, checked: io::Result<Checked>) -> VerifyResult {
+    match checked {
+        Ok(Checked::SizeMismatch { expected, actual }) => VerifyResult::SizeMismatch {
+            filename,
+            expected,
+            actual,
+        },
+        Ok(Checked::Crc(crc)) => classify(filename, expected, Ok(crc)),
+        Err(e) => classify(filename, expected, Err(e)),
+    }
+}
+
+/// Classify the outcome of hashing one manifest entry.
+pub(crate) fn classify(filename: String, expected: u32, hashed: io::Result<u32>) -> VerifyResult {
+    match hashed {
+        Ok(actual) if actual == expected => VerifyResult::Ok {
+            filename,
+            crc: actual,
+        },
+        Ok(actual) => VerifyResult::Mismatch {
+            filename,
+            expected,
+            actual,
+        },
+        Err(e) if e.kind() == io::ErrorKind::NotFound => VerifyResult::Missing { filename },
+        Err(e) => VerifyResult::Error {
+            filename,
+            message: e.to_string(),
+        },
+    }
+}
+
+/// Split a manifest filename into path components.
+///
+/// Both `/` and `\\` are treated as separators whatever the host platform, so
+/// manifests written on Windows verify on Unix and vice versa. Empty and `.`
+/// components (`./a.bin`, `a//b.bin`) are dropped.
+pub(crate) fn entry_components(filename: &str) -> impl Iterator<Item = &str> {
+    filename
+        .split(['/', '\\'])
+        .filter(|part| !part.is_empty() && *part != ".")
+}
+
+/// Resolve a manifest filename against the directory containing the manifest.
+///
+/// Absolute filenames are used as they are. In relative ones both `/` and
+/// `\` separate components, and empty and `.` components are dropped.
+pub fn resolve_entry_path(base_dir: &Path, filename: &str) -> PathBuf {
+    if Path::new(filename).is_absolute() {
+        return PathBuf::from(filename);
+    }
+    let mut path = base_dir.to_path_buf();
+    path.extend(entry_components(filename));
+    path
+}
+
+/// Resolve a manifest filename as [`resolve_entry_path`] does, but only if
+/// the result stays inside `base_dir`.
+///
+/// Both paths are made absolute and `.`/`..` components are folded
+/// lexically before comparing, so `sub/../a.bin` is accepted while
+/// `../../etc/passwd`, or an absolute path elsewhere, is refused with the
+/// reason. Symbolic links inside the tree are not followed. An untrusted
+/// manifest therefore cannot make verification read, or report on, files it
+/// was not shipped with.
+pub fn safe_entry_path(base_dir: &Path, filename: &str) -> Result<PathBuf, String> {
+    let path = resolve_entry_path(base_dir, filename);
+    let base = if base_dir.as_os_str().is_empty() {
+        Path::new(".")
+    } else {
+        base_dir
+    };
+    let absolute = |path: &Path| {
+        std::path::absolute(path)
+            .map(|path| fold_dots(&path))
+            .map_err(|e| format!("cannot resolve {}: {}", path.display(), e))
+    };
+    let (inside, root) = (absolute(&path)?, absolute(base)?);
+    if inside.starts_with(&root) {
+        Ok(path)
+    } else {
+        Err(format!(
+            "{} is outside {}",
+            inside.display(),
+            root.display()
+        ))
+    }
+}
+
+/// `path` with `.` components dropped and each `..` cancelling the
+/// component before it, without looking at the filesystem.
+pub(crate) fn fold_dots(path: &Path) -> PathBuf {
+    let mut folded = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                if matches!(folded.components().next_back(), Some(Component::Normal(_))) {
+                    folded.pop();
+                }
+            }
+            other => folded.push(other),
+        }
+    }
+    folded
+}
+
+/// Find the file `filename` names below `base_dir` when its path matches an
+/// existing one only if case is ignored.
+///
+/// Each component is first tried as written; otherwise the directory is
+/// listed for a single entry that matches case-insensitively. `None` when a
+/// component has no such match or more than one. Absolute filenames are not
+/// looked up.
+pub fn find_entry_ignore_case(base_dir: &Path, filename: &str) -> Option<PathBuf> {
+    if Path::new(filename).is_absolute() {
+        return None;
+    }
+    let mut path = base_dir.to_path_buf();
+    for component in entry_components(filename) {
+        let exact = path.join(component);
+        if component == ".." || fs::symlink_metadata(&exact).is_ok() {
+            path = exact;
+            continue;
+        }
+        let wanted = component.to_lowercase();
+        // A manifest named without a directory has an empty base directory.
+        let dir = if path.as_os_str().is_empty() {
+            Path::new(".")
+        } else {
+            &path
+        };
+        let mut matches = fs::read_dir(dir).ok()?.filter_map(|entry| {
+            let name = entry.ok()?.file_name();
+            (name.to_str()?.to_lowercase() == wanted).then_some(name)
+        });
+        let name = matches.next()?;
+        if matches.next().is_some() {
+            return None;
+        }
+        path.push(name);
+    }
+    Some(path)
+}
+
+/// Find the file `filename` names below `base_dir` when it is stored under
+/// the other Unicode normalization form of the same name.
+///
+/// macOS writes names decomposed (NFD, `e` followed by a combining accent)
+/// while most other systems and most manifests use the composed form (NFC),
+/// so a name copied between them no longer matches byte for byte. Both forms
+/// are tried; `None` when neither differs from `filename` or exists.
+pub fn find_entry_normalized(base_dir: &Path, filename: &str) -> Option<PathBuf> {
+    use unicode_normalization::UnicodeNormalization;
+
+    [filename.nfc().collect::<String>(), filename.nfd().collect()]
+        .into_iter()
+        .filter(|form| form != filename)
+        .map(|form| resolve_entry_path(base_dir, &form))
+        .find(|path| fs::symlink_metadata(path).is_ok())
+}
+
+/// Check a single file against an expected CRC32.
+///
+/// This is the check [`verify_entries_with`] makes for each manifest entry,
+/// without the size comparison and the fallback lookups; the result's
+/// filename is `path` as displayed.
+pub fn verify_one(path: &Path, expected: u32) -> VerifyResult {
+    let checked = check_file(path, None, ReadControl::default(), &mut 0);
+    classify_checked(path.display().to_string(), expected, checked)
+}
+
+/// Like [`verify_one`], reading under `read`; `None` when it was cancelled
+/// before the file was checked.
+pub fn verify_one_with(path: &Path, expected: u32, read: ReadControl) -> Option<VerifyResult> {
+    let checked = check_file(path, None, read, &mut 0);
+    if matches!(&checked, Err(e) if is_cancelled(e)) {
+        return None;
+    }
+    Some(classify_checked(
+        path.display().to_string(),
+        expected,
+        checked,
+    ))
+}
+
+/// Verify every entry of the `.sfv` manifest at `sfv_path`.
+///
+/// Filenames are resolved relative to the directory containing the manifest
+/// with [`resolve_entry_path`].
+/// Only failures to read or parse the manifest itself are returned as `Err`;
+/// per-file problems are reported in the returned results.
+pub fn verify_sfv(sfv_path: &Path) -> io::Result<Vec<VerifyResult>> {
+    verify_sfv_with_base(sfv_path, None)
+}
+
+/// Like [`verify_sfv`], resolving filenames against `base_dir` instead of the
+/// manifest's directory when it is given, so a manifest that was moved, or a
+/// copy of the files elsewhere, can be checked.
+pub fn verify_sfv_with_base(
+    sfv_path: &Path,
+    base_dir: Option<&Path>,
+) -> io::Result<Vec<VerifyResult>> {
+    let entries = read_sfv(sfv_path)?;
+    let base_dir = base_dir.unwrap_or_else(|| sfv_path.parent().unwrap_or_else(|| Path::new("")));
+    Ok(verify_entries(base_dir, entries).collect())
+}
+
+/// Find the one `.sfv` manifest directly inside `dir`.
+///
+/// Fails with [`io::ErrorKind::NotFound`] when there is none and with
+/// [`io::ErrorKind::InvalidInput`], naming them, when there are several.
+pub fn find_sfv(dir: &Path) -> io::Result<PathBuf> {
+    let mut found = Vec::new();
+    for entry in fs::read_dir(dir)? {
+        let entry = entry?;
+        let path = entry.path();
+        let is_sfv = path
+            .extension()
+            .is_some_and(|ext| ext.eq_ignore_ascii_case("sfv"));
+        if is_sfv && entry.file_type()?.is_file() {
+            found.push(path);
+        }
+    }
+    found.sort();
+
+    match found.len() {
+        0 => Err(io::Error::new(
+            io::ErrorKind::NotFound,
+            format!("no .sfv file in {}", dir.display()),
+        )),
+        1 => Ok(found.remove(0)),
+        _ => {
+            let names: Vec<_> = found
+                .iter()
+                .filter_map(|path| path.file_name())
+                .map(|name| name.to_string_lossy())
+                .collect();
+            Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!(
+                    "more than one .sfv file in {}: {}",
+                    dir.display(),
+                    names.join(", ")
+                ),
+            ))
+        }
+    }
+}
+
+/// Verify the one `.sfv` manifest in `dir`, as found by [`find_sfv`].
+pub fn find_and_verify(dir: &Path) -> io::Result<Vec<VerifyResult>> {
+    verify_sfv(&find_sfv(dir)?)
+}
+
+/// Lazily verify already-parsed manifest entries, resolving their filenames
+/// against `base_dir`. Each file is hashed when its result is requested, so
+/// callers can report progress as they go. An entry with a recorded
+/// [`size`](SfvEntry::size) is compared by size first, and only hashed when
+/// the sizes agree.
+pub fn verify_entries(
+    base_dir: &Path,
+    entries: Vec<SfvEntry>,
+) -> impl Iterator<Item = VerifyResult> + '_ {
+    verify_entries_with(base_dir, entries, VerifyOptions::default()).map(|entry| entry.result)
+}
+
+/// Like [`verify_entries`], with the fallbacks enabled in `options`.
+pub fn verify_entries_with<'a>(
+    base_dir: &'a Path,
+    entries: Vec<SfvEntry>,
+    options: VerifyOptions<'a>,
+) -> impl Iterator<Item = VerifiedEntry> + 'a {
+    entries.into_iter().map_while(move |entry| {
+        if options.read.is_cancelled() {
+            return None;
+        }
+        if !options.allow_escape {
+            if let Err(reason) = safe_entry_path(base_dir, &entry.filename) {
+                log::debug!("{}: rejected: {}", entry.filename, reason);
+                return Some(VerifiedEntry {
+                    expected: entry.crc,
+                    result: VerifyResult::Rejected {
+                        filename: entry.filename,
+                        reason,
+                    },
+                    found_as: None,
+                    bytes: 0,
+                });
+            }
+        }
+        let path = resolve_entry_path(base_dir, &entry.filename);
+        log::debug!("{}: resolved to {}", entry.filename, path.display());
+        let mut found_as = None;
+        let mut bytes = 0;
+        let mut checked = check_file(&path, entry.size, options.read, &mut bytes);
+        let not_found = |checked: &io::Result<Checked>| {
+            matches!(checked, Err(e) if e.kind() == io::ErrorKind::NotFound)
+        };
+        if not_found(&checked) {
+            if let Some(found) = find_entry_normalized(base_dir, &entry.filename) {
+                log::debug!(
+                    "{}: found under another Unicode normalization as {}",
+                    entry.filename,
+                    found.display()
+                );
+                checked = check_file(&found, entry.size, options.read, &mut bytes);
+            }
+        }
+        if not_found(&checked) && options.ignore_case {
+            let found = find_entry_ignore_case(base_dir, &entry.filename);
+            log::debug!("{}: case-insensitive lookup found {:?}", entry.filename, found);
+            if let Some(found) = found {
+                checked = check_file(&found, entry.size, options.read, &mut bytes);
+                found_as = Some(found);
+            }
+        }
+        if not_found(&checked) && options.split_parts {
+            log::debug!("{}: trying numbered parts", entry.filename);
+            checked = check_split(&path, entry.size, options.read, &mut bytes);
+        }
+        if matches!(&checked, Err(e) if is_cancelled(e)) {
+            log::debug!("{}: cancelled", entry.filename);
+            return None;
+        }
+        Some(VerifiedEntry {
+            result: classify_checked(entry.filename, entry.crc, checked),
+            expected: entry.crc,
+            found_as,
+            bytes,
+        })
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::TempDir;
+
+    #[test]
+    fn test_verify_sfv_classifies_entries() {
+        let dir = TempDir::new("verify");
+        dir.write("good.txt", b"123456789");
+        dir.write("bad.txt", b"Hello, World!");
+        let sfv = dir.write(
+            "release.sfv",
+            b"; test manifest\ngood.txt CBF43926\nbad.txt CBF43926\ngone.txt 00000000\n",
+        );
+
+        let results = verify_sfv(&sfv).unwrap();
+        assert_eq!(
+            results,
+            vec![
+                VerifyResult::Ok {
+                    filename: "good.txt".to_string(),
+                    crc: 0xCBF43926,
+                },
+                VerifyResult::Mismatch {
+                    filename: "bad.txt".to_string(),
+                    expected: 0xCBF43926,
+                    actual: 0xEC4AC3D0,
+                },
+                VerifyResult::Missing {
+                    filename: "gone.txt".to_string(),
+                },
+            ]
+        );
+        assert_eq!(
+            VerifyCounts::from_results(&results),
+            VerifyCounts {
+                ok: 1,
+                failed: 1,
+                missing: 1,
+            }
+        );
+    }
+
+    #[test]
+    fn test_verify_one() {
+        let dir = TempDir::new("verify_one");
+        let path = dir.write("good.txt", b"123456789");
+        let filename = path.display().to_string();
+        assert_eq!(
+            verify_one(&path, 0xCBF43926),
+            VerifyResult::Ok {
+                filename: filename.clone(),
+                crc: 0xCBF43926,
+            }
+        );
+        assert_eq!(
+            verify_one(&path, 0),
+            VerifyResult::Mismatch {
+                filename,
+                expected: 0,
+                actual: 0xCBF43926,
+            }
+        );
+        assert!(matches!(
+            verify_one(&dir.path().join("gone.txt"), 0),
+            VerifyResult::Missing { .. }
+        ));
+        assert!(matches!(
+            verify_one(dir.path(), 0),
+            VerifyResult::Error { .. }
+        ));
+    }
+
+    #[test]
+    fn test_verify_stops_when_cancelled() {
+        use std::sync::atomic::{AtomicBool, Ordering};
+
+        let dir = TempDir::new("verify_cancel");
+        let path = dir.write("a.bin", b"123456789");
+        dir.write("b.bin", b"123456789");
+        let entries = crate::parse_sfv("a.bin CBF43926\nb.bin CBF43926\n").unwrap();
+        let flag = AtomicBool::new(false);
+        let read = ReadControl {
+            cancel: Some(&flag),
+            ..Default::default()
+        };
+        let options = VerifyOptions {
+            read,
+            ..Default::default()
+        };
+
+        let mut results = verify_entries_with(dir.path(), entries, options);
+        assert!(results.next().unwrap().result.is_ok());
+        flag.store(true, Ordering::Relaxed);
+        assert!(results.next().is_none());
+        assert_eq!(verify_one_with(&path, 0xCBF43926, read), None);
+    }
+
+    #[test]
+    fn test_resolve_entry_path_normalizes_separators() {
+        let base = Path::new("base");
+        let expected = base.join("sub").join("file.bin");
+        assert_eq!(resolve_entry_path(base, "sub\\file.bin"), expected);
+        assert_eq!(resolve_entry_path(base, "sub/file.bin"), expected);
+        assert_eq!(resolve_entry_path(base, ".\\sub\\\\file.bin"), expected);
+        assert_eq!(resolve_entry_path(base, "./sub/./file.bin"), expected);
+    }
+
+    #[test]
+    fn test_verify_sfv_with_base_overrides_manifest_dir() {
+        let dir = TempDir::new("verify_base_dir");
+        let sfv = dir.write("manifests/release.sfv", b"sub/a.bin CBF43926\r\n");
+        dir.write("copy/sub/a.bin", b"123456789");
+
+        assert!(!verify_sfv(&sfv).unwrap()[0].is_ok());
+        assert_eq!(
+            verify_sfv_with_base(&sfv, None).unwrap(),
+            verify_sfv(&sfv).unwrap()
+        );
+        let results = verify_sfv_with_base(&sfv, Some(&dir.path().join("copy"))).unwrap();
+        assert!(results.iter().all(VerifyResult::is_ok), "{results:?}");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_verify_sfv_windows_separators_on_unix() {
+        let dir = TempDir::new("verify_backslash");
+        dir.write("sub/deeper/a.bin", b"123456789");
+        let sfv = dir.write(
+            "release.sfv",
+            b"sub\\deeper\\a.bin CBF43926\r\n.\\sub\\deeper\\a.bin CBF43926\r\n",
+        );
+        let results = verify_sfv(&sfv).unwrap();
+        assert!(results.iter().all(VerifyResult::is_ok), "{results:?}");
+    }
+
+    #[cfg(windows)]
+    #[test]
+    fn test_verify_sfv_unix_separators_on_windows() {
+        let dir = TempDir::new("verify_slash");
+        dir.write("sub\\deeper\\a.bin", b"123456789");
+        let sfv = dir.write(
+            "release.sfv",
+            b"sub/deeper/a.bin CBF43926\n./sub/deeper/a.bin CBF43926\n",
+        );
+        let results = verify_sfv(&sfv).unwrap();
+        assert!(results.iter().all(VerifyResult::is_ok), "{results:?}");
+    }
+
+    #[test]
+    fn test_find_and_verify() {
+        let dir = TempDir::new("find_sfv");
+        dir.write("good.txt", b"123456789");
+        dir.write("nested/other.sfv", b"good.txt 00000000\n");
+        let err = find_and_verify(dir.path()).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::NotFound);
+
+        let sfv = dir.write("Release.SFV", b"good.txt CBF43926\n");
+        assert_eq!(find_sfv(dir.path()).unwrap(), sfv);
+        let results = find_and_verify(dir.path()).unwrap();
+        assert!(results.len() == 1 && results[0].is_ok(), "{results:?}");
+
+        dir.write("second.sfv", b"");
+        let err = find_and_verify(dir.path()).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+        assert!(err.to_string().contains("Release.SFV, second.sfv"), "{err}");
+    }
+
+    #[test]
+    fn test_verify_entries_with_split_parts() {
+        let dir = TempDir::new("verify_split");
+        dir.write("whole.bin", b"123456789");
+        dir.write("joined.bin.001", b"1234");
+        dir.write("joined.bin.002", b"56789");
+        dir.write("gap.bin.001", b"1234");
+        dir.write("gap.bin.003", b"89");
+        let entries = crate::parse_sfv(
+            "whole.bin CBF43926\njoined.bin CBF43926\ngap.bin CBF43926\ngone.bin CBF43926\n",
+        )
+        .unwrap();
+
+        let plain: Vec<_> = verify_entries(dir.path(), entries.clone()).collect();
+        assert!(plain[0].is_ok());
+        assert!(matches!(plain[1], VerifyResult::Missing { .. }));
+
+        let options = VerifyOptions {
+            split_parts: true,
+            ..Default::default()
+        };
+        let results: Vec<_> = verify_entries_with(dir.path(), entries, options)
+            .map(|entry| entry.result)
+            .collect();
+        assert!(results[0].is_ok() && results[1].is_ok(), "{results:?}");
+        assert!(
+            matches!(&results[2], VerifyResult::Error { message, .. }
+                if message.contains("gap.bin.002 is missing")),
+            "{results:?}"
+        );
+        assert!(matches!(results[3], VerifyResult::Missing { .. }));
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_verify_entries_ignore_case() {
+        let dir = TempDir::new("verify_case");
+        dir.write("Season 1/movie.mkv", b"123456789");
+        dir.write("twins/a.bin", b"123456789");
+        dir.write("twins/A.BIN", b"123456789");
+        let entries = crate::parse_sfv(
+            "season 1\\Movie.MKV CBF43926\ntwins/a.Bin CBF43926\nnone.bin CBF43926\n",
+        )
+        .unwrap();
+
+        let plain: Vec<_> = verify_entries(dir.path(), entries.clone()).collect();
+        assert!(matches!(plain[0], VerifyResult::Missing { .. }));
+
+        let options = VerifyOptions {
+            ignore_case: true,
+            ..Default::default()
+        };
+        let results: Vec<_> = verify_entries_with(dir.path(), entries, options).collect();
+        assert!(results[0].result.is_ok(), "{results:?}");
+        assert_eq!(
+            results[0].found_as,
+            Some(dir.path().join("Season 1").join("movie.mkv"))
+        );
+        assert_eq!(results[0].result.filename(), "season 1\\Movie.MKV");
+        assert!(results[1].found_as.is_none());
+        assert!(matches!(results[2].result, VerifyResult::Missing { .. }));
+    }
+
+    #[test]
+    fn test_verify_entries_rejects_escaping_paths() {
+        let root = TempDir::new("verify_escape");
+        root.write("secret.txt", b"123456789");
+        let release = root.path().join("release");
+        fs::create_dir(&release).unwrap();
+        root.write("release/sub/a.txt", b"123456789");
+        let secret = root.path().join("secret.txt");
+        let text = format!(
+            "sub/a.txt CBF43926\nsub/../sub/a.txt CBF43926\n../secret.txt CBF43926\n\
+             sub\\..\\..\\secret.txt CBF43926\n{} CBF43926\n{} CBF43926\n",
+            secret.display(),
+            release.join("sub").join("a.txt").display()
+        );
+        let entries = crate::parse_sfv(&text).unwrap();
+
+        let results: Vec<_> = verify_entries(&release, entries.clone()).collect();
+        let rejected: Vec<_> = results
+            .iter()
+            .map(|result| matches!(result, VerifyResult::Rejected { .. }))
+            .collect();
+        assert_eq!(
+            rejected,
+            [false, false, true, true, true, false],
+            "{results:?}"
+        );
+        assert!(results[0].is_ok() && results[1].is_ok() && results[5].is_ok());
+        assert_eq!(VerifyCounts::from_results(&results).failed, 3);
+
+        let options = VerifyOptions {
+            allow_escape: true,
+            ..Default::default()
+        };
+        let results: Vec<_> = verify_entries_with(&release, entries, options).collect();
+        assert!(
+            results.iter().all(|entry| entry.result.is_ok()),
+            "{results:?}"
+        );
+    }
+
+    #[test]
+    fn test_verify_entries_unicode_normalization() {
+        let dir = TempDir::new("verify_unicode");
+        // "café" decomposed on disk, as macOS stores it, and "naïve" composed.
+        dir.write("cafe\u{301}.txt", b"123456789");
+        dir.write("na\u{ef}ve.txt", b"123456789");
+        let entries =
+            crate::parse_sfv("caf\u{e9}.txt CBF43926\nnai\u{308}ve.txt CBF43926\n").unwrap();
+
+        let results: Vec<_> = verify_entries(dir.path(), entries).collect();
+        assert!(results.iter().all(VerifyResult::is_ok), "{results:?}");
+        assert_eq!(results[0].filename(), "caf\u{e9}.txt");
+        assert!(find_entry_normalized(dir.path(), "plain.txt").is_none());
+    }
+
+    #[test]
+    fn test_verify_sfv_checks_recorded_sizes() {
+        let dir = TempDir::new("verify_sizes");
+        dir.write("good.txt", b"123456789");
+        dir.write("short.txt", b"12345678");
+        dir.write("joined.bin.001", b"1234");
+        dir.write("joined.bin.002", b"56789");
+        let entries = crate::parse_sfv(
+            "; size 9\ngood.txt CBF43926\n; size 9\nshort.txt CBF43926\n\
+             ; size 10\njoined.bin CBF43926\n",
+        )
+        .unwrap();
+        let options = VerifyOptions {
+            split_parts: true,
+            ..Default::default()
+        };
+        let results: Vec<_> = verify_entries_with(dir.path(), entries, options).collect();
+        assert!(results[0].result.is_ok());
+        assert_eq!(
+            results[1].result,
+            VerifyResult::SizeMismatch {
+                filename: "short.txt".to_string(),
+                expected: 9,
+                actual: 8,
+            }
+        );
+        assert_eq!(results[1].bytes, 0);
+        assert!(matches!(
+            results[2].result,
+            VerifyResult::SizeMismatch { actual: 9, .. }
+        ));
+    }
+
+    #[test]
+    fn test_verify_sfv_missing_manifest() {
+        let dir = TempDir::new("verify_missing");
+        assert!(verify_sfv(&dir.path().join("nope.sfv")).is_err());
+    }
+}